@@ -34,4 +34,140 @@ pub enum PaymentError {
     RefundNotAuthorized,
     #[msg("Missing allowance accounts for Inco decryption access.")]
     MissingAllowanceAccounts,
+    #[msg("Refund amount must be greater than zero.")]
+    InvalidRefundAmount,
+    #[msg("Refund exceeds the remaining refundable amount.")]
+    RefundExceedsRemaining,
+    #[msg("The provided settlement wallet is invalid.")]
+    InvalidSettlementWallet,
+    #[msg("Merchant has been suspended by the platform.")]
+    MerchantSuspended,
+    #[msg("The provided merchant fee recipient is invalid.")]
+    InvalidFeeRecipient,
+    #[msg("Merchant fee token account is required when the merchant charges a fee.")]
+    MissingMerchantFeeAccount,
+    #[msg("The provided authority is invalid.")]
+    InvalidAuthority,
+    #[msg("No authority transfer is pending.")]
+    NoPendingAuthority,
+    #[msg("This mint is not accepted for payments.")]
+    MintNotAccepted,
+    #[msg("Treasury account does not match the accepted mint.")]
+    InvalidTreasury,
+    #[msg("Destination token account requires a memo; pass the memo program.")]
+    MemoProgramRequired,
+    #[msg("Mint has extensions that are not allowed unless whitelisted.")]
+    RestrictedMintExtension,
+    #[msg("The provided plan ID is invalid.")]
+    InvalidPlanId,
+    #[msg("Subscription interval and trial period must be positive.")]
+    InvalidSubscriptionInterval,
+    #[msg("Subscription plan is not accepting new subscribers.")]
+    SubscriptionPlanInactive,
+    #[msg("Delegated amount does not cover a billing cycle.")]
+    InsufficientDelegation,
+    #[msg("Subscription is not active.")]
+    SubscriptionNotActive,
+    #[msg("Subscription is not paused.")]
+    SubscriptionNotPaused,
+    #[msg("Subscription charge is not due yet.")]
+    SubscriptionNotDue,
+    #[msg("Allowance limit, period or expiry is invalid.")]
+    InvalidAllowance,
+    #[msg("Spending allowance has expired.")]
+    AllowanceExpired,
+    #[msg("Charge exceeds the remaining allowance for this period.")]
+    AllowanceExceeded,
+    #[msg("Delivery deadline must be in the future.")]
+    InvalidDeliveryDeadline,
+    #[msg("Payment is not held in escrow.")]
+    PaymentNotEscrowed,
+    #[msg("Delivery deadline has passed.")]
+    DeliveryDeadlinePassed,
+    #[msg("Delivery deadline has not been reached.")]
+    DeliveryDeadlineNotReached,
+    #[msg("Delivery has already been confirmed.")]
+    DeliveryAlreadyConfirmed,
+    #[msg("Escrow can only be released by the customer, or after a confirmed delivery's deadline.")]
+    EscrowNotReleasable,
+    #[msg("Escrow is frozen by an open dispute.")]
+    EscrowDisputed,
+    #[msg("Dispute window has closed for this payment.")]
+    DisputeWindowClosed,
+    #[msg("Dispute is not open.")]
+    DisputeNotOpen,
+    #[msg("Dispute window must not be negative.")]
+    InvalidDisputeWindow,
+    #[msg("Missing or invalid merchant signature.")]
+    InvalidSignature,
+    #[msg("Price quote has expired.")]
+    QuoteExpired,
+    #[msg("Payment intent has expired.")]
+    PaymentIntentExpired,
+    #[msg("Invoice ID must be 1-32 bytes.")]
+    InvalidInvoiceId,
+    #[msg("Invoice due date must be in the future.")]
+    InvalidDueDate,
+    #[msg("Invoice has already been paid in full.")]
+    InvoiceAlreadyPaid,
+    #[msg("Payer is not allowed to pay this invoice.")]
+    InvoicePayerNotAllowed,
+    #[msg("Payment must be positive and no more than the invoice balance.")]
+    InvalidInvoicePayment,
+    #[msg("Invoice is not overdue.")]
+    InvoiceNotOverdue,
+    #[msg("Split needs 1-8 distinct recipients with non-zero shares summing to 10,000 bps.")]
+    InvalidSplitConfig,
+    #[msg("Recipient token accounts do not match the merchant's split config.")]
+    InvalidSplitRecipient,
+    #[msg("Referral share cannot exceed 10,000 bps of the platform fee.")]
+    InvalidReferralShare,
+    #[msg("Referrer account is invalid, inactive or does not match the merchant's referrer.")]
+    InvalidReferrer,
+    #[msg("This merchant's referrer must be passed with the payment.")]
+    ReferrerRequired,
+    #[msg("Referral vault is required when a referral fee is due.")]
+    MissingReferralVault,
+    #[msg("Merchant already has a referrer.")]
+    ReferrerAlreadySet,
+    #[msg("Fee tiers must be at most 8, strictly ascending by volume, with min fee not above max fee.")]
+    InvalidFeeSchedule,
+    #[msg("Payment amount is above the maximum allowed.")]
+    PaymentAboveMaximum,
+    #[msg("Payment would exceed the customer's daily spend limit.")]
+    CustomerDailyLimitExceeded,
+    #[msg("Payment would exceed the merchant's daily intake limit.")]
+    MerchantDailyLimitExceeded,
+    #[msg("This merchant ID is reserved.")]
+    MerchantIdReserved,
+    #[msg("An approved merchant application from this signer is required.")]
+    MerchantApplicationRequired,
+    #[msg("The merchant token account is neither the settlement wallet's ATA nor its registered settlement account.")]
+    InvalidSettlementToken,
+    #[msg("Hold period must be between 0 and 30 days.")]
+    InvalidHoldPeriod,
+    #[msg("This merchant settles into its merchant vault, which must be passed.")]
+    MissingMerchantVault,
+    #[msg("Split payments cannot be held; split recipients are paid directly.")]
+    HeldSettlementUnsupported,
+    #[msg("Too many pending tranches.")]
+    TooManyTranches,
+    #[msg("No balance available to withdraw.")]
+    NoBalanceToWithdraw,
+    #[msg("Reserve must be at most 10000 bps with a period between 1 second and 180 days.")]
+    InvalidReserveConfig,
+    #[msg("This merchant holds a reserve, whose account must be passed.")]
+    MissingMerchantReserve,
+    #[msg("No reserve tranche has matured yet.")]
+    NothingToRelease,
+    #[msg("Account is not a program account of the type being migrated.")]
+    InvalidMigrationAccount,
+    #[msg("This payment was applied to an invoice, which must be passed.")]
+    MissingInvoice,
+    #[msg("Dispute is still within its resolution window.")]
+    DisputeResolutionPending,
+    #[msg("Changing the settlement wallet requires the wallet, an accepted mint and the wallet's token account.")]
+    MissingSettlementAccounts,
+    #[msg("Charge exceeds the delegation approved through this allowance.")]
+    AllowanceDelegationExceeded,
 }
\ No newline at end of file
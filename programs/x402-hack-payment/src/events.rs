@@ -0,0 +1,527 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{
+    DisputeStatus, FeeTier, InvoiceStatus, Payment, PaymentLimits, SplitRecipient, SplitShare, SubscriptionStatus,
+};
+
+#[event]
+pub struct PlatformConfigured {
+    pub platform_config: Pubkey,
+    pub authority: Pubkey,
+    pub treasury: Pubkey,
+    pub usdc_mint: Pubkey,
+    pub fee_bps: u64,
+    pub min_payment_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PlatformConfigUpdated {
+    pub platform_config: Pubkey,
+    pub authority: Pubkey,
+    pub fee_bps: u64,
+    pub min_payment_amount: u64,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AcceptedMintUpdated {
+    pub accepted_mint: Pubkey,
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub min_payment_amount: u64,
+    pub is_enabled: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantInitialized {
+    pub merchant: Pubkey,
+    pub merchant_id: String,
+    pub authority: Pubkey,
+    pub settlement_wallet: Pubkey,
+    pub fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantUpdated {
+    pub merchant: Pubkey,
+    pub settlement_wallet: Pubkey,
+    pub fee_bps: u16,
+    pub fee_recipient: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantSigningKeyUpdated {
+    pub merchant: Pubkey,
+    pub signing_key: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantSettlementTokenUpdated {
+    pub merchant: Pubkey,
+    pub settlement_token: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantStatusChanged {
+    pub merchant: Pubkey,
+    pub is_active: bool,
+    pub suspended: bool,
+    pub changed_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantAuthorityProposed {
+    pub merchant: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantAuthorityTransferred {
+    pub merchant: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentProcessed {
+    pub payment: Pubkey,
+    pub payment_id: String,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub merchant_fee_amount: u64,
+    pub merchant_amount: u64,
+    pub transfer_fee_amount: u64,
+    pub net_amount: u64,
+    pub referrer: Option<Pubkey>,
+    pub referral_fee_amount: u64,
+    pub reserve_amount: u64,
+    pub timestamp: i64,
+}
+
+impl PaymentProcessed {
+    pub fn new(payment: Pubkey, record: &Payment) -> Self {
+        Self {
+            payment,
+            payment_id: record.payment_id.clone(),
+            customer: record.customer,
+            merchant: record.merchant,
+            mint: record.mint,
+            amount: record.amount,
+            fee_amount: record.fee_amount,
+            merchant_fee_amount: record.merchant_fee_amount,
+            merchant_amount: record.merchant_amount,
+            transfer_fee_amount: record.transfer_fee_amount,
+            net_amount: record.net_amount,
+            referrer: record.referrer,
+            referral_fee_amount: record.referral_fee_amount,
+            reserve_amount: record.reserve_amount,
+            timestamp: record.created_at,
+        }
+    }
+}
+
+#[event]
+pub struct PaymentRefunded {
+    pub payment: Pubkey,
+    pub payment_id: String,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    /// Amount returned to the customer by this refund
+    pub amount: u64,
+    /// Returned from the platform treasury
+    pub fee_amount: u64,
+    /// Returned by the merchant, including its share of any referral and merchant-side fees
+    pub merchant_amount: u64,
+    /// Cumulative amount refunded on the payment, including this refund
+    pub refunded_amount: u64,
+    /// Token-2022 transfer fees withheld from this refund
+    pub transfer_fee_amount: u64,
+    /// What the customer received from this refund, `amount` less `transfer_fee_amount`
+    pub net_amount: u64,
+    pub fully_refunded: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeesClaimed {
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub treasury: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    pub platform_config: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferCancelled {
+    pub platform_config: Pubkey,
+    pub authority: Pubkey,
+    pub cancelled_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub platform_config: Pubkey,
+    pub old_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PrivateReceiptIssued {
+    pub receipt: Pubkey,
+    pub payment_id: String,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub encrypted_amount_handle: u128,
+    pub access_granted: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionPlanUpdated {
+    pub plan: Pubkey,
+    pub merchant: Pubkey,
+    pub plan_id: String,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub interval: i64,
+    pub trial_period: i64,
+    pub max_cycles: u32,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionCreated {
+    pub subscription: Pubkey,
+    pub plan: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub delegated_amount: u64,
+    pub next_charge_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionCharged {
+    pub subscription: Pubkey,
+    pub payment: Pubkey,
+    pub cycle: u32,
+    pub amount: u64,
+    pub next_charge_at: i64,
+    pub status: SubscriptionStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SubscriptionStatusChanged {
+    pub subscription: Pubkey,
+    pub status: SubscriptionStatus,
+    pub changed_by: Pubkey,
+    pub next_charge_at: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowanceUpdated {
+    pub allowance: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub limit_per_period: u64,
+    pub period_length: i64,
+    pub expires_at: Option<i64>,
+    /// Additional amount approved to the payment delegate
+    pub delegated_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowanceRevoked {
+    pub allowance: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    /// Delegation taken back from the payment delegate
+    pub released_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AllowanceCharged {
+    pub allowance: Pubkey,
+    pub payment: Pubkey,
+    pub amount: u64,
+    pub spent_in_period: u64,
+    pub period_start: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowCreated {
+    pub payment: Pubkey,
+    pub escrow: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub delivery_deadline: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowDeliveryConfirmed {
+    pub payment: Pubkey,
+    pub merchant: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowReleased {
+    pub payment: Pubkey,
+    pub released_by: Pubkey,
+    pub merchant_amount: u64,
+    pub fee_amount: u64,
+    pub merchant_fee_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EscrowExpired {
+    pub payment: Pubkey,
+    pub customer: Pubkey,
+    pub refunded_amount: u64,
+    pub expired_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeConfigUpdated {
+    pub platform_config: Pubkey,
+    pub dispute_window: i64,
+    pub arbiter: Option<Pubkey>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeOpened {
+    pub dispute: Pubkey,
+    pub payment: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeEvidenceSubmitted {
+    pub dispute: Pubkey,
+    pub submitted_by: Pubkey,
+    pub evidence_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub dispute: Pubkey,
+    pub payment: Pubkey,
+    pub status: DisputeStatus,
+    pub refunded_amount: u64,
+    pub resolved_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ResourcePaid {
+    pub access_receipt: Pubkey,
+    pub merchant: Pubkey,
+    pub customer: Pubkey,
+    pub resource_hash: [u8; 32],
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub fee_amount: u64,
+    pub nonce: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InvoiceCreated {
+    pub invoice: Pubkey,
+    pub merchant: Pubkey,
+    pub invoice_id: String,
+    pub mint: Pubkey,
+    pub amount_due: u64,
+    pub due_date: i64,
+    pub payer: Option<Pubkey>,
+    pub memo_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InvoicePaymentApplied {
+    pub invoice: Pubkey,
+    pub payment: Pubkey,
+    pub payer: Pubkey,
+    pub amount: u64,
+    pub amount_paid: u64,
+    pub status: InvoiceStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct InvoiceStatusChanged {
+    pub invoice: Pubkey,
+    pub status: InvoiceStatus,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SplitConfigUpdated {
+    pub merchant: Pubkey,
+    pub split_config: Pubkey,
+    pub recipients: Vec<SplitRecipient>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentSplitRecorded {
+    pub payment: Pubkey,
+    pub payment_split: Pubkey,
+    pub shares: Vec<SplitShare>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferrerUpdated {
+    pub referrer: Pubkey,
+    pub wallet: Pubkey,
+    pub share_bps: u16,
+    pub is_active: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantReferrerSet {
+    pub merchant: Pubkey,
+    pub referrer: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReferralFeesClaimed {
+    pub referrer: Pubkey,
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeScheduleUpdated {
+    pub platform_config: Pubkey,
+    pub fee_tiers: Vec<FeeTier>,
+    pub fixed_fee: u64,
+    pub min_fee: u64,
+    pub max_fee: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PaymentLimitsUpdated {
+    /// `None` for the platform defaults
+    pub merchant: Option<Pubkey>,
+    /// `None` when a merchant override is removed
+    pub limits: Option<PaymentLimits>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantOnboardingUpdated {
+    pub platform_config: Pubkey,
+    pub require_merchant_approval: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantApplicationSubmitted {
+    pub application: Pubkey,
+    pub merchant_id: String,
+    pub applicant: Pubkey,
+    pub details_hash: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantApplicationReviewed {
+    pub application: Pubkey,
+    pub merchant_id: String,
+    pub applicant: Pubkey,
+    pub approved: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantIdReservationUpdated {
+    pub merchant_id: String,
+    pub holder: Option<Pubkey>,
+    /// `false` once the reservation is released
+    pub reserved: bool,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantHoldPeriodUpdated {
+    pub merchant: Pubkey,
+    pub hold_period: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantReserveUpdated {
+    pub merchant: Pubkey,
+    pub reserve_bps: u16,
+    pub reserve_period: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReserveReleased {
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Still reserved after the release
+    pub held: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct MerchantBalanceWithdrawn {
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Still on hold after the withdrawal
+    pub held: u64,
+    pub timestamp: i64,
+}
@@ -0,0 +1,225 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::{AcceptedMint, Platform};
+use crate::errors::PaymentError;
+use crate::events::AcceptedMintUpdated;
+use crate::utils::restricted_mint_extensions;
+
+#[derive(Accounts)]
+pub struct AddAcceptedMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AcceptedMint::INIT_SPACE,
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint,
+        token::authority = platform_config,
+        token::token_program = token_program,
+        seeds = [Platform::TREASURY_SEED, mint.key().as_ref()],
+        bump,
+    )]
+    pub treasury: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddAcceptedMint<'info> {
+    /// Registers `mint` with a dedicated treasury. Defaults the minimum to the platform minimum.
+    /// Mints with restricted Token-2022 extensions (e.g. permanent delegate) are rejected
+    /// unless `allow_restricted_extensions` is set.
+    pub fn add_accepted_mint(
+        &mut self,
+        min_payment_amount: Option<u64>,
+        allow_restricted_extensions: bool,
+        bump: u8,
+    ) -> Result<()> {
+        let min_payment_amount = min_payment_amount.unwrap_or(self.platform_config.min_payment_amount);
+        require!(min_payment_amount > 0, PaymentError::InvalidMinPaymentAmount);
+
+        let restricted = restricted_mint_extensions(&self.mint.to_account_info())?;
+        if !restricted.is_empty() {
+            msg!("Mint {} has restricted extensions: {:?}", self.mint.key(), restricted);
+            require!(allow_restricted_extensions, PaymentError::RestrictedMintExtension);
+        }
+
+        let accepted_mint = &mut self.accepted_mint;
+        accepted_mint.mint = self.mint.key();
+        accepted_mint.treasury = self.treasury.key();
+        accepted_mint.min_payment_amount = min_payment_amount;
+        accepted_mint.decimals = self.mint.decimals;
+        accepted_mint.is_enabled = true;
+        accepted_mint.restricted_extensions_allowed = allow_restricted_extensions;
+        accepted_mint.bump = bump;
+
+        msg!(
+            "Mint {} accepted with minimum {}, treasury {}",
+            accepted_mint.mint,
+            min_payment_amount,
+            accepted_mint.treasury
+        );
+
+        emit!(AcceptedMintUpdated {
+            accepted_mint: accepted_mint.key(),
+            mint: accepted_mint.mint,
+            treasury: accepted_mint.treasury,
+            min_payment_amount,
+            is_enabled: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Registers the platform's original USDC mint for a platform set up before mints were
+/// registered individually, keeping the original treasury so fees already collected there
+/// can still be claimed and refunded.
+#[derive(Accounts)]
+pub struct RegisterUsdcMint<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(address = platform_config.usdc_mint @ PaymentError::InvalidUsdcMint)]
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        address = platform_config.treasury @ PaymentError::InvalidTreasury,
+        token::mint = usdc_mint,
+        token::authority = platform_config,
+    )]
+    pub platform_treasury: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AcceptedMint::INIT_SPACE,
+        seeds = [AcceptedMint::SEED, usdc_mint.key().as_ref()],
+        bump,
+    )]
+    pub usdc_accepted_mint: Account<'info, AcceptedMint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterUsdcMint<'info> {
+    pub fn register_usdc_mint(&mut self, bump: u8) -> Result<()> {
+        let accepted_mint = &mut self.usdc_accepted_mint;
+        accepted_mint.mint = self.usdc_mint.key();
+        accepted_mint.treasury = self.platform_treasury.key();
+        accepted_mint.min_payment_amount = self.platform_config.min_payment_amount;
+        accepted_mint.decimals = self.usdc_mint.decimals;
+        accepted_mint.is_enabled = true;
+        accepted_mint.restricted_extensions_allowed = false;
+        accepted_mint.bump = bump;
+
+        msg!("USDC mint {} registered with treasury {}", accepted_mint.mint, accepted_mint.treasury);
+
+        emit!(AcceptedMintUpdated {
+            accepted_mint: accepted_mint.key(),
+            mint: accepted_mint.mint,
+            treasury: accepted_mint.treasury,
+            min_payment_amount: accepted_mint.min_payment_amount,
+            is_enabled: true,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateAcceptedMint<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        mut,
+        seeds = [AcceptedMint::SEED, accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+}
+
+impl<'info> UpdateAcceptedMint<'info> {
+    pub fn update_accepted_mint(&mut self, min_payment_amount: Option<u64>, is_enabled: Option<bool>) -> Result<()> {
+        let accepted_mint = &mut self.accepted_mint;
+
+        if let Some(min_payment_amount) = min_payment_amount {
+            require!(min_payment_amount > 0, PaymentError::InvalidMinPaymentAmount);
+            accepted_mint.min_payment_amount = min_payment_amount;
+            msg!("Mint {} minimum updated to {}", accepted_mint.mint, min_payment_amount);
+        }
+
+        if let Some(is_enabled) = is_enabled {
+            accepted_mint.is_enabled = is_enabled;
+            msg!("Mint {} {}", accepted_mint.mint, if is_enabled { "enabled" } else { "disabled" });
+        }
+
+        emit!(AcceptedMintUpdated {
+            accepted_mint: accepted_mint.key(),
+            mint: accepted_mint.mint,
+            treasury: accepted_mint.treasury,
+            min_payment_amount: accepted_mint.min_payment_amount,
+            is_enabled: accepted_mint.is_enabled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn add_handler(
+    ctx: Context<AddAcceptedMint>,
+    min_payment_amount: Option<u64>,
+    allow_restricted_extensions: bool,
+) -> Result<()> {
+    let bump = ctx.bumps.accepted_mint;
+    ctx.accounts.add_accepted_mint(min_payment_amount, allow_restricted_extensions, bump)
+}
+
+pub fn register_usdc_handler(ctx: Context<RegisterUsdcMint>) -> Result<()> {
+    let bump = ctx.bumps.usdc_accepted_mint;
+    ctx.accounts.register_usdc_mint(bump)
+}
+
+pub fn update_mint_handler(
+    ctx: Context<UpdateAcceptedMint>,
+    min_payment_amount: Option<u64>,
+    is_enabled: Option<bool>,
+) -> Result<()> {
+    ctx.accounts.update_accepted_mint(min_payment_amount, is_enabled)
+}
@@ -0,0 +1,490 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::memo::Memo;
+
+use crate::state::{
+    AcceptedMint, Customer, Delegation, Merchant, MerchantReserve, MerchantStats, MerchantVault, Payment, Platform, Referrer,
+    SpendingAllowance,
+};
+use crate::errors::PaymentError;
+use crate::events::{AllowanceCharged, AllowanceRevoked, AllowanceUpdated, PaymentProcessed};
+use crate::utils::{apply_referral, approve_delegate, reduce_delegate, settle_payment, FeeBreakdown, PaymentSettlement, TokenTransfer};
+
+#[derive(Accounts)]
+pub struct ApproveAllowance<'info> {
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    /// Re-approving updates the limits of an existing allowance
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = 8 + SpendingAllowance::INIT_SPACE,
+        seeds = [SpendingAllowance::SEED, customer.key().as_ref(), merchant_account.key().as_ref()],
+        bump,
+    )]
+    pub allowance: Account<'info, SpendingAllowance>,
+
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = 8 + Customer::INIT_SPACE,
+        seeds = [Customer::SEED, customer.key().as_ref()],
+        bump,
+    )]
+    pub customer_account: Account<'info, Customer>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = customer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = 8 + Delegation::INIT_SPACE,
+        seeds = [Delegation::SEED, customer_token.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: PDA approved as delegate; holds no data
+    #[account(
+        seeds = [Platform::DELEGATE_SEED],
+        bump,
+    )]
+    pub payment_delegate: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ApproveAllowance<'info> {
+    /// Sets the spending limit and approves the payment delegate for `delegate_amount` more
+    pub fn approve_allowance(
+        &mut self,
+        limit_per_period: u64,
+        period_length: i64,
+        expires_at: Option<i64>,
+        delegate_amount: u64,
+        bumps: &ApproveAllowanceBumps,
+    ) -> Result<()> {
+        require!(limit_per_period > 0 && period_length > 0, PaymentError::InvalidAllowance);
+        let now = Clock::get()?.unix_timestamp;
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > now, PaymentError::InvalidAllowance);
+        }
+
+        self.customer_account.init_if_new(self.customer.key(), now, bumps.customer_account);
+        self.delegation.init_if_new(self.customer_token.key(), bumps.delegation);
+
+        if delegate_amount > 0 {
+            approve_delegate(
+                &self.token_program,
+                &self.mint,
+                &self.customer_token,
+                &mut self.delegation,
+                self.payment_delegate.to_account_info(),
+                self.customer.to_account_info(),
+                delegate_amount,
+            )?;
+        }
+
+        let allowance = &mut self.allowance;
+        let is_new = allowance.customer == Pubkey::default();
+        allowance.customer = self.customer.key();
+        allowance.merchant = self.merchant_account.key();
+        allowance.mint = self.mint.key();
+        allowance.customer_token = self.customer_token.key();
+        allowance.limit_per_period = limit_per_period;
+        allowance.period_length = period_length;
+        allowance.expires_at = expires_at;
+        allowance.delegated_amount = allowance.delegated_amount
+            .checked_add(delegate_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        if is_new {
+            allowance.period_start = now;
+            allowance.spent_in_period = 0;
+            allowance.created_at = now;
+            allowance.bump = bumps.allowance;
+        }
+
+        msg!(
+            "Customer {} allows merchant {} up to {} every {}s",
+            allowance.customer,
+            self.merchant_account.merchant_id,
+            limit_per_period,
+            period_length
+        );
+
+        emit!(AllowanceUpdated {
+            allowance: allowance.key(),
+            customer: allowance.customer,
+            merchant: allowance.merchant,
+            mint: allowance.mint,
+            limit_per_period,
+            period_length,
+            expires_at,
+            delegated_amount: delegate_amount,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RevokeAllowance<'info> {
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = customer,
+        seeds = [SpendingAllowance::SEED, customer.key().as_ref(), allowance.merchant.as_ref()],
+        bump = allowance.bump,
+        has_one = customer @ PaymentError::Unauthorized,
+    )]
+    pub allowance: Account<'info, SpendingAllowance>,
+
+    #[account(
+        address = allowance.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        address = allowance.customer_token,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub customer_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [Delegation::SEED, customer_token.key().as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: PDA approved as delegate; holds no data
+    #[account(
+        seeds = [Platform::DELEGATE_SEED],
+        bump,
+    )]
+    pub payment_delegate: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> RevokeAllowance<'info> {
+    /// Closes the allowance and takes its uncharged delegation back from the payment delegate.
+    /// The approval is reset to what other allowances and subscriptions on the same token
+    /// account still need.
+    pub fn revoke_allowance(&mut self) -> Result<()> {
+        let released_amount = reduce_delegate(
+            &self.token_program,
+            &self.mint,
+            &self.customer_token,
+            &mut self.delegation,
+            self.payment_delegate.to_account_info(),
+            self.customer.to_account_info(),
+            self.allowance.delegated_amount,
+        )?;
+
+        msg!("Customer {} revoked allowance for merchant {}", self.customer.key(), self.allowance.merchant);
+
+        emit!(AllowanceRevoked {
+            allowance: self.allowance.key(),
+            customer: self.customer.key(),
+            merchant: self.allowance.merchant,
+            released_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(payment_id: String)]
+pub struct ChargeWithAllowance<'info> {
+    /// Merchant authority; pays rent for the payment record
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Box<Account<'info, Platform>>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    #[account(
+        mut,
+        seeds = [SpendingAllowance::SEED, allowance.customer.as_ref(), merchant_account.key().as_ref()],
+        bump = allowance.bump,
+    )]
+    pub allowance: Box<Account<'info, SpendingAllowance>>,
+
+    #[account(
+        mut,
+        seeds = [Customer::SEED, allowance.customer.as_ref()],
+        bump = customer_account.bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Payment::INIT_SPACE,
+        seeds = [Payment::SEED, merchant_account.key().as_ref(), payment_id.as_bytes()],
+        bump,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        address = allowance.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        address = allowance.customer_token,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [Delegation::SEED, customer_token.key().as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Box<Account<'info, Delegation>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the merchant share instead of `merchant_token` while the merchant has a hold period
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the merchant-side fee; required when `merchant_account.fee > 0`
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
+        token::authority = platform_config,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required when the merchant has a referrer; otherwise optionally credits this referrer
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Option<Box<Account<'info, Referrer>>>,
+
+    /// Referrer-owned token account for `mint`, required when a referral fee is due
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: PDA delegate that signs the pull from `customer_token`
+    #[account(
+        seeds = [Platform::DELEGATE_SEED],
+        bump,
+    )]
+    pub payment_delegate: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub memo_program: Option<Program<'info, Memo>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ChargeWithAllowance<'info> {
+    pub fn charge_with_allowance(
+        &mut self,
+        payment_id: String,
+        amount: u64,
+        bumps: &ChargeWithAllowanceBumps,
+    ) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        require!(amount >= self.accepted_mint.min_payment_amount, PaymentError::PaymentBelowMinimum);
+        require!(!payment_id.is_empty() && payment_id.len() <= 64, PaymentError::InvalidPaymentId);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.allowance.spend(amount, now)?;
+        // Only what this allowance approved may be pulled; the rest belongs to other allowances
+        // and subscriptions on the same token account
+        self.allowance.delegated_amount = self.allowance.delegated_amount
+            .checked_sub(amount)
+            .ok_or(PaymentError::AllowanceDelegationExceeded)?;
+        self.delegation.release(amount);
+
+        self.platform_config
+            .limits_for(&self.merchant_account)
+            .check(amount, &self.customer_account, &self.merchant_account, now)?;
+
+        let fees = apply_referral(
+            FeeBreakdown::calculate(&self.platform_config, &self.merchant_account, &self.merchant_stats, amount)?,
+            &self.merchant_account,
+            &self.mint.key(),
+            self.referrer.as_deref(),
+            self.referral_vault.as_deref(),
+        )?;
+        let seeds = &[Platform::DELEGATE_SEED, &[bumps.payment_delegate]];
+        let (fees, transfer_fee_amount) = settle_payment(
+            PaymentSettlement {
+                token_transfer: TokenTransfer {
+                    token_program: &self.token_program,
+                    mint: &self.mint,
+                    memo_program: self.memo_program.as_ref(),
+                },
+                from: self.customer_token.to_account_info(),
+                authority: self.payment_delegate.to_account_info(),
+                signer_seeds: &[&seeds[..]],
+                customer: Some(&mut self.customer_account),
+                merchant: &mut self.merchant_account,
+                merchant_stats: &mut self.merchant_stats,
+                merchant_token: Some(self.merchant_token.to_account_info()),
+                platform_treasury: self.platform_treasury.to_account_info(),
+                merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                merchant_vault: self.merchant_vault.as_deref_mut(),
+                merchant_vault_tokens: self.merchant_vault_tokens.as_deref_mut(),
+                merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+            },
+            fees,
+            &payment_id,
+        )?;
+
+        let mut payment = Payment::completed(
+            payment_id,
+            self.allowance.customer,
+            self.merchant_account.key(),
+            self.mint.key(),
+            &fees,
+            transfer_fee_amount,
+            bumps.payment_account,
+        )?;
+        if fees.referral_fee > 0 {
+            payment.referrer = self.referrer.as_ref().map(|referrer| referrer.key());
+        }
+        self.payment_account.set_inner(payment);
+
+        msg!(
+            "Merchant {} charged {} to customer {} ({} of {} used this period)",
+            self.merchant_account.merchant_id,
+            amount,
+            self.allowance.customer,
+            self.allowance.spent_in_period,
+            self.allowance.limit_per_period
+        );
+
+        emit!(PaymentProcessed::new(self.payment_account.key(), &self.payment_account));
+        emit!(AllowanceCharged {
+            allowance: self.allowance.key(),
+            payment: self.payment_account.key(),
+            amount,
+            spent_in_period: self.allowance.spent_in_period,
+            period_start: self.allowance.period_start,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn approve_handler(
+    ctx: Context<ApproveAllowance>,
+    limit_per_period: u64,
+    period_length: i64,
+    expires_at: Option<i64>,
+    delegate_amount: u64,
+) -> Result<()> {
+    ctx.accounts.approve_allowance(limit_per_period, period_length, expires_at, delegate_amount, &ctx.bumps)
+}
+
+pub fn revoke_handler(ctx: Context<RevokeAllowance>) -> Result<()> {
+    ctx.accounts.revoke_allowance()
+}
+
+pub fn charge_allowance_handler(ctx: Context<ChargeWithAllowance>, payment_id: String, amount: u64) -> Result<()> {
+    ctx.accounts.charge_with_allowance(payment_id, amount, &ctx.bumps)
+}
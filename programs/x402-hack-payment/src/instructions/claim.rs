@@ -1,8 +1,12 @@
 use anchor_lang::prelude::*;
 
 use crate::state::platform::Platform;
+use crate::state::AcceptedMint;
 use crate::errors::PaymentError;
-use anchor_spl::token::{Transfer, transfer};
+use crate::events::FeesClaimed;
+use crate::utils::TokenTransfer;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::memo::Memo;
 
 #[derive(Accounts)]
 pub struct ClaimPlatformFees<'info> {
@@ -16,52 +20,72 @@ pub struct ClaimPlatformFees<'info> {
     )]
     pub platform_config: Account<'info, Platform>,
 
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
     #[account(
         mut,
-        seeds = [Platform::TREASURY_SEED],
-        bump = platform_config.treasury_bump,
-        token::mint = usdc_mint,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
         token::authority = platform_config,
+        token::token_program = token_program,
     )]
-    pub platform_treasury_usdc: Account<'info, anchor_spl::token::TokenAccount>,
+    pub platform_treasury: InterfaceAccount<'info, TokenAccount>,
 
     #[account(
         mut,
-        associated_token::mint = usdc_mint,
+        associated_token::mint = mint,
         associated_token::authority = authority,
+        associated_token::token_program = token_program,
     )]
-    pub authority_usdc: Account<'info, anchor_spl::token::TokenAccount>,
+    pub authority_token: InterfaceAccount<'info, TokenAccount>,
 
-    pub usdc_mint: Account<'info, anchor_spl::token::Mint>,
+    pub mint: InterfaceAccount<'info, Mint>,
 
-    pub token_program: Program<'info, anchor_spl::token::Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, anchor_spl::associated_token::AssociatedToken>,
+    pub memo_program: Option<Program<'info, Memo>>,
 }
 
 impl<'info> ClaimPlatformFees<'info> {
     pub fn claim_platform_fees(&mut self) -> Result<()> {
-        let treasury_balance = self.platform_treasury_usdc.amount;
+        let treasury_balance = self.platform_treasury.amount;
         require!(treasury_balance > 0, PaymentError::NoFeesToClaim);
 
-        let cpi_accounts = Transfer {
-            from: self.platform_treasury_usdc.to_account_info(),
-            to: self.authority_usdc.to_account_info(),
-            authority: self.platform_config.to_account_info(),
-        };
-
         // Use platform_config seeds since it's the token authority
         let seeds = &[Platform::SEED, &[self.platform_config.bump]];
         let signer = &[&seeds[..]];
 
-        let cpi_program = self.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
-        transfer(cpi_ctx, treasury_balance)?;
-        msg!("Claimed {} USDC fees to {}", treasury_balance, self.authority.key());
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
+        };
+        token_transfer.transfer(
+            self.platform_treasury.to_account_info(),
+            self.authority_token.to_account_info(),
+            self.platform_config.to_account_info(),
+            signer,
+            treasury_balance,
+            "platform fee claim",
+        )?;
+        msg!("Claimed {} fees in mint {} to {}", treasury_balance, self.mint.key(), self.authority.key());
+
+        emit!(FeesClaimed {
+            authority: self.authority.key(),
+            mint: self.mint.key(),
+            treasury: self.platform_treasury.key(),
+            amount: treasury_balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
 }
 
-pub fn handler(ctx: Context<ClaimPlatformFees>) -> Result<()> {
+pub fn claim_fees_handler(ctx: Context<ClaimPlatformFees>) -> Result<()> {
     ctx.accounts.claim_platform_fees()
 }
\ No newline at end of file
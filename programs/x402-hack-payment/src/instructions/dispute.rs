@@ -0,0 +1,460 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::memo::Memo;
+
+use crate::state::{
+    AcceptedMint, Customer, Dispute, DisputeResolution, DisputeStatus, Escrow, Merchant, MerchantReserve, MerchantStats,
+    MerchantVault, Payment, PaymentStatus, Platform,
+};
+use crate::errors::PaymentError;
+use crate::events::{DisputeEvidenceSubmitted, DisputeOpened, DisputeResolved};
+use crate::utils::{check_referral_vault, settle_payment, PaymentSettlement, TokenTransfer};
+
+#[derive(Accounts)]
+pub struct OpenDispute<'info> {
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        constraint = payment_account.customer == customer.key() @ PaymentError::Unauthorized,
+        constraint = payment_account.status == PaymentStatus::Escrowed @ PaymentError::PaymentNotEscrowed,
+    )]
+    pub payment_account: Account<'info, Payment>,
+
+    #[account(
+        mut,
+        seeds = [Escrow::SEED, payment_account.key().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+
+    #[account(
+        init,
+        payer = customer,
+        space = 8 + Dispute::INIT_SPACE,
+        seeds = [Dispute::SEED, payment_account.key().as_ref()],
+        bump,
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenDispute<'info> {
+    /// Opens a dispute and freezes the payment's escrow until it is resolved or lapses.
+    /// Only escrowed payments can be disputed: a completed payment has already been settled to
+    /// the merchant, so it is refunded through `refund_payment` rather than frozen here, and no
+    /// held tranche or reserve ever carries a disputed payment.
+    pub fn open_dispute(&mut self, evidence_hash: [u8; 32], bumps: &OpenDisputeBumps) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let window = self.platform_config.dispute_window;
+        let closes_at = self.payment_account.created_at
+            .checked_add(window)
+            .ok_or(PaymentError::CalculationError)?;
+        require!(window > 0 && now <= closes_at, PaymentError::DisputeWindowClosed);
+
+        self.escrow.disputed = true;
+
+        self.dispute.set_inner(Dispute {
+            payment: self.payment_account.key(),
+            customer: self.customer.key(),
+            merchant: self.payment_account.merchant,
+            customer_evidence: evidence_hash,
+            merchant_evidence: None,
+            status: DisputeStatus::Open,
+            refunded_amount: 0,
+            opened_at: now,
+            resolved_at: None,
+            resolved_by: None,
+            bump: bumps.dispute,
+        });
+
+        msg!("Customer {} disputed payment {}", self.customer.key(), self.payment_account.payment_id);
+
+        emit!(DisputeOpened {
+            dispute: self.dispute.key(),
+            payment: self.payment_account.key(),
+            customer: self.customer.key(),
+            merchant: self.payment_account.merchant,
+            evidence_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SubmitDisputeEvidence<'info> {
+    /// The disputing customer or the merchant authority
+    pub submitter: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Dispute::SEED, dispute.payment.as_ref()],
+        bump = dispute.bump,
+        constraint = dispute.status == DisputeStatus::Open @ PaymentError::DisputeNotOpen,
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(
+        address = dispute.merchant,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+}
+
+impl<'info> SubmitDisputeEvidence<'info> {
+    /// Records the submitter's evidence hash, replacing any earlier submission
+    pub fn submit_dispute_evidence(&mut self, evidence_hash: [u8; 32]) -> Result<()> {
+        let submitter = self.submitter.key();
+        if submitter == self.dispute.customer {
+            self.dispute.customer_evidence = evidence_hash;
+        } else if submitter == self.merchant_account.authority {
+            self.dispute.merchant_evidence = Some(evidence_hash);
+        } else {
+            return err!(PaymentError::Unauthorized);
+        }
+
+        msg!("Evidence submitted by {} for dispute on payment {}", submitter, self.dispute.payment);
+
+        emit!(DisputeEvidenceSubmitted {
+            dispute: self.dispute.key(),
+            submitted_by: submitter,
+            evidence_hash,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    /// Platform authority or appointed arbiter
+    pub resolver: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.can_resolve_disputes(&resolver.key()) @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Box<Account<'info, Platform>>,
+
+    #[account(
+        mut,
+        seeds = [Dispute::SEED, payment_account.key().as_ref()],
+        bump = dispute.bump,
+        constraint = dispute.status == DisputeStatus::Open @ PaymentError::DisputeNotOpen,
+    )]
+    pub dispute: Box<Account<'info, Dispute>>,
+
+    #[account(
+        mut,
+        constraint = payment_account.status == PaymentStatus::Escrowed @ PaymentError::PaymentNotEscrowed,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        mut,
+        seeds = [Escrow::SEED, payment_account.key().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        mut,
+        address = escrow.vault,
+    )]
+    pub escrow_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [Customer::SEED, payment_account.customer.as_ref()],
+        bump = customer_account.bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    #[account(
+        mut,
+        address = payment_account.merchant @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    #[account(
+        address = payment_account.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payment_account.customer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the merchant share instead of `merchant_token` while the merchant has a hold period
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the merchant-side fee; required when the released share carries one
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Referrer-owned token account, required when the payment carries a referral fee
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Required when a destination token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+impl<'info> ResolveDispute<'info> {
+    pub fn resolve_dispute(&mut self, resolution: DisputeResolution) -> Result<()> {
+        let amount = self.payment_account.amount;
+        let (refund, status) = match resolution {
+            DisputeResolution::RefundFull => (amount, DisputeStatus::Refunded),
+            DisputeResolution::RefundPartial { amount: partial } => {
+                require!(partial > 0 && partial < amount, PaymentError::InvalidRefundAmount);
+                (partial, DisputeStatus::PartiallyRefunded)
+            }
+            DisputeResolution::Reject => (0, DisputeStatus::Rejected),
+        };
+
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
+        };
+        let payment_key = self.payment_account.key();
+        let seeds = &[Escrow::SEED, payment_key.as_ref(), &[self.escrow.bump]];
+        let signer_seeds = &[&seeds[..]];
+        let memo = self.payment_account.payment_id.clone();
+
+        // A full refund returns the whole vault, including any deposit transfer fee shortfall
+        let vault_balance = self.escrow_vault.amount;
+        let customer_amount = if refund == amount { vault_balance } else { refund };
+        let returned_fee = token_transfer.transfer(
+            self.escrow_vault.to_account_info(),
+            self.customer_token.to_account_info(),
+            self.escrow.to_account_info(),
+            signer_seeds,
+            customer_amount,
+            &memo,
+        )?;
+        let mut transfer_fee_amount = returned_fee;
+
+        if refund < amount {
+            let fees = self.payment_account.escrow_release(refund, vault_balance)?;
+            if fees.referral_fee > 0 {
+                let referrer = self.payment_account.referrer.ok_or(PaymentError::InvalidReferrer)?;
+                check_referral_vault(self.referral_vault.as_deref(), &referrer, &self.mint.key())?;
+            }
+            let (fees, settled_transfer_fee) = settle_payment(
+                PaymentSettlement {
+                    token_transfer: TokenTransfer {
+                        token_program: &self.token_program,
+                        mint: &self.mint,
+                        memo_program: self.memo_program.as_ref(),
+                    },
+                    from: self.escrow_vault.to_account_info(),
+                    authority: self.escrow.to_account_info(),
+                    signer_seeds,
+                    customer: None,
+                    merchant: &mut self.merchant_account,
+                    merchant_stats: &mut self.merchant_stats,
+                    merchant_token: Some(self.merchant_token.to_account_info()),
+                    platform_treasury: self.platform_treasury.to_account_info(),
+                    merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                    referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                    merchant_vault: self.merchant_vault.as_deref_mut(),
+                    merchant_vault_tokens: self.merchant_vault_tokens.as_deref_mut(),
+                    merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                    merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+                },
+                fees,
+                &memo,
+            )?;
+            transfer_fee_amount += settled_transfer_fee;
+            self.payment_account.reserve_amount = fees.reserve;
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let payment = &mut self.payment_account;
+        payment.transfer_fee_amount = payment.transfer_fee_amount
+            .checked_add(transfer_fee_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        payment.net_amount = payment.amount
+            .checked_sub(payment.transfer_fee_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        payment.status = match status {
+            DisputeStatus::Refunded => PaymentStatus::Refunded,
+            DisputeStatus::PartiallyRefunded => PaymentStatus::PartiallyRefunded,
+            _ => PaymentStatus::Released,
+        };
+        if refund > 0 {
+            payment.refunded_amount = refund;
+            payment.refunded_net_amount = customer_amount
+                .checked_sub(returned_fee)
+                .ok_or(PaymentError::CalculationError)?;
+            payment.refunded_at = Some(now);
+            // The customer's spend was recorded when the escrow was funded
+            self.customer_account.record_refund(refund)?;
+        }
+        self.escrow.closed_at = Some(now);
+
+        let dispute = &mut self.dispute;
+        dispute.status = status;
+        dispute.refunded_amount = refund;
+        dispute.resolved_at = Some(now);
+        dispute.resolved_by = Some(self.resolver.key());
+
+        msg!("Dispute on payment {} resolved as {:?}, {} refunded", payment.payment_id, status, refund);
+
+        emit!(DisputeResolved {
+            dispute: dispute.key(),
+            payment: payment.key(),
+            status,
+            refunded_amount: refund,
+            resolved_by: self.resolver.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct LapseDispute<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        mut,
+        seeds = [Dispute::SEED, dispute.payment.as_ref()],
+        bump = dispute.bump,
+        constraint = dispute.status == DisputeStatus::Open @ PaymentError::DisputeNotOpen,
+    )]
+    pub dispute: Account<'info, Dispute>,
+
+    #[account(
+        mut,
+        seeds = [Escrow::SEED, dispute.payment.as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> LapseDispute<'info> {
+    /// Permissionless; closes a dispute left unresolved for the platform's dispute window and
+    /// unfreezes its escrow, which then releases or expires under its own rules
+    pub fn lapse_dispute(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let lapses_at = self.dispute.opened_at
+            .checked_add(self.platform_config.dispute_window)
+            .ok_or(PaymentError::CalculationError)?;
+        require!(now > lapses_at, PaymentError::DisputeResolutionPending);
+
+        self.escrow.disputed = false;
+
+        let dispute = &mut self.dispute;
+        dispute.status = DisputeStatus::Lapsed;
+        dispute.resolved_at = Some(now);
+        dispute.resolved_by = Some(self.caller.key());
+
+        msg!("Dispute on payment {} lapsed unresolved", dispute.payment);
+
+        emit!(DisputeResolved {
+            dispute: dispute.key(),
+            payment: dispute.payment,
+            status: DisputeStatus::Lapsed,
+            refunded_amount: 0,
+            resolved_by: self.caller.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn open_dispute_handler(ctx: Context<OpenDispute>, evidence_hash: [u8; 32]) -> Result<()> {
+    ctx.accounts.open_dispute(evidence_hash, &ctx.bumps)
+}
+
+pub fn evidence_handler(ctx: Context<SubmitDisputeEvidence>, evidence_hash: [u8; 32]) -> Result<()> {
+    ctx.accounts.submit_dispute_evidence(evidence_hash)
+}
+
+pub fn resolve_handler(ctx: Context<ResolveDispute>, resolution: DisputeResolution) -> Result<()> {
+    ctx.accounts.resolve_dispute(resolution)
+}
+
+pub fn lapse_handler(ctx: Context<LapseDispute>) -> Result<()> {
+    ctx.accounts.lapse_dispute()
+}
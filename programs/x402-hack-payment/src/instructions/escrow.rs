@@ -0,0 +1,570 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
+
+use crate::state::{
+    AcceptedMint, Customer, Escrow, Merchant, MerchantReserve, MerchantStats, MerchantVault, Payment, PaymentStatus,
+    Platform, Referrer,
+};
+use crate::errors::PaymentError;
+use crate::events::{EscrowCreated, EscrowDeliveryConfirmed, EscrowExpired, EscrowReleased, PaymentProcessed};
+use crate::utils::{
+    apply_referral, check_referral_vault, settle_payment, verify_ed25519_signature, FeeBreakdown, PaymentSettlement,
+    TokenTransfer,
+};
+
+#[derive(Accounts)]
+#[instruction(payment_id: String)]
+pub struct CreateEscrowPayment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Box<Account<'info, Platform>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Payment::INIT_SPACE,
+        seeds = [Payment::SEED, merchant_account.key().as_ref(), payment_id.as_bytes()],
+        bump,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Escrow::INIT_SPACE,
+        seeds = [Escrow::SEED, payment_account.key().as_ref()],
+        bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [Escrow::VAULT_SEED, payment_account.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = escrow,
+        token::token_program = token_program,
+    )]
+    pub escrow_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Customer::INIT_SPACE,
+        seeds = [Customer::SEED, payer.key().as_ref()],
+        bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required when the merchant has a referrer; otherwise optionally credits this referrer
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Option<Box<Account<'info, Referrer>>>,
+
+    /// Referrer-owned token account for `mint`, required when a referral fee is due
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Instructions sysvar, read to find the merchant's Ed25519 escrow intent signature
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateEscrowPayment<'info> {
+    pub fn create_escrow_payment(
+        &mut self,
+        payment_id: String,
+        amount: u64,
+        delivery_deadline: i64,
+        expiry: i64,
+        bumps: &CreateEscrowPaymentBumps,
+    ) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        require!(amount >= self.accepted_mint.min_payment_amount, PaymentError::PaymentBelowMinimum);
+        require!(!payment_id.is_empty() && payment_id.len() <= 64, PaymentError::InvalidPaymentId);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(delivery_deadline > now, PaymentError::InvalidDeliveryDeadline);
+        require!(now <= expiry, PaymentError::PaymentIntentExpired);
+
+        // As with direct payments, only the merchant can issue the payment id, amount and deadline
+        let message = Escrow::intent_message(
+            &self.merchant_account.key(),
+            &self.mint.key(),
+            amount,
+            delivery_deadline,
+            expiry,
+            &payment_id,
+        );
+        let signer = verify_ed25519_signature(&self.instructions, &message)?;
+        require!(self.merchant_account.is_signer(&signer), PaymentError::InvalidSignature);
+
+        self.platform_config
+            .limits_for(&self.merchant_account)
+            .check(amount, &self.customer_account, &self.merchant_account, now)?;
+
+        // Fees are fixed now so later config changes don't affect funds already in escrow;
+        // any hold or reserve applies when the escrow is released
+        let fees = apply_referral(
+            FeeBreakdown::calculate(&self.platform_config, &self.merchant_account, &self.merchant_stats, amount)?,
+            &self.merchant_account,
+            &self.mint.key(),
+            self.referrer.as_deref(),
+            self.referral_vault.as_deref(),
+        )?;
+
+        // The customer's spend counts against its limits from the moment the funds are escrowed
+        self.customer_account.init_if_new(self.payer.key(), now, bumps.customer_account);
+        self.customer_account.record_payment(amount)?;
+
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: None,
+        };
+        let transfer_fee_amount = token_transfer.transfer(
+            self.customer_token.to_account_info(),
+            self.escrow_vault.to_account_info(),
+            self.payer.to_account_info(),
+            &[],
+            amount,
+            &payment_id,
+        )?;
+
+        let mut payment = Payment::completed(
+            payment_id,
+            self.payer.key(),
+            self.merchant_account.key(),
+            self.mint.key(),
+            &fees,
+            transfer_fee_amount,
+            bumps.payment_account,
+        )?;
+        payment.status = PaymentStatus::Escrowed;
+        if fees.referral_fee > 0 {
+            payment.referrer = self.referrer.as_ref().map(|referrer| referrer.key());
+        }
+        self.payment_account.set_inner(payment);
+
+        self.escrow.set_inner(Escrow {
+            payment: self.payment_account.key(),
+            vault: self.escrow_vault.key(),
+            delivery_deadline,
+            delivered_at: None,
+            closed_at: None,
+            bump: bumps.escrow,
+            disputed: false,
+        });
+
+        msg!(
+            "Payment {} of {} escrowed for merchant {} until {}",
+            self.payment_account.payment_id,
+            amount,
+            self.merchant_account.merchant_id,
+            delivery_deadline
+        );
+
+        emit!(EscrowCreated {
+            payment: self.payment_account.key(),
+            escrow: self.escrow.key(),
+            customer: self.payer.key(),
+            merchant: self.merchant_account.key(),
+            mint: self.mint.key(),
+            amount,
+            delivery_deadline,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ConfirmDelivery<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        constraint = payment_account.merchant == merchant_account.key() @ PaymentError::Unauthorized,
+        constraint = payment_account.status == PaymentStatus::Escrowed @ PaymentError::PaymentNotEscrowed,
+    )]
+    pub payment_account: Account<'info, Payment>,
+
+    #[account(
+        mut,
+        seeds = [Escrow::SEED, payment_account.key().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+}
+
+impl<'info> ConfirmDelivery<'info> {
+    pub fn confirm_delivery(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        require!(now < self.escrow.delivery_deadline, PaymentError::DeliveryDeadlinePassed);
+        require!(self.escrow.delivered_at.is_none(), PaymentError::DeliveryAlreadyConfirmed);
+
+        self.escrow.delivered_at = Some(now);
+
+        msg!("Merchant {} confirmed delivery of payment {}", self.merchant_account.merchant_id, self.payment_account.payment_id);
+
+        emit!(EscrowDeliveryConfirmed {
+            payment: self.payment_account.key(),
+            merchant: self.merchant_account.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReleaseEscrow<'info> {
+    /// The customer at any time, or anyone once delivery is confirmed and the deadline has passed
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = payment_account.status == PaymentStatus::Escrowed @ PaymentError::PaymentNotEscrowed,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        mut,
+        seeds = [Escrow::SEED, payment_account.key().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        mut,
+        address = escrow.vault,
+    )]
+    pub escrow_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        address = payment_account.merchant @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    #[account(
+        address = payment_account.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the merchant share instead of `merchant_token` while the merchant has a hold period
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the merchant-side fee; required when the payment carries one
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Referrer-owned token account, required when the payment carries a referral fee
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Required when a destination token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+impl<'info> ReleaseEscrow<'info> {
+    pub fn release_escrow(&mut self) -> Result<()> {
+        require!(!self.escrow.disputed, PaymentError::EscrowDisputed);
+        let now = Clock::get()?.unix_timestamp;
+        let is_customer = self.caller.key() == self.payment_account.customer;
+        require!(
+            is_customer || (self.escrow.delivered_at.is_some() && now >= self.escrow.delivery_deadline),
+            PaymentError::EscrowNotReleasable
+        );
+
+        let fees = self.payment_account.escrow_release(0, self.escrow_vault.amount)?;
+        if fees.referral_fee > 0 {
+            let referrer = self.payment_account.referrer.ok_or(PaymentError::InvalidReferrer)?;
+            check_referral_vault(self.referral_vault.as_deref(), &referrer, &self.mint.key())?;
+        }
+        let payment_key = self.payment_account.key();
+        let seeds = &[Escrow::SEED, payment_key.as_ref(), &[self.escrow.bump]];
+        let (fees, transfer_fee_amount) = settle_payment(
+            PaymentSettlement {
+                token_transfer: TokenTransfer {
+                    token_program: &self.token_program,
+                    mint: &self.mint,
+                    memo_program: self.memo_program.as_ref(),
+                },
+                from: self.escrow_vault.to_account_info(),
+                authority: self.escrow.to_account_info(),
+                signer_seeds: &[&seeds[..]],
+                customer: None,
+                merchant: &mut self.merchant_account,
+                merchant_stats: &mut self.merchant_stats,
+                merchant_token: Some(self.merchant_token.to_account_info()),
+                platform_treasury: self.platform_treasury.to_account_info(),
+                merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                merchant_vault: self.merchant_vault.as_deref_mut(),
+                merchant_vault_tokens: self.merchant_vault_tokens.as_deref_mut(),
+                merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+            },
+            fees,
+            &self.payment_account.payment_id,
+        )?;
+
+        let payment = &mut self.payment_account;
+        payment.transfer_fee_amount = payment.transfer_fee_amount
+            .checked_add(transfer_fee_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        payment.net_amount = payment.amount
+            .checked_sub(payment.transfer_fee_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        payment.reserve_amount = fees.reserve;
+        payment.status = PaymentStatus::Released;
+        self.escrow.closed_at = Some(now);
+
+        msg!(
+            "Escrow for payment {} released: {} to merchant {}, {} fee to platform",
+            payment.payment_id,
+            fees.merchant_amount,
+            self.merchant_account.merchant_id,
+            fees.fee
+        );
+
+        emit!(EscrowReleased {
+            payment: payment.key(),
+            released_by: self.caller.key(),
+            merchant_amount: fees.merchant_amount,
+            fee_amount: fees.fee,
+            merchant_fee_amount: fees.merchant_fee,
+            timestamp: now,
+        });
+        emit!(PaymentProcessed::new(payment.key(), payment));
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExpireEscrow<'info> {
+    pub caller: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = payment_account.status == PaymentStatus::Escrowed @ PaymentError::PaymentNotEscrowed,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        mut,
+        seeds = [Escrow::SEED, payment_account.key().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Box<Account<'info, Escrow>>,
+
+    #[account(
+        mut,
+        address = escrow.vault,
+    )]
+    pub escrow_vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [Customer::SEED, payment_account.customer.as_ref()],
+        bump = customer_account.bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    #[account(
+        address = payment_account.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payment_account.customer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Required when the customer's token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+impl<'info> ExpireEscrow<'info> {
+    pub fn expire_escrow(&mut self) -> Result<()> {
+        require!(!self.escrow.disputed, PaymentError::EscrowDisputed);
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.escrow.delivery_deadline, PaymentError::DeliveryDeadlineNotReached);
+        require!(self.escrow.delivered_at.is_none(), PaymentError::DeliveryAlreadyConfirmed);
+
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
+        };
+        let refunded = self.escrow_vault.amount;
+        let payment_key = self.payment_account.key();
+        let seeds = &[Escrow::SEED, payment_key.as_ref(), &[self.escrow.bump]];
+        let returned_fee = token_transfer.transfer(
+            self.escrow_vault.to_account_info(),
+            self.customer_token.to_account_info(),
+            self.escrow.to_account_info(),
+            &[&seeds[..]],
+            refunded,
+            &self.payment_account.payment_id,
+        )?;
+
+        let payment = &mut self.payment_account;
+        payment.status = PaymentStatus::Expired;
+        payment.refunded_amount = payment.amount;
+        payment.refunded_net_amount = refunded
+            .checked_sub(returned_fee)
+            .ok_or(PaymentError::CalculationError)?;
+        payment.refunded_at = Some(now);
+        self.escrow.closed_at = Some(now);
+        self.customer_account.record_refund(payment.amount)?;
+
+        msg!("Escrow for payment {} expired: {} returned to customer {}", payment.payment_id, refunded, payment.customer);
+
+        emit!(EscrowExpired {
+            payment: payment.key(),
+            customer: payment.customer,
+            refunded_amount: refunded,
+            expired_by: self.caller.key(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn create_escrow_handler(
+    ctx: Context<CreateEscrowPayment>,
+    payment_id: String,
+    amount: u64,
+    delivery_deadline: i64,
+    expiry: i64,
+) -> Result<()> {
+    ctx.accounts.create_escrow_payment(payment_id, amount, delivery_deadline, expiry, &ctx.bumps)
+}
+
+pub fn confirm_handler(ctx: Context<ConfirmDelivery>) -> Result<()> {
+    ctx.accounts.confirm_delivery()
+}
+
+pub fn release_escrow_handler(ctx: Context<ReleaseEscrow>) -> Result<()> {
+    ctx.accounts.release_escrow()
+}
+
+pub fn expire_handler(ctx: Context<ExpireEscrow>) -> Result<()> {
+    ctx.accounts.expire_escrow()
+}
@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenInterface};
+use anchor_spl::associated_token::AssociatedToken;
 
-use crate::state::{Merchant, Platform};
+use crate::state::{
+    AcceptedMint, ApplicationStatus, Merchant, MerchantApplication, MerchantStats, Platform, ReservedMerchantId,
+};
 use crate::errors::PaymentError;
-// Remove unused: use crate::state::merchant;
+use crate::events::MerchantInitialized;
+use crate::utils::init_settlement_wallet;
 
 #[derive(Accounts)]
 #[instruction(merchant_id: String)]
@@ -17,13 +22,51 @@ pub struct InitializeMerchant<'info> {
         bump,
     )]
     pub merchant_account: Account<'info, Merchant>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MerchantStats::INIT_SPACE,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
     #[account(
         seeds = [Platform::SEED],
         bump = platform_config.bump,
     )]
     pub platform_config: Account<'info, Platform>,
+    /// CHECK: Reservation PDA for `merchant_id`; empty unless the platform reserved the ID
+    #[account(
+        seeds = [ReservedMerchantId::SEED, merchant_id.as_bytes()],
+        bump,
+    )]
+    pub reserved_merchant_id: UncheckedAccount<'info>,
+    /// Approved application, required while the platform requires merchant approval; closed on use
+    #[account(
+        mut,
+        seeds = [MerchantApplication::SEED, merchant_id.as_bytes()],
+        bump = merchant_application.bump,
+        constraint = merchant_application.merchant_id == merchant_id @ PaymentError::MerchantApplicationRequired,
+        constraint = merchant_application.applicant == payer.key() @ PaymentError::MerchantApplicationRequired,
+        constraint = merchant_application.status == ApplicationStatus::Approved
+            @ PaymentError::MerchantApplicationRequired,
+        close = payer,
+    )]
+    pub merchant_application: Option<Account<'info, MerchantApplication>>,
     /// CHECK: Settlement wallet validated in instruction logic
     pub settlement_wallet: UncheckedAccount<'info>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::InvalidTokenMint,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+    /// CHECK: Settlement wallet's ATA for `mint`, checked and created if missing in instruction logic
+    #[account(mut)]
+    pub settlement_token: UncheckedAccount<'info>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
 
@@ -31,15 +74,28 @@ impl<'info> InitializeMerchant<'info> {
     pub fn initialize_merchant(
         &mut self, 
         merchant_id: String, 
-        fee_bps: u16,  // Changed to u16
+        fee_bps: u16,
         bumps: &InitializeMerchantBumps
     ) -> Result<()> {
         let clock = Clock::get()?.unix_timestamp;
         
         // Validations
-        require!(!merchant_id.is_empty() && merchant_id.len() <= 64, PaymentError::InvalidMerchantId);
+        Merchant::validate_id(&merchant_id)?;
         require!(self.platform_config.is_active, PaymentError::PlatformInactive);
-        require!(fee_bps <= 1000, PaymentError::FeeTooHigh); // Max 10% merchant fee
+        ReservedMerchantId::check_claim(&self.reserved_merchant_id, &self.payer.key())?;
+        init_settlement_wallet(
+            &self.settlement_wallet,
+            &self.settlement_token,
+            &self.mint,
+            self.payer.to_account_info(),
+            &self.token_program,
+            &self.associated_token_program,
+            &self.system_program,
+        )?;
+        if self.platform_config.require_merchant_approval {
+            require!(self.merchant_application.is_some(), PaymentError::MerchantApplicationRequired);
+        }
+        require!(fee_bps <= Merchant::MAX_FEE_BPS, PaymentError::FeeTooHigh); // Max 10% merchant fee
         
         let merchant_account = &mut self.merchant_account;
         
@@ -52,18 +108,44 @@ impl<'info> InitializeMerchant<'info> {
         merchant_account.transaction_count = 0;
         merchant_account.created_at = clock;
         merchant_account.is_active = true;
+        merchant_account.suspended = false;
+        merchant_account.pending_authority = None;
+        merchant_account.fee_recipient = self.settlement_wallet.key();
+        merchant_account.signing_key = None;
+        merchant_account.referrer = None;
+        merchant_account.refund_count = 0;
+        merchant_account.refunded_volume = 0;
+        merchant_account.refunded_fees = 0;
+        merchant_account.limits = None;
+        merchant_account.receive_day = 0;
+        merchant_account.received_today = 0;
+        merchant_account.settlement_token = None;
+        merchant_account.hold_period = None;
+        merchant_account.reserve_bps = 0;
+        merchant_account.reserve_period = 0;
         merchant_account.bump = bumps.merchant_account;
+
+        self.merchant_stats.set_inner(MerchantStats::new(merchant_account.key(), bumps.merchant_stats));
         
         msg!("Merchant {} initialized with {} bps fee", merchant_account.merchant_id, fee_bps);
+
+        emit!(MerchantInitialized {
+            merchant: merchant_account.key(),
+            merchant_id: merchant_account.merchant_id.clone(),
+            authority: merchant_account.authority,
+            settlement_wallet: merchant_account.settlement_wallet,
+            fee_bps,
+            timestamp: clock,
+        });
         Ok(())
     }
 }
 
 
-pub fn handler(
+pub fn initialize_merchant_handler(
     ctx: Context<InitializeMerchant>, 
     merchant_id: String, 
-    fee_bps: u16  // Changed to u16
+    fee_bps: u16
 ) -> Result<()> {
     ctx.accounts.initialize_merchant(merchant_id, fee_bps, &ctx.bumps)
 }
\ No newline at end of file
@@ -0,0 +1,403 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
+
+use crate::state::{
+    AcceptedMint, Customer, Invoice, InvoiceStatus, Merchant, MerchantReserve, MerchantStats, MerchantVault, Payment,
+    Platform, Referrer,
+};
+use crate::errors::PaymentError;
+use crate::events::{InvoiceCreated, InvoicePaymentApplied, InvoiceStatusChanged, PaymentProcessed};
+use crate::utils::{apply_referral, settle_payment, FeeBreakdown, PaymentSettlement, TokenTransfer};
+
+#[derive(Accounts)]
+#[instruction(invoice_id: String)]
+pub struct CreateInvoice<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Invoice::INIT_SPACE,
+        seeds = [Invoice::SEED, merchant_account.key().as_ref(), invoice_id.as_bytes()],
+        bump,
+    )]
+    pub invoice: Account<'info, Invoice>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateInvoice<'info> {
+    pub fn create_invoice(
+        &mut self,
+        invoice_id: String,
+        amount_due: u64,
+        due_date: i64,
+        payer: Option<Pubkey>,
+        memo_hash: [u8; 32],
+        bump: u8,
+    ) -> Result<()> {
+        require!(!invoice_id.is_empty() && invoice_id.len() <= 32, PaymentError::InvalidInvoiceId);
+        require!(amount_due >= self.accepted_mint.min_payment_amount, PaymentError::PaymentBelowMinimum);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(due_date > now, PaymentError::InvalidDueDate);
+
+        self.invoice.set_inner(Invoice {
+            merchant: self.merchant_account.key(),
+            invoice_id,
+            mint: self.accepted_mint.mint,
+            amount_due,
+            amount_paid: 0,
+            due_date,
+            payer,
+            memo_hash,
+            status: InvoiceStatus::Open,
+            payment_count: 0,
+            created_at: now,
+            paid_at: None,
+            bump,
+        });
+
+        msg!(
+            "Invoice {} created for merchant {}: {} due by {}",
+            self.invoice.invoice_id,
+            self.merchant_account.merchant_id,
+            amount_due,
+            due_date
+        );
+
+        emit!(InvoiceCreated {
+            invoice: self.invoice.key(),
+            merchant: self.merchant_account.key(),
+            invoice_id: self.invoice.invoice_id.clone(),
+            mint: self.invoice.mint,
+            amount_due,
+            due_date,
+            payer,
+            memo_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct PayInvoice<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Box<Account<'info, Platform>>,
+
+    #[account(
+        mut,
+        seeds = [Invoice::SEED, merchant_account.key().as_ref(), invoice.invoice_id.as_bytes()],
+        bump = invoice.bump,
+        constraint = invoice.status != InvoiceStatus::Paid @ PaymentError::InvoiceAlreadyPaid,
+    )]
+    pub invoice: Box<Account<'info, Invoice>>,
+
+    /// Seeded by the invoice and its payment count, so no other payment can take the address
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Payment::INIT_SPACE,
+        seeds = [Payment::SEED, invoice.key().as_ref(), &invoice.payment_count.to_le_bytes()],
+        bump,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Customer::INIT_SPACE,
+        seeds = [Customer::SEED, payer.key().as_ref()],
+        bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    #[account(
+        address = invoice.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the merchant share instead of `merchant_token` while the merchant has a hold period
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the merchant-side fee; required when `merchant_account.fee > 0`
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
+        token::authority = platform_config,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required when the merchant has a referrer; otherwise optionally credits this referrer
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Option<Box<Account<'info, Referrer>>>,
+
+    /// Referrer-owned token account for `mint`, required when a referral fee is due
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// Required when a destination token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PayInvoice<'info> {
+    /// Applies a full or partial payment to the invoice
+    pub fn pay_invoice(&mut self, amount: u64, bumps: &PayInvoiceBumps) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        if let Some(payer) = self.invoice.payer {
+            require_keys_eq!(payer, self.payer.key(), PaymentError::InvoicePayerNotAllowed);
+        }
+
+        let remaining = self.invoice.remaining_amount();
+        require!(amount > 0 && amount <= remaining, PaymentError::InvalidInvoicePayment);
+        // Settling the final balance is always allowed, even if it is below the minimum
+        require!(
+            amount == remaining || amount >= self.accepted_mint.min_payment_amount,
+            PaymentError::PaymentBelowMinimum
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        self.platform_config
+            .limits_for(&self.merchant_account)
+            .check(amount, &self.customer_account, &self.merchant_account, now)?;
+
+        let fees = apply_referral(
+            FeeBreakdown::calculate(&self.platform_config, &self.merchant_account, &self.merchant_stats, amount)?,
+            &self.merchant_account,
+            &self.mint.key(),
+            self.referrer.as_deref(),
+            self.referral_vault.as_deref(),
+        )?;
+        self.customer_account.init_if_new(self.payer.key(), now, bumps.customer_account);
+        let payment_id = format!("{}-{}", self.invoice.invoice_id, self.invoice.payment_count);
+
+        let (fees, transfer_fee_amount) = settle_payment(
+            PaymentSettlement {
+                token_transfer: TokenTransfer {
+                    token_program: &self.token_program,
+                    mint: &self.mint,
+                    memo_program: self.memo_program.as_ref(),
+                },
+                from: self.customer_token.to_account_info(),
+                authority: self.payer.to_account_info(),
+                signer_seeds: &[],
+                customer: Some(&mut self.customer_account),
+                merchant: &mut self.merchant_account,
+                merchant_stats: &mut self.merchant_stats,
+                merchant_token: Some(self.merchant_token.to_account_info()),
+                platform_treasury: self.platform_treasury.to_account_info(),
+                merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                merchant_vault: self.merchant_vault.as_deref_mut(),
+                merchant_vault_tokens: self.merchant_vault_tokens.as_deref_mut(),
+                merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+            },
+            fees,
+            &payment_id,
+        )?;
+
+        let mut payment = Payment::completed(
+            payment_id,
+            self.payer.key(),
+            self.merchant_account.key(),
+            self.mint.key(),
+            &fees,
+            transfer_fee_amount,
+            bumps.payment_account,
+        )?;
+        payment.invoice = Some(self.invoice.key());
+        payment.sequence = self.invoice.payment_count;
+        if fees.referral_fee > 0 {
+            payment.referrer = self.referrer.as_ref().map(|referrer| referrer.key());
+        }
+        self.payment_account.set_inner(payment);
+
+        let invoice = &mut self.invoice;
+        invoice.amount_paid = invoice.amount_paid
+            .checked_add(amount)
+            .ok_or(PaymentError::CalculationError)?;
+        invoice.payment_count = invoice.payment_count
+            .checked_add(1)
+            .ok_or(PaymentError::CalculationError)?;
+        invoice.status = invoice.current_status(now);
+        if invoice.status == InvoiceStatus::Paid {
+            invoice.paid_at = Some(now);
+        }
+
+        msg!(
+            "Invoice {} received {} ({} of {} paid)",
+            invoice.invoice_id,
+            amount,
+            invoice.amount_paid,
+            invoice.amount_due
+        );
+
+        emit!(PaymentProcessed::new(self.payment_account.key(), &self.payment_account));
+        emit!(InvoicePaymentApplied {
+            invoice: invoice.key(),
+            payment: self.payment_account.key(),
+            payer: self.payer.key(),
+            amount,
+            amount_paid: invoice.amount_paid,
+            status: invoice.status,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct MarkInvoiceOverdue<'info> {
+    #[account(
+        mut,
+        seeds = [Invoice::SEED, invoice.merchant.as_ref(), invoice.invoice_id.as_bytes()],
+        bump = invoice.bump,
+    )]
+    pub invoice: Account<'info, Invoice>,
+}
+
+impl<'info> MarkInvoiceOverdue<'info> {
+    /// Permissionless; flags an unpaid invoice past its due date
+    pub fn mark_invoice_overdue(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let status = self.invoice.current_status(now);
+        require!(
+            status == InvoiceStatus::Overdue && self.invoice.status != InvoiceStatus::Overdue,
+            PaymentError::InvoiceNotOverdue
+        );
+
+        self.invoice.status = status;
+
+        msg!("Invoice {} is overdue", self.invoice.invoice_id);
+
+        emit!(InvoiceStatusChanged {
+            invoice: self.invoice.key(),
+            status,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn create_invoice_handler(
+    ctx: Context<CreateInvoice>,
+    invoice_id: String,
+    amount_due: u64,
+    due_date: i64,
+    payer: Option<Pubkey>,
+    memo_hash: [u8; 32],
+) -> Result<()> {
+    let bump = ctx.bumps.invoice;
+    ctx.accounts.create_invoice(invoice_id, amount_due, due_date, payer, memo_hash, bump)
+}
+
+pub fn pay_handler(ctx: Context<PayInvoice>, amount: u64) -> Result<()> {
+    ctx.accounts.pay_invoice(amount, &ctx.bumps)
+}
+
+pub fn overdue_handler(ctx: Context<MarkInvoiceOverdue>) -> Result<()> {
+    ctx.accounts.mark_invoice_overdue()
+}
@@ -0,0 +1,498 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+
+use crate::state::{AcceptedMint, Merchant, MerchantReserve, MerchantStats, MerchantVault, PaymentLimits, Platform};
+use crate::errors::PaymentError;
+use crate::events::{
+    MerchantAuthorityProposed, MerchantAuthorityTransferred, MerchantSigningKeyUpdated, MerchantStatusChanged,
+    MerchantHoldPeriodUpdated, MerchantReserveUpdated, MerchantSettlementTokenUpdated, MerchantUpdated,
+    PaymentLimitsUpdated,
+};
+use crate::utils::{check_migratable, grow_account, init_settlement_wallet, BPS_DENOMINATOR};
+
+#[derive(Accounts)]
+pub struct UpdateMerchant<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMerchantConfig<'info> {
+    /// Pays for the new settlement wallet's token account if it has none
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    /// CHECK: New settlement wallet, validated in instruction logic; required when changing it
+    pub settlement_wallet: Option<UncheckedAccount<'info>>,
+
+    /// Mint the new settlement wallet's token account is created for; required when changing it
+    pub mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::InvalidTokenMint,
+    )]
+    pub accepted_mint: Option<Box<Account<'info, AcceptedMint>>>,
+
+    /// CHECK: New settlement wallet's ATA for `mint`, checked and created if missing in instruction logic
+    #[account(mut)]
+    pub settlement_token: Option<UncheckedAccount<'info>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> UpdateMerchantConfig<'info> {
+    pub fn update_merchant(
+        &mut self,
+        settlement_wallet: Option<Pubkey>,
+        fee_bps: Option<u16>,
+        fee_recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        if let Some(settlement_wallet) = settlement_wallet {
+            // The new wallet gets the same checks and token account as at registration
+            let (Some(wallet), Some(mint), Some(accepted_mint), Some(settlement_token)) = (
+                self.settlement_wallet.as_ref(),
+                self.mint.as_ref(),
+                self.accepted_mint.as_ref(),
+                self.settlement_token.as_ref(),
+            ) else {
+                return err!(PaymentError::MissingSettlementAccounts);
+            };
+            require_keys_eq!(wallet.key(), settlement_wallet, PaymentError::InvalidSettlementWallet);
+            require_keys_eq!(mint.key(), accepted_mint.mint, PaymentError::InvalidTokenMint);
+            init_settlement_wallet(
+                wallet,
+                settlement_token,
+                mint,
+                self.authority.to_account_info(),
+                &self.token_program,
+                &self.associated_token_program,
+                &self.system_program,
+            )?;
+
+            let merchant_account = &mut self.merchant_account;
+            merchant_account.settlement_wallet = settlement_wallet;
+            // A custom settlement account belongs to the old wallet
+            merchant_account.settlement_token = None;
+            msg!("Merchant {} settlement wallet set to {}", merchant_account.merchant_id, settlement_wallet);
+        }
+
+        let merchant_account = &mut self.merchant_account;
+
+        if let Some(fee_bps) = fee_bps {
+            require!(fee_bps <= Merchant::MAX_FEE_BPS, PaymentError::FeeTooHigh);
+            merchant_account.fee = fee_bps;
+            msg!("Merchant {} fee set to {} bps", merchant_account.merchant_id, fee_bps);
+        }
+
+        if let Some(fee_recipient) = fee_recipient {
+            require!(fee_recipient != Pubkey::default(), PaymentError::InvalidFeeRecipient);
+            merchant_account.fee_recipient = fee_recipient;
+            msg!("Merchant {} fee recipient set to {}", merchant_account.merchant_id, fee_recipient);
+        }
+
+        emit!(MerchantUpdated {
+            merchant: merchant_account.key(),
+            settlement_wallet: merchant_account.settlement_wallet,
+            fee_bps: merchant_account.fee,
+            fee_recipient: merchant_account.fee_recipient,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> UpdateMerchant<'info> {
+    pub fn set_merchant_active(&mut self, is_active: bool) -> Result<()> {
+        let merchant_account = &mut self.merchant_account;
+        // A platform suspension can only be lifted by the platform authority
+        require!(!(is_active && merchant_account.suspended), PaymentError::MerchantSuspended);
+
+        merchant_account.is_active = is_active;
+        msg!(
+            "Merchant {} {}",
+            merchant_account.merchant_id,
+            if is_active { "activated" } else { "deactivated" }
+        );
+
+        emit!(MerchantStatusChanged {
+            merchant: merchant_account.key(),
+            is_active,
+            suspended: merchant_account.suspended,
+            changed_by: self.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_merchant_authority(&mut self, new_authority: Pubkey) -> Result<()> {
+        let merchant_account = &mut self.merchant_account;
+        merchant_account.pending_authority = Some(new_authority);
+
+        msg!(
+            "Merchant {} authority transfer proposed to {}",
+            merchant_account.merchant_id,
+            new_authority
+        );
+
+        emit!(MerchantAuthorityProposed {
+            merchant: merchant_account.key(),
+            authority: merchant_account.authority,
+            pending_authority: new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_merchant_signing_key(&mut self, signing_key: Option<Pubkey>) -> Result<()> {
+        let merchant_account = &mut self.merchant_account;
+        merchant_account.signing_key = signing_key;
+
+        msg!("Merchant {} signing key set to {:?}", merchant_account.merchant_id, signing_key);
+
+        emit!(MerchantSigningKeyUpdated {
+            merchant: merchant_account.key(),
+            signing_key,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetSettlementToken<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    /// Omit to settle into the settlement wallet's ATAs again
+    #[account(
+        token::authority = merchant_account.settlement_wallet,
+    )]
+    pub settlement_token: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+impl<'info> SetSettlementToken<'info> {
+    /// Settles the token account's mint into it instead of the settlement wallet's ATA
+    pub fn set_settlement_token(&mut self) -> Result<()> {
+        let settlement_token = self.settlement_token.as_ref().map(|account| account.key());
+        let merchant_account = &mut self.merchant_account;
+        merchant_account.settlement_token = settlement_token;
+
+        msg!("Merchant {} settlement token account set to {:?}", merchant_account.merchant_id, settlement_token);
+
+        emit!(MerchantSettlementTokenUpdated {
+            merchant: merchant_account.key(),
+            settlement_token,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptMerchantAuthority<'info> {
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        constraint = merchant_account.pending_authority == Some(new_authority.key()) @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+}
+
+impl<'info> AcceptMerchantAuthority<'info> {
+    pub fn accept_merchant_authority(&mut self) -> Result<()> {
+        let merchant_account = &mut self.merchant_account;
+        let old_authority = merchant_account.authority;
+        merchant_account.authority = self.new_authority.key();
+        merchant_account.pending_authority = None;
+
+        msg!(
+            "Merchant {} authority transferred from {} to {}",
+            merchant_account.merchant_id,
+            old_authority,
+            merchant_account.authority
+        );
+
+        emit!(MerchantAuthorityTransferred {
+            merchant: merchant_account.key(),
+            old_authority,
+            new_authority: merchant_account.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SuspendMerchant<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+}
+
+impl<'info> SuspendMerchant<'info> {
+    pub fn set_merchant_suspended(&mut self, suspended: bool) -> Result<()> {
+        let merchant_account = &mut self.merchant_account;
+        merchant_account.suspended = suspended;
+
+        msg!(
+            "Merchant {} {} by platform",
+            merchant_account.merchant_id,
+            if suspended { "suspended" } else { "reinstated" }
+        );
+
+        emit!(MerchantStatusChanged {
+            merchant: merchant_account.key(),
+            is_active: merchant_account.is_active,
+            suspended,
+            changed_by: self.authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Overrides the platform's default limits for this merchant; `None` restores the defaults
+    pub fn set_merchant_limits(&mut self, limits: Option<PaymentLimits>) -> Result<()> {
+        let merchant_account = &mut self.merchant_account;
+        merchant_account.limits = limits;
+
+        msg!("Merchant {} limits set to {:?}", merchant_account.merchant_id, limits);
+
+        emit!(PaymentLimitsUpdated {
+            merchant: Some(merchant_account.key()),
+            limits,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Holds the merchant's payment proceeds in its vault for `hold_period` seconds;
+    /// `None` returns to direct settlement. Funds already held keep their release times.
+    pub fn set_merchant_hold_period(&mut self, hold_period: Option<i64>) -> Result<()> {
+        if let Some(hold_period) = hold_period {
+            require!(
+                (0..=MerchantVault::MAX_HOLD_PERIOD).contains(&hold_period),
+                PaymentError::InvalidHoldPeriod
+            );
+        }
+
+        let merchant_account = &mut self.merchant_account;
+        merchant_account.hold_period = hold_period;
+
+        msg!("Merchant {} hold period set to {:?}", merchant_account.merchant_id, hold_period);
+
+        emit!(MerchantHoldPeriodUpdated {
+            merchant: merchant_account.key(),
+            hold_period,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Holds `reserve_bps` of the merchant's payouts in its reserve for
+    /// `reserve_period` seconds; zero bps disables it. Funds already reserved keep their release times.
+    pub fn set_merchant_reserve(&mut self, reserve_bps: u16, reserve_period: i64) -> Result<()> {
+        require!(reserve_bps as u64 <= BPS_DENOMINATOR, PaymentError::InvalidReserveConfig);
+        if reserve_bps > 0 {
+            require!(
+                reserve_period > 0 && reserve_period <= MerchantReserve::MAX_RESERVE_PERIOD,
+                PaymentError::InvalidReserveConfig
+            );
+        }
+
+        let merchant_account = &mut self.merchant_account;
+        merchant_account.reserve_bps = reserve_bps;
+        merchant_account.reserve_period = reserve_period;
+
+        msg!(
+            "Merchant {} reserve set to {} bps for {}s",
+            merchant_account.merchant_id,
+            reserve_bps,
+            reserve_period
+        );
+
+        emit!(MerchantReserveUpdated {
+            merchant: merchant_account.key(),
+            reserve_bps,
+            reserve_period,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+/// Grows a `Merchant` account created by an older program version to the current layout.
+/// Newer fields follow `bump` and decode from zeros, so the account is resized and zero-filled
+/// before it is deserialized; anyone may pay for it.
+#[derive(Accounts)]
+pub struct MigrateMerchant<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: May not deserialize as `Merchant` yet; owner and discriminator are checked in instruction logic
+    #[account(mut)]
+    pub merchant_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateMerchant<'info> {
+    pub fn migrate_merchant(&mut self) -> Result<()> {
+        let merchant_info = self.merchant_account.to_account_info();
+        check_migratable::<Merchant>(&merchant_info)?;
+
+        // `merchant_id` is the first field after the discriminator
+        let id_len = {
+            let data = merchant_info.try_borrow_data()?;
+            require!(data.len() >= 12, PaymentError::InvalidMigrationAccount);
+            u32::from_le_bytes(data[8..12].try_into().unwrap()) as usize
+        };
+        let new_len = Merchant::space(id_len);
+        let old_len = grow_account(
+            &merchant_info,
+            &self.payer.to_account_info(),
+            &self.system_program.to_account_info(),
+            new_len,
+        )?;
+
+        let mut merchant = Merchant::try_deserialize(&mut &merchant_info.try_borrow_data()?[..])?;
+        if merchant.fee_recipient == Pubkey::default() {
+            merchant.fee_recipient = merchant.settlement_wallet;
+        }
+        merchant.try_serialize(&mut &mut merchant_info.try_borrow_mut_data()?[..])?;
+
+        msg!("Merchant {} migrated from {} to {} bytes", merchant.merchant_id, old_len, old_len.max(new_len));
+        Ok(())
+    }
+}
+
+/// Creates the stats account for a merchant registered before stats were tracked
+#[derive(Accounts)]
+pub struct InitMerchantStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MerchantStats::INIT_SPACE,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+pub fn update_merchant_handler(
+    ctx: Context<UpdateMerchantConfig>,
+    settlement_wallet: Option<Pubkey>,
+    fee_bps: Option<u16>,
+    fee_recipient: Option<Pubkey>,
+) -> Result<()> {
+    ctx.accounts.update_merchant(settlement_wallet, fee_bps, fee_recipient)
+}
+
+pub fn set_active_handler(ctx: Context<UpdateMerchant>, is_active: bool) -> Result<()> {
+    ctx.accounts.set_merchant_active(is_active)
+}
+
+pub fn propose_authority_handler(ctx: Context<UpdateMerchant>, new_authority: Pubkey) -> Result<()> {
+    ctx.accounts.propose_merchant_authority(new_authority)
+}
+
+pub fn signing_key_handler(ctx: Context<UpdateMerchant>, signing_key: Option<Pubkey>) -> Result<()> {
+    ctx.accounts.set_merchant_signing_key(signing_key)
+}
+
+pub fn settlement_token_handler(ctx: Context<SetSettlementToken>) -> Result<()> {
+    ctx.accounts.set_settlement_token()
+}
+
+pub fn accept_authority_handler(ctx: Context<AcceptMerchantAuthority>) -> Result<()> {
+    ctx.accounts.accept_merchant_authority()
+}
+
+pub fn suspend_handler(ctx: Context<SuspendMerchant>, suspended: bool) -> Result<()> {
+    ctx.accounts.set_merchant_suspended(suspended)
+}
+
+pub fn limits_handler(ctx: Context<SuspendMerchant>, limits: Option<PaymentLimits>) -> Result<()> {
+    ctx.accounts.set_merchant_limits(limits)
+}
+
+pub fn hold_period_handler(ctx: Context<SuspendMerchant>, hold_period: Option<i64>) -> Result<()> {
+    ctx.accounts.set_merchant_hold_period(hold_period)
+}
+
+pub fn migrate_merchant_handler(ctx: Context<MigrateMerchant>) -> Result<()> {
+    ctx.accounts.migrate_merchant()
+}
+
+pub fn set_reserve_handler(ctx: Context<SuspendMerchant>, reserve_bps: u16, reserve_period: i64) -> Result<()> {
+    ctx.accounts.set_merchant_reserve(reserve_bps, reserve_period)
+}
+
+pub fn init_stats_handler(ctx: Context<InitMerchantStats>) -> Result<()> {
+    let merchant = ctx.accounts.merchant_account.key();
+    ctx.accounts.merchant_stats.set_inner(MerchantStats::new(merchant, ctx.bumps.merchant_stats));
+    msg!("Stats initialized for merchant {}", ctx.accounts.merchant_account.merchant_id);
+    Ok(())
+}
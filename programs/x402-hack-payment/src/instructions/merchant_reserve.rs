@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::memo::Memo;
+
+use crate::state::{AcceptedMint, Merchant, MerchantReserve};
+use crate::errors::PaymentError;
+use crate::events::ReserveReleased;
+use crate::utils::TokenTransfer;
+
+#[derive(Accounts)]
+pub struct OpenMerchantReserve<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MerchantReserve::INIT_SPACE,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub merchant_reserve: Box<Account<'info, MerchantReserve>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [MerchantReserve::TOKENS_SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = merchant_reserve,
+        token::token_program = token_program,
+    )]
+    pub reserve_tokens: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenMerchantReserve<'info> {
+    pub fn open_merchant_reserve(&mut self, bump: u8) -> Result<()> {
+        self.merchant_reserve.set_inner(MerchantReserve {
+            merchant: self.merchant_account.key(),
+            mint: self.mint.key(),
+            vault: self.reserve_tokens.key(),
+            pending: Vec::new(),
+            bump,
+        });
+
+        msg!(
+            "Merchant reserve {} opened for merchant {} in mint {}",
+            self.reserve_tokens.key(),
+            self.merchant_account.merchant_id,
+            self.mint.key()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReleaseReserve<'info> {
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Box<Account<'info, MerchantReserve>>,
+
+    #[account(
+        mut,
+        address = merchant_reserve.vault,
+    )]
+    pub reserve_tokens: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Required when the merchant's token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+impl<'info> ReleaseReserve<'info> {
+    /// Pays every matured reserve tranche out to the merchant's settlement account in one transfer
+    pub fn release_reserve(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let amount = self.merchant_reserve.release(now)?;
+        require!(amount > 0, PaymentError::NothingToRelease);
+
+        let merchant = self.merchant_account.key();
+        let mint = self.mint.key();
+        let seeds = &[MerchantReserve::SEED, merchant.as_ref(), mint.as_ref(), &[self.merchant_reserve.bump]];
+
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
+        };
+        token_transfer.transfer(
+            self.reserve_tokens.to_account_info(),
+            self.merchant_token.to_account_info(),
+            self.merchant_reserve.to_account_info(),
+            &[&seeds[..]],
+            amount,
+            "merchant reserve release",
+        )?;
+
+        msg!("Released {} of merchant {} reserve in mint {}", amount, self.merchant_account.merchant_id, mint);
+
+        emit!(ReserveReleased {
+            merchant,
+            mint,
+            amount,
+            held: self.merchant_reserve.held(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn open_reserve_handler(ctx: Context<OpenMerchantReserve>) -> Result<()> {
+    let bump = ctx.bumps.merchant_reserve;
+    ctx.accounts.open_merchant_reserve(bump)
+}
+
+pub fn release_reserve_handler(ctx: Context<ReleaseReserve>) -> Result<()> {
+    ctx.accounts.release_reserve()
+}
@@ -0,0 +1,164 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::memo::Memo;
+
+use crate::state::{AcceptedMint, Merchant, MerchantVault};
+use crate::errors::PaymentError;
+use crate::events::MerchantBalanceWithdrawn;
+use crate::utils::TokenTransfer;
+
+#[derive(Accounts)]
+pub struct OpenMerchantVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MerchantVault::INIT_SPACE,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump,
+    )]
+    pub merchant_vault: Box<Account<'info, MerchantVault>>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [MerchantVault::TOKENS_SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = merchant_vault,
+        token::token_program = token_program,
+    )]
+    pub vault_tokens: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenMerchantVault<'info> {
+    pub fn open_merchant_vault(&mut self, bump: u8) -> Result<()> {
+        self.merchant_vault.set_inner(MerchantVault {
+            merchant: self.merchant_account.key(),
+            mint: self.mint.key(),
+            vault: self.vault_tokens.key(),
+            available: 0,
+            pending: Vec::new(),
+            bump,
+        });
+
+        msg!(
+            "Merchant vault {} opened for merchant {} in mint {}",
+            self.vault_tokens.key(),
+            self.merchant_account.merchant_id,
+            self.mint.key()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawMerchantBalance<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Box<Account<'info, MerchantVault>>,
+
+    #[account(
+        mut,
+        address = merchant_vault.vault,
+    )]
+    pub vault_tokens: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Required when the merchant's token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+impl<'info> WithdrawMerchantBalance<'info> {
+    /// Pays every released tranche out to the merchant's settlement account in one transfer
+    pub fn withdraw_merchant_balance(&mut self) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        self.merchant_vault.mature(now)?;
+        let amount = self.merchant_vault.available;
+        require!(amount > 0, PaymentError::NoBalanceToWithdraw);
+        self.merchant_vault.available = 0;
+
+        let merchant = self.merchant_account.key();
+        let mint = self.mint.key();
+        let seeds = &[MerchantVault::SEED, merchant.as_ref(), mint.as_ref(), &[self.merchant_vault.bump]];
+
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
+        };
+        token_transfer.transfer(
+            self.vault_tokens.to_account_info(),
+            self.merchant_token.to_account_info(),
+            self.merchant_vault.to_account_info(),
+            &[&seeds[..]],
+            amount,
+            "merchant balance withdrawal",
+        )?;
+
+        msg!("Merchant {} withdrew {} in mint {}", self.merchant_account.merchant_id, amount, mint);
+
+        emit!(MerchantBalanceWithdrawn {
+            merchant,
+            mint,
+            amount,
+            held: self.merchant_vault.held(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn open_merchant_vault_handler(ctx: Context<OpenMerchantVault>) -> Result<()> {
+    let bump = ctx.bumps.merchant_vault;
+    ctx.accounts.open_merchant_vault(bump)
+}
+
+pub fn withdraw_handler(ctx: Context<WithdrawMerchantBalance>) -> Result<()> {
+    ctx.accounts.withdraw_merchant_balance()
+}
@@ -1,15 +1,43 @@
+pub mod accepted_mint;
+pub mod allowance;
 pub mod claim;
+pub mod dispute;
+pub mod escrow;
 pub mod initialize;
+pub mod invoice;
+pub mod merchant;
+pub mod merchant_reserve;
+pub mod merchant_vault;
+pub mod onboarding;
 pub mod payment;
 pub mod platform;
 pub mod private_receipt;
+pub mod referral;
 pub mod refund;
+pub mod resource;
+pub mod split;
+pub mod subscription;
 pub mod transfer;
+pub mod update_platform;
 
+pub use accepted_mint::*;
+pub use allowance::*;
 pub use claim::*;
+pub use dispute::*;
+pub use escrow::*;
 pub use initialize::*;
+pub use invoice::*;
+pub use merchant::*;
+pub use merchant_reserve::*;
+pub use merchant_vault::*;
+pub use onboarding::*;
 pub use payment::*;
 pub use platform::*;
 pub use private_receipt::*;
+pub use referral::*;
 pub use refund::*;
+pub use resource::*;
+pub use split::*;
+pub use subscription::*;
 pub use transfer::*;
+pub use update_platform::*;
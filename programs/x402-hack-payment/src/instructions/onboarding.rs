@@ -0,0 +1,237 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{ApplicationStatus, Merchant, MerchantApplication, Platform, ReservedMerchantId};
+use crate::errors::PaymentError;
+use crate::events::{MerchantApplicationReviewed, MerchantApplicationSubmitted, MerchantIdReservationUpdated};
+
+#[derive(Accounts)]
+#[instruction(merchant_id: String)]
+pub struct SubmitMerchantApplication<'info> {
+    #[account(mut)]
+    pub applicant: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    /// CHECK: Merchant PDA for `merchant_id`; must not exist yet
+    #[account(
+        seeds = [b"merchant", merchant_id.as_bytes()],
+        bump,
+        constraint = merchant_account.data_is_empty() @ PaymentError::InvalidMerchantId,
+    )]
+    pub merchant_account: UncheckedAccount<'info>,
+
+    /// CHECK: Reservation PDA for `merchant_id`; empty unless the platform reserved the ID
+    #[account(
+        seeds = [ReservedMerchantId::SEED, merchant_id.as_bytes()],
+        bump,
+    )]
+    pub reserved_merchant_id: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = applicant,
+        space = 8 + MerchantApplication::INIT_SPACE,
+        seeds = [MerchantApplication::SEED, merchant_id.as_bytes()],
+        bump,
+    )]
+    pub merchant_application: Account<'info, MerchantApplication>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SubmitMerchantApplication<'info> {
+    pub fn submit_merchant_application(&mut self, merchant_id: String, details_hash: [u8; 32], bump: u8) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        Merchant::validate_id(&merchant_id)?;
+        ReservedMerchantId::check_claim(&self.reserved_merchant_id, &self.applicant.key())?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.merchant_application.set_inner(MerchantApplication {
+            merchant_id: merchant_id.clone(),
+            applicant: self.applicant.key(),
+            details_hash,
+            status: ApplicationStatus::Pending,
+            submitted_at: now,
+            reviewed_at: None,
+            bump,
+        });
+
+        msg!("Application for merchant {} submitted by {}", merchant_id, self.applicant.key());
+
+        emit!(MerchantApplicationSubmitted {
+            application: self.merchant_application.key(),
+            merchant_id,
+            applicant: self.applicant.key(),
+            details_hash,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReviewMerchantApplication<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        mut,
+        seeds = [MerchantApplication::SEED, merchant_application.merchant_id.as_bytes()],
+        bump = merchant_application.bump,
+        has_one = applicant,
+    )]
+    pub merchant_application: Account<'info, MerchantApplication>,
+
+    /// CHECK: Receives the application's rent if it is rejected
+    #[account(mut)]
+    pub applicant: UncheckedAccount<'info>,
+}
+
+impl<'info> ReviewMerchantApplication<'info> {
+    /// Approves the application, or rejects it by closing it so the ID can be applied for again
+    pub fn review_merchant_application(&mut self, approve: bool) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let application = &mut self.merchant_application;
+
+        if approve {
+            application.status = ApplicationStatus::Approved;
+            application.reviewed_at = Some(now);
+        } else {
+            application.close(self.applicant.to_account_info())?;
+        }
+
+        msg!(
+            "Application for merchant {} {}",
+            application.merchant_id,
+            if approve { "approved" } else { "rejected" }
+        );
+
+        emit!(MerchantApplicationReviewed {
+            application: application.key(),
+            merchant_id: application.merchant_id.clone(),
+            applicant: application.applicant,
+            approved: approve,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(merchant_id: String)]
+pub struct ReserveMerchantId<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + ReservedMerchantId::INIT_SPACE,
+        seeds = [ReservedMerchantId::SEED, merchant_id.as_bytes()],
+        bump,
+    )]
+    pub reserved_merchant_id: Account<'info, ReservedMerchantId>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ReserveMerchantId<'info> {
+    /// Reserves `merchant_id` for `holder`, or blocks it when `holder` is `None`. Re-reserving changes the holder.
+    pub fn reserve_merchant_id(&mut self, merchant_id: String, holder: Option<Pubkey>, bump: u8) -> Result<()> {
+        Merchant::validate_id(&merchant_id)?;
+
+        let now = Clock::get()?.unix_timestamp;
+        self.reserved_merchant_id.set_inner(ReservedMerchantId {
+            merchant_id: merchant_id.clone(),
+            holder,
+            reserved_at: now,
+            bump,
+        });
+
+        msg!("Merchant ID {} reserved for {:?}", merchant_id, holder);
+
+        emit!(MerchantIdReservationUpdated {
+            merchant_id,
+            holder,
+            reserved: true,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ReleaseMerchantId<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        mut,
+        seeds = [ReservedMerchantId::SEED, reserved_merchant_id.merchant_id.as_bytes()],
+        bump = reserved_merchant_id.bump,
+        close = authority,
+    )]
+    pub reserved_merchant_id: Account<'info, ReservedMerchantId>,
+}
+
+impl<'info> ReleaseMerchantId<'info> {
+    pub fn release_merchant_id(&mut self) -> Result<()> {
+        let merchant_id = self.reserved_merchant_id.merchant_id.clone();
+
+        msg!("Merchant ID {} released", merchant_id);
+
+        emit!(MerchantIdReservationUpdated {
+            merchant_id,
+            holder: None,
+            reserved: false,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn submit_handler(ctx: Context<SubmitMerchantApplication>, merchant_id: String, details_hash: [u8; 32]) -> Result<()> {
+    let bump = ctx.bumps.merchant_application;
+    ctx.accounts.submit_merchant_application(merchant_id, details_hash, bump)
+}
+
+pub fn review_handler(ctx: Context<ReviewMerchantApplication>, approve: bool) -> Result<()> {
+    ctx.accounts.review_merchant_application(approve)
+}
+
+pub fn reserve_id_handler(ctx: Context<ReserveMerchantId>, merchant_id: String, holder: Option<Pubkey>) -> Result<()> {
+    let bump = ctx.bumps.reserved_merchant_id;
+    ctx.accounts.reserve_merchant_id(merchant_id, holder, bump)
+}
+
+pub fn release_id_handler(ctx: Context<ReleaseMerchantId>) -> Result<()> {
+    ctx.accounts.release_merchant_id()
+}
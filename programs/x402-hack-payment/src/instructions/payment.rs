@@ -1,12 +1,16 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, TokenAccount, Token, Transfer, transfer};
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
 
-use crate::state::Platform;
+use crate::state::{AcceptedMint, MerchantReserve, MerchantStats, MerchantVault, Platform, Referrer};
 use crate::state::merchant::Merchant;
 use crate::state::customer::Customer;
-use crate::state::payment::{Payment, PaymentStatus};
+use crate::state::payment::Payment;
 use crate::errors::PaymentError;
+use crate::events::PaymentProcessed;
+use crate::utils::{apply_referral, check_migratable, grow_account, settle_payment, verify_ed25519_signature, FeeBreakdown, PaymentSettlement, TokenTransfer};
 
 #[derive(Accounts)]
 #[instruction(payment_id: String)]
@@ -22,7 +26,7 @@ pub struct ProcessPayment<'info> {
         init,
         payer = payer,
         space = 8 + Payment::INIT_SPACE,
-        seeds = [Payment::SEED, payment_id.as_bytes()],
+        seeds = [Payment::SEED, merchant_account.key().as_ref(), payment_id.as_bytes()],
         bump,
     )]
     pub payment_account: Account<'info, Payment>,
@@ -30,7 +34,7 @@ pub struct ProcessPayment<'info> {
         init_if_needed,
         payer = payer,
         space = 8 + Customer::INIT_SPACE,
-        seeds = [b"customer", payer.key().as_ref()],
+        seeds = [Customer::SEED, payer.key().as_ref()],
         bump,
     )]
     pub customer_account: Account<'info, Customer>,
@@ -41,141 +45,220 @@ pub struct ProcessPayment<'info> {
     )]
     pub merchant_account: Account<'info, Merchant>,
     #[account(
-        constraint = usdc_mint.key() == platform_config.usdc_mint @ PaymentError::InvalidTokenMint
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
     )]
-    pub usdc_mint: Account<'info, Mint>,
+    pub accepted_mint: Account<'info, AcceptedMint>,
     #[account(
         mut,
-        associated_token::mint = usdc_mint,
+        associated_token::mint = mint,
         associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
     )]
-    pub customer_usdc: Account<'info, TokenAccount>,
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Receives the merchant share instead of `merchant_token` while the merchant has a hold period
     #[account(
         mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = merchant_account.settlement_wallet,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
     )]
-    pub merchant_usdc: Account<'info, TokenAccount>,
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// Receives the merchant-side fee; required when `merchant_account.fee > 0`
     #[account(
         mut,
-        seeds = [Platform::TREASURY_SEED],
-        bump = platform_config.treasury_bump,
-        token::mint = usdc_mint,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
         token::authority = platform_config,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+    /// Required when the merchant has a referrer; otherwise optionally credits this referrer
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
     )]
-    pub platform_treasury_usdc: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
+    pub referrer: Option<Account<'info, Referrer>>,
+    /// Referrer-owned token account for `mint`, required when a referral fee is due
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+    /// CHECK: Instructions sysvar, read to find the merchant's Ed25519 intent signature
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    /// Required when a destination token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> ProcessPayment<'info> {
-    pub fn process_payment(&mut self, payment_id: String, amount: u64, bumps: &ProcessPaymentBumps) -> Result<()> {
+    pub fn process_payment(&mut self, payment_id: String, amount: u64, expiry: i64, bumps: &ProcessPaymentBumps) -> Result<()> {
         require!(self.platform_config.is_active, PaymentError::PlatformInactive);
-        require!(self.merchant_account.is_active, PaymentError::MerchantInactive);
-        require!(amount >= self.platform_config.min_payment_amount, PaymentError::PaymentBelowMinimum);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        require!(amount >= self.accepted_mint.min_payment_amount, PaymentError::PaymentBelowMinimum);
         require!(!payment_id.is_empty() && payment_id.len() <= 64, PaymentError::InvalidPaymentId);
+        require!(Clock::get()?.unix_timestamp <= expiry, PaymentError::PaymentIntentExpired);
 
-        // Calculate fee and merchant amount
-        let fee = amount
-            .checked_mul(self.platform_config.fee_bps as u64)
-            .ok_or(PaymentError::CalculationError)?
-            .checked_div(10_000)
-            .ok_or(PaymentError::CalculationError)?;
+        // The merchant's signature binds amount, mint and payment_id to the invoice it issued
+        let message = Payment::intent_message(
+            &self.merchant_account.key(),
+            &self.mint.key(),
+            amount,
+            expiry,
+            &payment_id,
+        );
+        let signer = verify_ed25519_signature(&self.instructions, &message)?;
+        require!(self.merchant_account.is_signer(&signer), PaymentError::InvalidSignature);
+
+        self.platform_config
+            .limits_for(&self.merchant_account)
+            .check(amount, &self.customer_account, &self.merchant_account, Clock::get()?.unix_timestamp)?;
 
-        let merchant_amount = amount
-            .checked_sub(fee)
-            .ok_or(PaymentError::CalculationError)?;
+        let fees = apply_referral(
+            FeeBreakdown::calculate(&self.platform_config, &self.merchant_account, &self.merchant_stats, amount)?,
+            &self.merchant_account,
+            &self.mint.key(),
+            self.referrer.as_ref(),
+            self.referral_vault.as_deref(),
+        )?;
 
         // Initialize customer account if this is their first payment
-        if self.customer_account.transaction_count == 0 {
-            self.customer_account.customer = self.payer.key();
-            self.customer_account.total_spent = 0;
-            self.customer_account.transaction_count = 0;
-            self.customer_account.created_at = Clock::get()?.unix_timestamp;
-            self.customer_account.bump = bumps.customer_account;
-        }
+        self.customer_account.init_if_new(self.payer.key(), Clock::get()?.unix_timestamp, bumps.customer_account);
 
-        // Transfer merchant amount from customer to merchant
-        let transfer_to_merchant_accounts = Transfer {
-            from: self.customer_usdc.to_account_info(),
-            to: self.merchant_usdc.to_account_info(),
-            authority: self.payer.to_account_info(),
-        };
-        let transfer_to_merchant_ctx = CpiContext::new(
-            self.token_program.to_account_info(),
-            transfer_to_merchant_accounts,
-        );
-        transfer(transfer_to_merchant_ctx, merchant_amount)?;
-
-        // Transfer fee from customer to platform treasury
-        let transfer_fee_accounts = Transfer {
-            from: self.customer_usdc.to_account_info(),
-            to: self.platform_treasury_usdc.to_account_info(),
-            authority: self.payer.to_account_info(),
-        };
-        let transfer_fee_ctx = CpiContext::new(
-            self.token_program.to_account_info(),
-            transfer_fee_accounts,
-        );
-        transfer(transfer_fee_ctx, fee)?;
-
-        // Update customer stats
-        self.customer_account.transaction_count = self.customer_account
-            .transaction_count
-            .checked_add(1)
-            .ok_or(PaymentError::CalculationError)?;
-        
-        self.customer_account.total_spent = self.customer_account
-            .total_spent
-            .checked_add(amount)
-            .ok_or(PaymentError::CalculationError)?;
-
-        // Update merchant stats
-        self.merchant_account.transaction_count = self.merchant_account
-            .transaction_count
-            .checked_add(1)
-            .ok_or(PaymentError::CalculationError)?;
-        
-        self.merchant_account.volume = self.merchant_account
-            .volume
-            .checked_add(merchant_amount)
-            .ok_or(PaymentError::CalculationError)?;
-
-        self.merchant_account.total_fees = self.merchant_account
-            .total_fees
-            .checked_add(fee)
-            .ok_or(PaymentError::CalculationError)?;
+        let (fees, transfer_fee_amount) = settle_payment(
+            PaymentSettlement {
+                token_transfer: TokenTransfer {
+                    token_program: &self.token_program,
+                    mint: &self.mint,
+                    memo_program: self.memo_program.as_ref(),
+                },
+                from: self.customer_token.to_account_info(),
+                authority: self.payer.to_account_info(),
+                signer_seeds: &[],
+                customer: Some(&mut self.customer_account),
+                merchant: &mut self.merchant_account,
+                merchant_stats: &mut self.merchant_stats,
+                merchant_token: Some(self.merchant_token.to_account_info()),
+                platform_treasury: self.platform_treasury.to_account_info(),
+                merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                merchant_vault: self.merchant_vault.as_deref_mut(),
+                merchant_vault_tokens: self.merchant_vault_tokens.as_deref_mut(),
+                merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+            },
+            fees,
+            &payment_id,
+        )?;
 
         // Create payment record
-        self.payment_account.payment_id = payment_id.clone();
-        self.payment_account.customer = self.payer.key();
-        self.payment_account.merchant = self.merchant_account.key();
-        self.payment_account.amount = amount;
-        self.payment_account.fee_amount = fee;
-        self.payment_account.merchant_amount = merchant_amount;
-        self.payment_account.status = PaymentStatus::Completed;
-        self.payment_account.created_at = Clock::get()?.unix_timestamp;
-        self.payment_account.refunded_at = None;
-        self.payment_account.bump = bumps.payment_account;
+        let mut payment = Payment::completed(
+            payment_id,
+            self.payer.key(),
+            self.merchant_account.key(),
+            self.mint.key(),
+            &fees,
+            transfer_fee_amount,
+            bumps.payment_account,
+        )?;
+        if fees.referral_fee > 0 {
+            payment.referrer = self.referrer.as_ref().map(|referrer| referrer.key());
+        }
+        self.payment_account.set_inner(payment);
 
         msg!(
-            "Payment {} processed: {} USDC to merchant {}, {} USDC fee to platform",
-            payment_id,
-            merchant_amount,
+            "Payment {} processed: {} to merchant {}, {} fee to platform, {} merchant fee (mint {})",
+            self.payment_account.payment_id,
+            fees.merchant_amount,
             self.merchant_account.merchant_id,
-            fee
+            fees.fee,
+            fees.merchant_fee,
+            self.mint.key()
         );
 
+        emit!(PaymentProcessed::new(self.payment_account.key(), &self.payment_account));
+
+        Ok(())
+    }
+}
+
+/// Grows a `Customer` account created by an older program version to the current layout;
+/// anyone may pay for it. Newer fields follow `bump` and start at zero.
+#[derive(Accounts)]
+pub struct MigrateCustomer<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: May not deserialize as `Customer` yet; owner and discriminator are checked in instruction logic
+    #[account(mut)]
+    pub customer_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigrateCustomer<'info> {
+    pub fn migrate_customer(&mut self) -> Result<()> {
+        let customer_info = self.customer_account.to_account_info();
+        check_migratable::<Customer>(&customer_info)?;
+
+        let new_len = 8 + Customer::INIT_SPACE;
+        let old_len = grow_account(
+            &customer_info,
+            &self.payer.to_account_info(),
+            &self.system_program.to_account_info(),
+            new_len,
+        )?;
+
+        msg!("Customer {} migrated from {} to {} bytes", customer_info.key(), old_len, old_len.max(new_len));
         Ok(())
     }
 }
 
-pub fn handler(
+pub fn process_payment_handler(
     ctx: Context<ProcessPayment>, 
     payment_id: String,
-    amount: u64
+    amount: u64,
+    expiry: i64,
 ) -> Result<()> {
-    ctx.accounts.process_payment(payment_id, amount, &ctx.bumps)
-}
\ No newline at end of file
+    ctx.accounts.process_payment(payment_id, amount, expiry, &ctx.bumps)
+}
+
+pub fn migrate_customer_handler(ctx: Context<MigrateCustomer>) -> Result<()> {
+    ctx.accounts.migrate_customer()
+}
@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, TokenAccount, Token};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::state::platform::Platform;
+use crate::state::AcceptedMint;
 use crate::errors::PaymentError;
+use crate::events::PlatformConfigured;
+use crate::utils::restricted_mint_extensions;
 
 #[derive(Accounts)]
 pub struct InitializePlatform<'info> {
@@ -18,27 +21,41 @@ pub struct InitializePlatform<'info> {
     )]
     pub platform_config: Account<'info, Platform>,
 
-    pub usdc_mint: Account<'info, Mint>,
+    pub usdc_mint: InterfaceAccount<'info, Mint>,
     
     #[account(
         init,
         payer = authority,
         token::mint = usdc_mint,
         token::authority = platform_config,
+        token::token_program = token_program,
         seeds = [b"platform_treasury".as_ref()],
         bump,
     )]
-    pub platform_treasury: Account<'info, TokenAccount>,
+    pub platform_treasury: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AcceptedMint::INIT_SPACE,
+        seeds = [AcceptedMint::SEED, usdc_mint.key().as_ref()],
+        bump,
+    )]
+    pub usdc_accepted_mint: Account<'info, AcceptedMint>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> InitializePlatform<'info> {
-    pub fn set_platform_config(&mut self, fee_bps: u64, min_payment_amount: u64, platform_bump: u8, treasury_bump: u8) -> Result<()> {
-        require!(fee_bps <= 1000, PaymentError::InvalidFeeBps);
+    pub fn set_platform_config(&mut self, fee_bps: u64, min_payment_amount: u64, bumps: &InitializePlatformBumps) -> Result<()> {
+        require!(fee_bps <= Platform::MAX_FEE_BPS, PaymentError::InvalidFeeBps);
         require!(min_payment_amount > 0, PaymentError::InvalidMinPaymentAmount);
         require!(self.usdc_mint.decimals == 6, PaymentError::InvalidUsdcMint);
+        require!(
+            restricted_mint_extensions(&self.usdc_mint.to_account_info())?.is_empty(),
+            PaymentError::RestrictedMintExtension
+        );
 
         self.platform_config.authority = self.authority.key();
         self.platform_config.treasury = self.platform_treasury.key();
@@ -46,20 +63,44 @@ impl<'info> InitializePlatform<'info> {
         self.platform_config.fee_bps = fee_bps;
         self.platform_config.min_payment_amount = min_payment_amount;
         self.platform_config.is_active = true;
-        self.platform_config.bump = platform_bump;
-        self.platform_config.treasury_bump = treasury_bump;
+        self.platform_config.bump = bumps.platform_config;
+        self.platform_config.treasury_bump = bumps.platform_treasury;
+        self.platform_config.pending_authority = None;
+        self.platform_config.dispute_window = 0;
+        self.platform_config.arbiter = None;
+        self.platform_config.fee_tiers = Vec::new();
+        self.platform_config.fixed_fee = 0;
+        self.platform_config.min_fee = 0;
+        self.platform_config.max_fee = 0;
+
+        // USDC is the first accepted mint and keeps the original treasury
+        self.usdc_accepted_mint.mint = self.usdc_mint.key();
+        self.usdc_accepted_mint.treasury = self.platform_treasury.key();
+        self.usdc_accepted_mint.min_payment_amount = min_payment_amount;
+        self.usdc_accepted_mint.decimals = self.usdc_mint.decimals;
+        self.usdc_accepted_mint.is_enabled = true;
+        self.usdc_accepted_mint.restricted_extensions_allowed = false;
+        self.usdc_accepted_mint.bump = bumps.usdc_accepted_mint;
 
         msg!("Platform initialized successfully with fee {}", fee_bps);
         msg!("Minimum payment amount set to {}", min_payment_amount);
         msg!("USDC mint set to {}", self.usdc_mint.key());
         msg!("Treasury token account created at {}", self.platform_treasury.key());
+
+        emit!(PlatformConfigured {
+            platform_config: self.platform_config.key(),
+            authority: self.platform_config.authority,
+            treasury: self.platform_config.treasury,
+            usdc_mint: self.platform_config.usdc_mint,
+            fee_bps,
+            min_payment_amount,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         
         Ok(())
     } 
 }
 
-pub fn handler(ctx: Context<InitializePlatform>, fee_bps: u64, min_payment_amount: u64) -> Result<()> {
-    let platform_bump = ctx.bumps.platform_config;
-    let treasury_bump = ctx.bumps.platform_treasury;
-    ctx.accounts.set_platform_config(fee_bps, min_payment_amount, platform_bump, treasury_bump)
+pub fn set_platform_config_handler(ctx: Context<InitializePlatform>, fee_bps: u64, min_payment_amount: u64) -> Result<()> {
+    ctx.accounts.set_platform_config(fee_bps, min_payment_amount, &ctx.bumps)
 }
@@ -5,6 +5,7 @@ use inco_lightning::ID as INCO_LIGHTNING_ID;
 
 use crate::state::PrivateReceipt;
 use crate::errors::PaymentError;
+use crate::events::PrivateReceiptIssued;
 
 /// Issue a private receipt for a payment with FHE-encrypted amount
 /// 
@@ -30,7 +31,7 @@ pub struct IssuePrivateReceipt<'info> {
         init,
         payer = customer,
         space = 8 + PrivateReceipt::INIT_SPACE,
-        seeds = [PrivateReceipt::SEED, payment_id.as_bytes()],
+        seeds = [PrivateReceipt::SEED, merchant.key().as_ref(), payment_id.as_bytes()],
         bump,
     )]
     pub private_receipt: Account<'info, PrivateReceipt>,
@@ -86,7 +87,8 @@ impl<'info> IssuePrivateReceipt<'info> {
         
         // Grant decryption access if allowance accounts are provided
         // This allows for simulation-first pattern: simulate without, then execute with allowances
-        if ctx.remaining_accounts.len() >= 2 {
+        let access_granted = ctx.remaining_accounts.len() >= 2;
+        if access_granted {
             let customer_allowance = &ctx.remaining_accounts[0];
             let merchant_allowance = &ctx.remaining_accounts[1];
             
@@ -144,6 +146,16 @@ impl<'info> IssuePrivateReceipt<'info> {
         msg!("   Handle: {}", handle);
         msg!("   Customer {} granted decrypt access", ctx.accounts.customer.key());
         msg!("   Merchant {} granted decrypt access", ctx.accounts.merchant.key());
+
+        emit!(PrivateReceiptIssued {
+            receipt: receipt.key(),
+            payment_id: receipt.payment_id.clone(),
+            customer: receipt.customer,
+            merchant: receipt.merchant,
+            encrypted_amount_handle: handle,
+            access_granted,
+            timestamp: receipt.issued_at,
+        });
         
         Ok(())
     }
@@ -0,0 +1,287 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
+
+use crate::state::{AcceptedMint, Merchant, Platform, Referrer};
+use crate::errors::PaymentError;
+use crate::events::{MerchantReferrerSet, ReferralFeesClaimed, ReferrerUpdated};
+use crate::utils::{TokenTransfer, BPS_DENOMINATOR};
+
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RegisterReferrer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Referrer::INIT_SPACE,
+        seeds = [Referrer::SEED, wallet.as_ref()],
+        bump,
+    )]
+    pub referrer: Account<'info, Referrer>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RegisterReferrer<'info> {
+    pub fn register_referrer(&mut self, wallet: Pubkey, share_bps: u16, bump: u8) -> Result<()> {
+        require!(share_bps as u64 <= BPS_DENOMINATOR, PaymentError::InvalidReferralShare);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.referrer.set_inner(Referrer {
+            wallet,
+            share_bps,
+            is_active: true,
+            created_at: now,
+            bump,
+        });
+
+        msg!("Referrer {} registered with {} bps of the platform fee", wallet, share_bps);
+
+        emit!(ReferrerUpdated {
+            referrer: self.referrer.key(),
+            wallet,
+            share_bps,
+            is_active: true,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateReferrer<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        mut,
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Account<'info, Referrer>,
+}
+
+impl<'info> UpdateReferrer<'info> {
+    pub fn update_referrer(&mut self, share_bps: Option<u16>, is_active: Option<bool>) -> Result<()> {
+        let referrer = &mut self.referrer;
+
+        if let Some(share_bps) = share_bps {
+            require!(share_bps as u64 <= BPS_DENOMINATOR, PaymentError::InvalidReferralShare);
+            referrer.share_bps = share_bps;
+        }
+
+        if let Some(is_active) = is_active {
+            referrer.is_active = is_active;
+        }
+
+        msg!("Referrer {} updated: {} bps, active {}", referrer.wallet, referrer.share_bps, referrer.is_active);
+
+        emit!(ReferrerUpdated {
+            referrer: referrer.key(),
+            wallet: referrer.wallet,
+            share_bps: referrer.share_bps,
+            is_active: referrer.is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetMerchantReferrer<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+        constraint = referrer.is_active @ PaymentError::InvalidReferrer,
+    )]
+    pub referrer: Account<'info, Referrer>,
+}
+
+impl<'info> SetMerchantReferrer<'info> {
+    /// Attributes the merchant to a referrer. Sticky: it cannot be changed afterwards.
+    pub fn set_merchant_referrer(&mut self) -> Result<()> {
+        require!(self.merchant_account.referrer.is_none(), PaymentError::ReferrerAlreadySet);
+
+        self.merchant_account.referrer = Some(self.referrer.key());
+
+        msg!("Merchant {} referred by {}", self.merchant_account.merchant_id, self.referrer.wallet);
+
+        emit!(MerchantReferrerSet {
+            merchant: self.merchant_account.key(),
+            referrer: self.referrer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct OpenReferralVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Account<'info, Referrer>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    #[account(
+        init,
+        payer = payer,
+        seeds = [Referrer::VAULT_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = referrer,
+        token::token_program = token_program,
+    )]
+    pub referral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> OpenReferralVault<'info> {
+    pub fn open_referral_vault(&mut self) -> Result<()> {
+        msg!(
+            "Referral vault {} opened for referrer {} in mint {}",
+            self.referral_vault.key(),
+            self.referrer.wallet,
+            self.mint.key()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralFees<'info> {
+    #[account(mut)]
+    pub wallet: Signer<'info>,
+
+    #[account(
+        seeds = [Referrer::SEED, wallet.key().as_ref()],
+        bump = referrer.bump,
+        has_one = wallet @ PaymentError::Unauthorized,
+    )]
+    pub referrer: Account<'info, Referrer>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [Referrer::VAULT_SEED, referrer.key().as_ref(), mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = referrer,
+        token::token_program = token_program,
+    )]
+    pub referral_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = wallet,
+        associated_token::token_program = token_program,
+    )]
+    pub wallet_token: InterfaceAccount<'info, TokenAccount>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub memo_program: Option<Program<'info, Memo>>,
+}
+
+impl<'info> ClaimReferralFees<'info> {
+    pub fn claim_referral_fees(&mut self) -> Result<()> {
+        let balance = self.referral_vault.amount;
+        require!(balance > 0, PaymentError::NoFeesToClaim);
+
+        let wallet = self.wallet.key();
+        let seeds = &[Referrer::SEED, wallet.as_ref(), &[self.referrer.bump]];
+
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
+        };
+        token_transfer.transfer(
+            self.referral_vault.to_account_info(),
+            self.wallet_token.to_account_info(),
+            self.referrer.to_account_info(),
+            &[&seeds[..]],
+            balance,
+            "referral fee claim",
+        )?;
+
+        msg!("Referrer {} claimed {} in mint {}", wallet, balance, self.mint.key());
+
+        emit!(ReferralFeesClaimed {
+            referrer: self.referrer.key(),
+            wallet,
+            mint: self.mint.key(),
+            amount: balance,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn register_handler(ctx: Context<RegisterReferrer>, wallet: Pubkey, share_bps: u16) -> Result<()> {
+    let bump = ctx.bumps.referrer;
+    ctx.accounts.register_referrer(wallet, share_bps, bump)
+}
+
+pub fn update_referrer_handler(ctx: Context<UpdateReferrer>, share_bps: Option<u16>, is_active: Option<bool>) -> Result<()> {
+    ctx.accounts.update_referrer(share_bps, is_active)
+}
+
+pub fn set_merchant_referrer_handler(ctx: Context<SetMerchantReferrer>) -> Result<()> {
+    ctx.accounts.set_merchant_referrer()
+}
+
+pub fn open_vault_handler(ctx: Context<OpenReferralVault>) -> Result<()> {
+    ctx.accounts.open_referral_vault()
+}
+
+pub fn claim_handler(ctx: Context<ClaimReferralFees>) -> Result<()> {
+    ctx.accounts.claim_referral_fees()
+}
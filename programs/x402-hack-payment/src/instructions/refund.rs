@@ -1,12 +1,17 @@
 use anchor_lang::prelude::*;
 
 use crate::state::platform::Platform;
+use crate::state::AcceptedMint;
 use crate::state::merchant::Merchant;
+use crate::state::{Customer, Invoice, MerchantReserve, MerchantStats, MerchantVault};
 use crate::state::payment::{Payment, PaymentStatus};
 
 use crate::errors::PaymentError;
-use anchor_spl::token::{Transfer, transfer, Mint, TokenAccount, Token};
+use crate::events::{InvoiceStatusChanged, PaymentRefunded};
+use crate::utils::{check_migratable, grow_account, TokenTransfer};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
 
 #[derive(Accounts)]
 #[instruction()]
@@ -23,9 +28,10 @@ pub struct RefundPayment<'info> {
     
     #[account(
         mut,
-        seeds = [Payment::SEED, payment_account.payment_id.as_bytes()],
-        bump = payment_account.bump,
-        constraint = payment_account.status == PaymentStatus::Completed @ PaymentError::PaymentAlreadyRefunded,
+        constraint = payment_account.is_address(&payment_account.key()) @ PaymentError::InvalidPaymentId,
+        constraint = payment_account.status == PaymentStatus::Completed
+            || payment_account.status == PaymentStatus::Released
+            || payment_account.status == PaymentStatus::PartiallyRefunded @ PaymentError::PaymentAlreadyRefunded,
         constraint = payment_account.merchant == merchant_account.key() @ PaymentError::RefundNotAuthorized,
     )]
     pub payment_account: Account<'info, Payment>,
@@ -37,6 +43,19 @@ pub struct RefundPayment<'info> {
         constraint = merchant_account.authority == merchant_authority.key() @ PaymentError::RefundNotAuthorized,
     )]
     pub merchant_account: Account<'info, Merchant>,
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+    
+    #[account(
+        mut,
+        seeds = [Customer::SEED, payment_account.customer.as_ref()],
+        bump = customer_account.bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
     
     /// CHECK: The original customer who made the payment
     #[account(
@@ -45,97 +64,313 @@ pub struct RefundPayment<'info> {
     pub customer: UncheckedAccount<'info>,
     
     #[account(
-        constraint = usdc_mint.key() == platform_config.usdc_mint @ PaymentError::InvalidTokenMint
+        constraint = mint.key() == payment_account.mint @ PaymentError::InvalidTokenMint
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    /// Refunds are allowed even if the mint has since been disabled
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
     )]
-    pub usdc_mint: Account<'info, Mint>,
+    pub accepted_mint: Account<'info, AcceptedMint>,
     
     #[account(
         mut,
-        associated_token::mint = usdc_mint,
+        associated_token::mint = mint,
         associated_token::authority = customer,
+        associated_token::token_program = token_program,
     )]
-    pub customer_usdc: Account<'info, TokenAccount>,
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
     
     #[account(
         mut,
-        associated_token::mint = usdc_mint,
-        associated_token::authority = merchant_account.settlement_wallet,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// When passed, the merchant's share is drawn from its held and available vault balance first
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Required when the payment reserved part of the merchant share; that part of the refund
+    /// comes out of the reserve while it is still held
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
     )]
-    pub merchant_usdc: Account<'info, TokenAccount>,
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
     
+    /// Required when the payment was applied to an invoice; the refund comes back off its balance
     #[account(
         mut,
-        seeds = [Platform::TREASURY_SEED],
-        bump = platform_config.treasury_bump,
-        token::mint = usdc_mint,
+        seeds = [Invoice::SEED, merchant_account.key().as_ref(), invoice.invoice_id.as_bytes()],
+        bump = invoice.bump,
+    )]
+    pub invoice: Option<Box<Account<'info, Invoice>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
         token::authority = platform_config,
+        token::token_program = token_program,
     )]
-    pub platform_treasury_usdc: Account<'info, TokenAccount>,
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
     
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
+    /// Required when the customer's token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> RefundPayment<'info> {
     pub fn refund(&mut self) -> Result<()> {
-        let merchant_amount = self.payment_account.merchant_amount;
-        let fee_amount = self.payment_account.fee_amount;
+        let remaining = self.payment_account.remaining_amount();
+        self.refund_amount(remaining)
+    }
+
+    pub fn refund_partial(&mut self, amount: u64) -> Result<()> {
+        self.refund_amount(amount)
+    }
+
+    fn refund_amount(&mut self, amount: u64) -> Result<()> {
+        require!(amount > 0, PaymentError::InvalidRefundAmount);
+        require!(
+            amount <= self.payment_account.remaining_amount(),
+            PaymentError::RefundExceedsRemaining
+        );
+
+        // Split the refund proportionally between merchant and treasury fee. The treasury only
+        // returns the part of the platform fee it kept; neither the referrer's cut nor the
+        // merchant-side fee is clawed back, so the merchant covers those shares.
+        let refunded_total = self.payment_account
+            .refunded_amount
+            .checked_add(amount)
+            .ok_or(PaymentError::CalculationError)?;
+        let referral_fee_amount = self.payment_account
+            .refund_share(self.payment_account.referral_fee_amount, amount)
+            .ok_or(PaymentError::CalculationError)?;
+        let fee_amount = self.payment_account
+            .refund_share(self.payment_account.fee_amount, amount)
+            .and_then(|fee| fee.checked_sub(referral_fee_amount))
+            .ok_or(PaymentError::CalculationError)?;
+        let merchant_amount = amount
+            .checked_sub(fee_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        let merchant_volume = self.payment_account
+            .refund_share(self.payment_account.merchant_amount, amount)
+            .ok_or(PaymentError::CalculationError)?;
+        let reserve_amount = self.payment_account
+            .refund_share(self.payment_account.reserve_amount, amount)
+            .ok_or(PaymentError::CalculationError)?;
         
-        // Transfer merchant amount back to customer
-        let transfer_merchant_accounts = Transfer {
-            from: self.merchant_usdc.to_account_info(),
-            to: self.customer_usdc.to_account_info(),
-            authority: self.merchant_authority.to_account_info(),
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
         };
-        let transfer_merchant_ctx = CpiContext::new(
-            self.token_program.to_account_info(),
-            transfer_merchant_accounts,
-        );
-        transfer(transfer_merchant_ctx, merchant_amount)?;
+        let memo = self.payment_account.payment_id.clone();
+
+        // Token-2022 transfer fees withheld on the way back to the customer
+        let mut transfer_fee_amount = 0u64;
+
+        let merchant = self.merchant_account.key();
+        let mint = self.mint.key();
+
+        // The payment's reserved share comes back out of the reserve while it is still held
+        let mut from_reserve = 0;
+        if reserve_amount > 0 {
+            let reserve = self.merchant_reserve.as_mut().ok_or(PaymentError::MissingMerchantReserve)?;
+            let tokens = self.merchant_reserve_tokens.as_ref().ok_or(PaymentError::MissingMerchantReserve)?;
+            require_keys_eq!(tokens.key(), reserve.vault, PaymentError::MissingMerchantReserve);
+
+            from_reserve = reserve.debit(reserve_amount);
+
+            let seeds = &[MerchantReserve::SEED, merchant.as_ref(), mint.as_ref(), &[reserve.bump]];
+            transfer_fee_amount += token_transfer.transfer(
+                tokens.to_account_info(),
+                self.customer_token.to_account_info(),
+                reserve.to_account_info(),
+                &[&seeds[..]],
+                from_reserve,
+                &memo,
+            )?;
+        }
+        let merchant_amount_due = merchant_amount - from_reserve;
+
+        // Then from the merchant vault, and only then the settlement account
+        let mut from_vault = 0;
+        if let Some(vault) = self.merchant_vault.as_mut() {
+            let tokens = self.merchant_vault_tokens.as_ref().ok_or(PaymentError::MissingMerchantVault)?;
+            require_keys_eq!(tokens.key(), vault.vault, PaymentError::MissingMerchantVault);
+
+            vault.mature(Clock::get()?.unix_timestamp)?;
+            from_vault = vault.debit(merchant_amount_due);
+
+            let seeds = &[MerchantVault::SEED, merchant.as_ref(), mint.as_ref(), &[vault.bump]];
+            transfer_fee_amount += token_transfer.transfer(
+                tokens.to_account_info(),
+                self.customer_token.to_account_info(),
+                vault.to_account_info(),
+                &[&seeds[..]],
+                from_vault,
+                &memo,
+            )?;
+        }
+        transfer_fee_amount += token_transfer.transfer(
+            self.merchant_token.to_account_info(),
+            self.customer_token.to_account_info(),
+            self.merchant_authority.to_account_info(),
+            &[],
+            merchant_amount_due - from_vault,
+            &memo,
+        )?;
         
         // Transfer fee back from treasury to customer (requires PDA signer)
         let seeds = &[Platform::SEED, &[self.platform_config.bump]];
-        let signer = &[&seeds[..]];
-        
-        let transfer_fee_accounts = Transfer {
-            from: self.platform_treasury_usdc.to_account_info(),
-            to: self.customer_usdc.to_account_info(),
-            authority: self.platform_config.to_account_info(),
-        };
-        let transfer_fee_ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
-            transfer_fee_accounts,
-            signer,
-        );
-        transfer(transfer_fee_ctx, fee_amount)?;
+        transfer_fee_amount += token_transfer.transfer(
+            self.platform_treasury.to_account_info(),
+            self.customer_token.to_account_info(),
+            self.platform_config.to_account_info(),
+            &[&seeds[..]],
+            fee_amount,
+            &memo,
+        )?;
         
+        let net_amount = amount
+            .checked_sub(transfer_fee_amount)
+            .ok_or(PaymentError::CalculationError)?;
+
         // Update payment status
-        self.payment_account.status = PaymentStatus::Refunded;
-        self.payment_account.refunded_at = Some(Clock::get()?.unix_timestamp);
+        let fully_refunded = refunded_total == self.payment_account.amount;
+        self.payment_account.refunded_amount = refunded_total;
+        self.payment_account.refunded_net_amount = self.payment_account
+            .refunded_net_amount
+            .checked_add(net_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        self.payment_account.status = if fully_refunded {
+            PaymentStatus::Refunded
+        } else {
+            PaymentStatus::PartiallyRefunded
+        };
+        let now = Clock::get()?.unix_timestamp;
+        self.payment_account.refunded_at = Some(now);
+
+        if let Some(invoice_key) = self.payment_account.invoice {
+            let invoice = self.invoice.as_mut().ok_or(PaymentError::MissingInvoice)?;
+            require_keys_eq!(invoice.key(), invoice_key, PaymentError::MissingInvoice);
+            let previous_status = invoice.status;
+            invoice.record_refund(amount, now)?;
+            if invoice.status != previous_status {
+                emit!(InvoiceStatusChanged {
+                    invoice: invoice_key,
+                    status: invoice.status,
+                    timestamp: now,
+                });
+            }
+        }
         
-        // Update merchant stats
-        self.merchant_account.transaction_count = self.merchant_account
-            .transaction_count
-            .saturating_sub(1);
-        self.merchant_account.volume = self.merchant_account
-            .volume
-            .saturating_sub(merchant_amount);
-        self.merchant_account.total_fees = self.merchant_account
-            .total_fees
-            .saturating_sub(fee_amount);
+        // Refunds are counted separately; payment counters are left untouched
+        self.merchant_account.record_refund(merchant_volume, fee_amount)?;
+        self.merchant_stats.record_refund(amount, merchant_volume)?;
+        self.customer_account.record_refund(amount)?;
         
         msg!(
-            "Payment {} refunded: {} USDC to customer {}",
+            "Payment {} refunded: {} to customer {}, {} after transfer fees ({} of {} refunded)",
             self.payment_account.payment_id,
-            self.payment_account.amount,
-            self.payment_account.customer
+            amount,
+            self.payment_account.customer,
+            net_amount,
+            refunded_total,
+            self.payment_account.amount
         );
+
+        emit!(PaymentRefunded {
+            payment: self.payment_account.key(),
+            payment_id: self.payment_account.payment_id.clone(),
+            customer: self.payment_account.customer,
+            merchant: self.merchant_account.key(),
+            amount,
+            fee_amount,
+            merchant_amount,
+            refunded_amount: refunded_total,
+            transfer_fee_amount,
+            net_amount,
+            fully_refunded,
+            timestamp: now,
+        });
         
         Ok(())
     }
 }
 
-pub fn handler(ctx: Context<RefundPayment>) -> Result<()> {
+/// Grows a `Payment` made by an older program version to the current layout so it can be
+/// refunded; anyone may pay for it. Payments from before multi-mint support get the USDC mint.
+#[derive(Accounts)]
+pub struct MigratePayment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    /// CHECK: May not deserialize as `Payment` yet; owner and discriminator are checked in instruction logic
+    #[account(mut)]
+    pub payment_account: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigratePayment<'info> {
+    pub fn migrate_payment(&mut self) -> Result<()> {
+        let payment_info = self.payment_account.to_account_info();
+        check_migratable::<Payment>(&payment_info)?;
+
+        let new_len = 8 + Payment::INIT_SPACE;
+        let old_len = grow_account(
+            &payment_info,
+            &self.payer.to_account_info(),
+            &self.system_program.to_account_info(),
+            new_len,
+        )?;
+
+        let mut payment = Payment::try_deserialize(&mut &payment_info.try_borrow_data()?[..])?;
+        if payment.is_legacy() {
+            payment.upgrade_legacy(self.platform_config.usdc_mint);
+            payment.try_serialize(&mut &mut payment_info.try_borrow_mut_data()?[..])?;
+        }
+
+        msg!("Payment {} migrated from {} to {} bytes", payment.payment_id, old_len, old_len.max(new_len));
+        Ok(())
+    }
+}
+
+pub fn refund_handler(ctx: Context<RefundPayment>) -> Result<()> {
     ctx.accounts.refund()
-}
\ No newline at end of file
+}
+
+pub fn partial_handler(ctx: Context<RefundPayment>, amount: u64) -> Result<()> {
+    ctx.accounts.refund_partial(amount)
+}
+
+pub fn migrate_payment_handler(ctx: Context<MigratePayment>) -> Result<()> {
+    ctx.accounts.migrate_payment()
+}
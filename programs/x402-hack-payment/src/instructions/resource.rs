@@ -0,0 +1,258 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::Memo;
+
+use crate::state::{
+    AccessReceipt, AcceptedMint, Customer, Merchant, MerchantReserve, MerchantStats, MerchantVault, Platform, Referrer,
+};
+use crate::errors::PaymentError;
+use crate::events::ResourcePaid;
+use crate::utils::{apply_referral, settle_payment, verify_ed25519_signature, FeeBreakdown, PaymentSettlement, TokenTransfer};
+
+#[derive(Accounts)]
+#[instruction(resource_hash: [u8; 32], amount: u64, nonce: u64)]
+pub struct PayForResource<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Box<Account<'info, Platform>>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    /// Fails to initialize if the quote's nonce was already redeemed
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccessReceipt::INIT_SPACE,
+        seeds = [AccessReceipt::SEED, merchant_account.key().as_ref(), &nonce.to_le_bytes()],
+        bump,
+    )]
+    pub access_receipt: Box<Account<'info, AccessReceipt>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Customer::INIT_SPACE,
+        seeds = [Customer::SEED, payer.key().as_ref()],
+        bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the merchant share instead of `merchant_token` while the merchant has a hold period
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the merchant-side fee; required when `merchant_account.fee > 0`
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
+        token::authority = platform_config,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required when the merchant has a referrer; otherwise optionally credits this referrer
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Option<Box<Account<'info, Referrer>>>,
+
+    /// Referrer-owned token account for `mint`, required when a referral fee is due
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Instructions sysvar, read to find the merchant's Ed25519 quote signature
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    /// Required when a destination token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> PayForResource<'info> {
+    pub fn pay_for_resource(
+        &mut self,
+        resource_hash: [u8; 32],
+        amount: u64,
+        nonce: u64,
+        expiry: i64,
+        bumps: &PayForResourceBumps,
+    ) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        require!(amount >= self.accepted_mint.min_payment_amount, PaymentError::PaymentBelowMinimum);
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(now <= expiry, PaymentError::QuoteExpired);
+
+        let message = AccessReceipt::quote_message(
+            &self.merchant_account.key(),
+            &self.payer.key(),
+            &resource_hash,
+            &self.mint.key(),
+            amount,
+            nonce,
+            expiry,
+        );
+        let signer = verify_ed25519_signature(&self.instructions, &message)?;
+        require!(self.merchant_account.is_signer(&signer), PaymentError::InvalidSignature);
+
+        self.platform_config
+            .limits_for(&self.merchant_account)
+            .check(amount, &self.customer_account, &self.merchant_account, now)?;
+
+        let fees = apply_referral(
+            FeeBreakdown::calculate(&self.platform_config, &self.merchant_account, &self.merchant_stats, amount)?,
+            &self.merchant_account,
+            &self.mint.key(),
+            self.referrer.as_deref(),
+            self.referral_vault.as_deref(),
+        )?;
+        self.customer_account.init_if_new(self.payer.key(), now, bumps.customer_account);
+
+        let (fees, _) = settle_payment(
+            PaymentSettlement {
+                token_transfer: TokenTransfer {
+                    token_program: &self.token_program,
+                    mint: &self.mint,
+                    memo_program: self.memo_program.as_ref(),
+                },
+                from: self.customer_token.to_account_info(),
+                authority: self.payer.to_account_info(),
+                signer_seeds: &[],
+                customer: Some(&mut self.customer_account),
+                merchant: &mut self.merchant_account,
+                merchant_stats: &mut self.merchant_stats,
+                merchant_token: Some(self.merchant_token.to_account_info()),
+                platform_treasury: self.platform_treasury.to_account_info(),
+                merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                merchant_vault: self.merchant_vault.as_deref_mut(),
+                merchant_vault_tokens: self.merchant_vault_tokens.as_deref_mut(),
+                merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+            },
+            fees,
+            &format!("x402-{}", nonce),
+        )?;
+
+        self.access_receipt.set_inner(AccessReceipt {
+            merchant: self.merchant_account.key(),
+            customer: self.payer.key(),
+            resource_hash,
+            mint: self.mint.key(),
+            amount,
+            nonce,
+            quote_expiry: expiry,
+            paid_at: now,
+            bump: bumps.access_receipt,
+        });
+
+        msg!(
+            "Resource quote {} from merchant {} paid: {}",
+            nonce,
+            self.merchant_account.merchant_id,
+            amount
+        );
+
+        emit!(ResourcePaid {
+            access_receipt: self.access_receipt.key(),
+            merchant: self.merchant_account.key(),
+            customer: self.payer.key(),
+            resource_hash,
+            mint: self.mint.key(),
+            amount,
+            fee_amount: fees.fee,
+            nonce,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn pay_resource_handler(
+    ctx: Context<PayForResource>,
+    resource_hash: [u8; 32],
+    amount: u64,
+    nonce: u64,
+    expiry: i64,
+) -> Result<()> {
+    ctx.accounts.pay_for_resource(resource_hash, amount, nonce, expiry, &ctx.bumps)
+}
@@ -0,0 +1,365 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions as instructions_sysvar;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::memo::Memo;
+
+use crate::state::{
+    AcceptedMint, Customer, Merchant, MerchantReserve, MerchantStats, Payment, PaymentSplit, Platform, Referrer,
+    SplitConfig, SplitRecipient, SplitShare,
+};
+use crate::errors::PaymentError;
+use crate::events::{PaymentProcessed, PaymentSplitRecorded, SplitConfigUpdated};
+use crate::utils::{apply_referral, calculate_bps, settle_payment, verify_ed25519_signature, FeeBreakdown, PaymentSettlement, TokenTransfer};
+
+#[derive(Accounts)]
+pub struct SetSplitConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + SplitConfig::INIT_SPACE,
+        seeds = [SplitConfig::SEED, merchant_account.key().as_ref()],
+        bump,
+    )]
+    pub split_config: Account<'info, SplitConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetSplitConfig<'info> {
+    pub fn set_split_config(&mut self, recipients: Vec<SplitRecipient>, bump: u8) -> Result<()> {
+        require!(SplitConfig::is_valid(&recipients), PaymentError::InvalidSplitConfig);
+        require!(self.merchant_account.hold_period.is_none(), PaymentError::HeldSettlementUnsupported);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.split_config.set_inner(SplitConfig {
+            merchant: self.merchant_account.key(),
+            recipients,
+            updated_at: now,
+            bump,
+        });
+
+        msg!(
+            "Merchant {} split set across {} recipients",
+            self.merchant_account.merchant_id,
+            self.split_config.recipients.len()
+        );
+
+        emit!(SplitConfigUpdated {
+            merchant: self.merchant_account.key(),
+            split_config: self.split_config.key(),
+            recipients: self.split_config.recipients.clone(),
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(payment_id: String)]
+pub struct ProcessSplitPayment<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Box<Account<'info, Platform>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Payment::INIT_SPACE,
+        seeds = [Payment::SEED, merchant_account.key().as_ref(), payment_id.as_bytes()],
+        bump,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentSplit::INIT_SPACE,
+        seeds = [PaymentSplit::SEED, payment_account.key().as_ref()],
+        bump,
+    )]
+    pub payment_split: Box<Account<'info, PaymentSplit>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + Customer::INIT_SPACE,
+        seeds = [Customer::SEED, payer.key().as_ref()],
+        bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    #[account(
+        mut,
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    #[account(
+        seeds = [SplitConfig::SEED, merchant_account.key().as_ref()],
+        bump = split_config.bump,
+    )]
+    pub split_config: Box<Account<'info, SplitConfig>>,
+
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = payer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the merchant-side fee; required when `merchant_account.fee > 0`
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
+        token::authority = platform_config,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required when the merchant has a referrer; otherwise optionally credits this referrer
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Option<Box<Account<'info, Referrer>>>,
+
+    /// Referrer-owned token account for `mint`, required when a referral fee is due
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: Instructions sysvar, read to find the merchant's Ed25519 intent signature
+    #[account(address = instructions_sysvar::ID)]
+    pub instructions: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Required when a destination token account enforces incoming memos
+    pub memo_program: Option<Program<'info, Memo>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ProcessSplitPayment<'info> {
+    /// Like `process_payment`, but the merchant's share is divided per `split_config`.
+    /// `recipient_tokens` holds one token account per recipient, in config order.
+    pub fn process_split_payment(
+        &mut self,
+        payment_id: String,
+        amount: u64,
+        expiry: i64,
+        recipient_tokens: &'info [AccountInfo<'info>],
+        bumps: &ProcessSplitPaymentBumps,
+    ) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        // Recipients other than the merchant can't be paid through its vault
+        require!(self.merchant_account.hold_period.is_none(), PaymentError::HeldSettlementUnsupported);
+        require!(amount >= self.accepted_mint.min_payment_amount, PaymentError::PaymentBelowMinimum);
+        require!(!payment_id.is_empty() && payment_id.len() <= 64, PaymentError::InvalidPaymentId);
+        require!(Clock::get()?.unix_timestamp <= expiry, PaymentError::PaymentIntentExpired);
+
+        let message = Payment::intent_message(
+            &self.merchant_account.key(),
+            &self.mint.key(),
+            amount,
+            expiry,
+            &payment_id,
+        );
+        let signer = verify_ed25519_signature(&self.instructions, &message)?;
+        require!(self.merchant_account.is_signer(&signer), PaymentError::InvalidSignature);
+
+        let recipients = &self.split_config.recipients;
+        require!(recipient_tokens.len() == recipients.len(), PaymentError::InvalidSplitRecipient);
+
+        self.platform_config
+            .limits_for(&self.merchant_account)
+            .check(amount, &self.customer_account, &self.merchant_account, Clock::get()?.unix_timestamp)?;
+
+        let fees = apply_referral(
+            FeeBreakdown::calculate(&self.platform_config, &self.merchant_account, &self.merchant_stats, amount)?,
+            &self.merchant_account,
+            &self.mint.key(),
+            self.referrer.as_deref(),
+            self.referral_vault.as_deref(),
+        )?;
+        self.customer_account.init_if_new(self.payer.key(), Clock::get()?.unix_timestamp, bumps.customer_account);
+
+        // Recipients are paid the merchant share below, in config order
+        let (fees, mut transfer_fee_amount) = settle_payment(
+            PaymentSettlement {
+                token_transfer: TokenTransfer {
+                    token_program: &self.token_program,
+                    mint: &self.mint,
+                    memo_program: self.memo_program.as_ref(),
+                },
+                from: self.customer_token.to_account_info(),
+                authority: self.payer.to_account_info(),
+                signer_seeds: &[],
+                customer: Some(&mut self.customer_account),
+                merchant: &mut self.merchant_account,
+                merchant_stats: &mut self.merchant_stats,
+                merchant_token: None,
+                platform_treasury: self.platform_treasury.to_account_info(),
+                merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                merchant_vault: None,
+                merchant_vault_tokens: None,
+                merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+            },
+            fees,
+            &payment_id,
+        )?;
+
+        let token_transfer = TokenTransfer {
+            token_program: &self.token_program,
+            mint: &self.mint,
+            memo_program: self.memo_program.as_ref(),
+        };
+
+        // The last recipient absorbs rounding dust so the shares sum to the unreserved merchant share
+        let payout = fees.merchant_amount
+            .checked_sub(fees.reserve)
+            .ok_or(PaymentError::CalculationError)?;
+        let mut shares = Vec::with_capacity(recipients.len());
+        let mut distributed = 0u64;
+        for (i, (recipient, token_info)) in recipients.iter().zip(recipient_tokens).enumerate() {
+            require!(token_info.is_writable, PaymentError::InvalidSplitRecipient);
+            require_keys_eq!(*token_info.owner, self.token_program.key(), PaymentError::InvalidSplitRecipient);
+            let token_account = InterfaceAccount::<TokenAccount>::try_from(token_info)?;
+            require_keys_eq!(token_account.owner, recipient.wallet, PaymentError::InvalidSplitRecipient);
+            require_keys_eq!(token_account.mint, self.mint.key(), PaymentError::InvalidSplitRecipient);
+
+            let share = if i + 1 == recipients.len() {
+                payout
+                    .checked_sub(distributed)
+                    .ok_or(PaymentError::CalculationError)?
+            } else {
+                calculate_bps(payout, recipient.bps as u64)?
+            };
+            distributed = distributed
+                .checked_add(share)
+                .ok_or(PaymentError::CalculationError)?;
+
+            transfer_fee_amount += token_transfer.transfer(
+                self.customer_token.to_account_info(),
+                token_info.clone(),
+                self.payer.to_account_info(),
+                &[],
+                share,
+                &payment_id,
+            )?;
+
+            shares.push(SplitShare {
+                wallet: recipient.wallet,
+                token_account: token_info.key(),
+                amount: share,
+            });
+        }
+
+        let mut payment = Payment::completed(
+            payment_id,
+            self.payer.key(),
+            self.merchant_account.key(),
+            self.mint.key(),
+            &fees,
+            transfer_fee_amount,
+            bumps.payment_account,
+        )?;
+        if fees.referral_fee > 0 {
+            payment.referrer = self.referrer.as_ref().map(|referrer| referrer.key());
+        }
+        self.payment_account.set_inner(payment);
+
+        self.payment_split.set_inner(PaymentSplit {
+            payment: self.payment_account.key(),
+            shares,
+            bump: bumps.payment_split,
+        });
+
+        msg!(
+            "Split payment {} processed: {} across {} recipients of merchant {}, {} fee to platform",
+            self.payment_account.payment_id,
+            fees.merchant_amount,
+            self.payment_split.shares.len(),
+            self.merchant_account.merchant_id,
+            fees.fee
+        );
+
+        emit!(PaymentProcessed::new(self.payment_account.key(), &self.payment_account));
+        emit!(PaymentSplitRecorded {
+            payment: self.payment_account.key(),
+            payment_split: self.payment_split.key(),
+            shares: self.payment_split.shares.clone(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn set_config_handler(ctx: Context<SetSplitConfig>, recipients: Vec<SplitRecipient>) -> Result<()> {
+    let bump = ctx.bumps.split_config;
+    ctx.accounts.set_split_config(recipients, bump)
+}
+
+pub fn payment_handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ProcessSplitPayment<'info>>,
+    payment_id: String,
+    amount: u64,
+    expiry: i64,
+) -> Result<()> {
+    ctx.accounts.process_split_payment(payment_id, amount, expiry, ctx.remaining_accounts, &ctx.bumps)
+}
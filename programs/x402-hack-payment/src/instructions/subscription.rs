@@ -0,0 +1,723 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use anchor_spl::memo::Memo;
+
+use crate::state::{
+    AcceptedMint, Customer, Delegation, Merchant, MerchantReserve, MerchantStats, MerchantVault, Payment, Platform,
+    Referrer, Subscription, SubscriptionPlan, SubscriptionStatus,
+};
+use crate::errors::PaymentError;
+use crate::events::{
+    PaymentProcessed, SubscriptionCharged, SubscriptionCreated, SubscriptionPlanUpdated, SubscriptionStatusChanged,
+};
+use crate::utils::{
+    apply_referral, approve_delegate, reduce_delegate, settle_payment, FeeBreakdown, PaymentSettlement, TokenTransfer,
+};
+
+#[derive(Accounts)]
+#[instruction(plan_id: String)]
+pub struct CreateSubscriptionPlan<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SubscriptionPlan::INIT_SPACE,
+        seeds = [SubscriptionPlan::SEED, merchant_account.key().as_ref(), plan_id.as_bytes()],
+        bump,
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, accepted_mint.mint.as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Account<'info, AcceptedMint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> CreateSubscriptionPlan<'info> {
+    pub fn create_subscription_plan(
+        &mut self,
+        plan_id: String,
+        amount: u64,
+        interval: i64,
+        trial_period: i64,
+        max_cycles: u32,
+        bump: u8,
+    ) -> Result<()> {
+        require!(!plan_id.is_empty() && plan_id.len() <= 32, PaymentError::InvalidPlanId);
+        require!(amount >= self.accepted_mint.min_payment_amount, PaymentError::PaymentBelowMinimum);
+        require!(interval > 0 && trial_period >= 0, PaymentError::InvalidSubscriptionInterval);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.plan.set_inner(SubscriptionPlan {
+            merchant: self.merchant_account.key(),
+            plan_id,
+            mint: self.accepted_mint.mint,
+            amount,
+            interval,
+            trial_period,
+            max_cycles,
+            is_active: true,
+            created_at: now,
+            bump,
+        });
+
+        msg!(
+            "Plan {} created for merchant {}: {} every {}s",
+            self.plan.plan_id,
+            self.merchant_account.merchant_id,
+            amount,
+            interval
+        );
+
+        emit!(SubscriptionPlanUpdated {
+            plan: self.plan.key(),
+            merchant: self.plan.merchant,
+            plan_id: self.plan.plan_id.clone(),
+            mint: self.plan.mint,
+            amount,
+            interval,
+            trial_period,
+            max_cycles,
+            is_active: true,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateSubscriptionPlan<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"merchant", merchant_account.merchant_id.as_bytes()],
+        bump = merchant_account.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        mut,
+        seeds = [SubscriptionPlan::SEED, merchant_account.key().as_ref(), plan.plan_id.as_bytes()],
+        bump = plan.bump,
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
+}
+
+impl<'info> UpdateSubscriptionPlan<'info> {
+    pub fn set_subscription_plan_active(&mut self, is_active: bool) -> Result<()> {
+        self.plan.is_active = is_active;
+
+        msg!("Plan {} {}", self.plan.plan_id, if is_active { "activated" } else { "deactivated" });
+
+        emit!(SubscriptionPlanUpdated {
+            plan: self.plan.key(),
+            merchant: self.plan.merchant,
+            plan_id: self.plan.plan_id.clone(),
+            mint: self.plan.mint,
+            amount: self.plan.amount,
+            interval: self.plan.interval,
+            trial_period: self.plan.trial_period,
+            max_cycles: self.plan.max_cycles,
+            is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Subscribe<'info> {
+    #[account(mut)]
+    pub customer: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    #[account(
+        seeds = [SubscriptionPlan::SEED, plan.merchant.as_ref(), plan.plan_id.as_bytes()],
+        bump = plan.bump,
+        constraint = plan.is_active @ PaymentError::SubscriptionPlanInactive,
+    )]
+    pub plan: Account<'info, SubscriptionPlan>,
+
+    #[account(
+        address = plan.merchant,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        init,
+        payer = customer,
+        space = 8 + Subscription::INIT_SPACE,
+        seeds = [Subscription::SEED, plan.key().as_ref(), customer.key().as_ref()],
+        bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = 8 + Customer::INIT_SPACE,
+        seeds = [Customer::SEED, customer.key().as_ref()],
+        bump,
+    )]
+    pub customer_account: Account<'info, Customer>,
+
+    #[account(
+        address = plan.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = customer,
+        associated_token::token_program = token_program,
+    )]
+    pub customer_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = customer,
+        space = 8 + Delegation::INIT_SPACE,
+        seeds = [Delegation::SEED, customer_token.key().as_ref()],
+        bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: PDA approved as delegate; holds no data
+    #[account(
+        seeds = [Platform::DELEGATE_SEED],
+        bump,
+    )]
+    pub payment_delegate: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Subscribe<'info> {
+    /// Creates the subscription and approves the payment delegate for `delegate_amount`,
+    /// which must cover at least one billing cycle
+    pub fn subscribe(&mut self, delegate_amount: u64, bumps: &SubscribeBumps) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        require!(delegate_amount >= self.plan.amount, PaymentError::InsufficientDelegation);
+
+        let now = Clock::get()?.unix_timestamp;
+        self.customer_account.init_if_new(self.customer.key(), now, bumps.customer_account);
+        self.delegation.init_if_new(self.customer_token.key(), bumps.delegation);
+
+        approve_delegate(
+            &self.token_program,
+            &self.mint,
+            &self.customer_token,
+            &mut self.delegation,
+            self.payment_delegate.to_account_info(),
+            self.customer.to_account_info(),
+            delegate_amount,
+        )?;
+
+        let next_charge_at = now
+            .checked_add(self.plan.trial_period)
+            .ok_or(PaymentError::CalculationError)?;
+        self.subscription.set_inner(Subscription {
+            customer: self.customer.key(),
+            plan: self.plan.key(),
+            merchant: self.plan.merchant,
+            customer_token: self.customer_token.key(),
+            status: SubscriptionStatus::Active,
+            next_charge_at,
+            cycles_charged: 0,
+            created_at: now,
+            cancelled_at: None,
+            bump: bumps.subscription,
+            delegated_amount: delegate_amount,
+        });
+
+        msg!(
+            "Customer {} subscribed to plan {}, first charge at {}",
+            self.customer.key(),
+            self.plan.plan_id,
+            next_charge_at
+        );
+
+        emit!(SubscriptionCreated {
+            subscription: self.subscription.key(),
+            plan: self.plan.key(),
+            customer: self.customer.key(),
+            merchant: self.plan.merchant,
+            delegated_amount: delegate_amount,
+            next_charge_at,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ChargeSubscription<'info> {
+    /// Anyone can crank a due subscription; pays rent for the payment record
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    #[account(
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+    )]
+    pub platform_config: Box<Account<'info, Platform>>,
+
+    #[account(
+        seeds = [SubscriptionPlan::SEED, plan.merchant.as_ref(), plan.plan_id.as_bytes()],
+        bump = plan.bump,
+    )]
+    pub plan: Box<Account<'info, SubscriptionPlan>>,
+
+    #[account(
+        mut,
+        seeds = [Subscription::SEED, plan.key().as_ref(), subscription.customer.as_ref()],
+        bump = subscription.bump,
+        has_one = plan,
+    )]
+    pub subscription: Box<Account<'info, Subscription>>,
+
+    #[account(
+        mut,
+        address = plan.merchant,
+    )]
+    pub merchant_account: Box<Account<'info, Merchant>>,
+
+    #[account(
+        mut,
+        seeds = [MerchantStats::SEED, merchant_account.key().as_ref()],
+        bump = merchant_stats.bump,
+    )]
+    pub merchant_stats: Box<Account<'info, MerchantStats>>,
+
+    #[account(
+        mut,
+        seeds = [Customer::SEED, subscription.customer.as_ref()],
+        bump = customer_account.bump,
+    )]
+    pub customer_account: Box<Account<'info, Customer>>,
+
+    /// Seeded by the subscription and cycle, so no other payment can take the charge's address
+    #[account(
+        init,
+        payer = cranker,
+        space = 8 + Payment::INIT_SPACE,
+        seeds = [Payment::SEED, subscription.key().as_ref(), &subscription.cycles_charged.to_le_bytes()],
+        bump,
+    )]
+    pub payment_account: Box<Account<'info, Payment>>,
+
+    #[account(
+        address = plan.mint @ PaymentError::InvalidTokenMint,
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [AcceptedMint::SEED, mint.key().as_ref()],
+        bump = accepted_mint.bump,
+        constraint = accepted_mint.is_enabled @ PaymentError::MintNotAccepted,
+    )]
+    pub accepted_mint: Box<Account<'info, AcceptedMint>>,
+
+    #[account(
+        mut,
+        address = subscription.customer_token,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub customer_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        seeds = [Delegation::SEED, customer_token.key().as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Box<Account<'info, Delegation>>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = merchant_account.settlement_wallet,
+        token::token_program = token_program,
+        constraint = merchant_account.is_settlement_token(&merchant_token.key(), &mint.key(), &token_program.key())
+            @ PaymentError::InvalidSettlementToken,
+    )]
+    pub merchant_token: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Receives the merchant share instead of `merchant_token` while the merchant has a hold period
+    #[account(
+        mut,
+        seeds = [MerchantVault::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_vault.bump,
+    )]
+    pub merchant_vault: Option<Box<Account<'info, MerchantVault>>>,
+
+    #[account(mut)]
+    pub merchant_vault_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives `reserve_bps` of the merchant share while the merchant holds a reserve
+    #[account(
+        mut,
+        seeds = [MerchantReserve::SEED, merchant_account.key().as_ref(), mint.key().as_ref()],
+        bump = merchant_reserve.bump,
+    )]
+    pub merchant_reserve: Option<Box<Account<'info, MerchantReserve>>>,
+
+    #[account(mut)]
+    pub merchant_reserve_tokens: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Receives the merchant-side fee; required when `merchant_account.fee > 0`
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = merchant_account.fee_recipient,
+        associated_token::token_program = token_program,
+    )]
+    pub merchant_fee_token: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    #[account(
+        mut,
+        address = accepted_mint.treasury @ PaymentError::InvalidTreasury,
+        token::mint = mint,
+        token::authority = platform_config,
+        token::token_program = token_program,
+    )]
+    pub platform_treasury: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Required when the merchant has a referrer; otherwise optionally credits this referrer
+    #[account(
+        seeds = [Referrer::SEED, referrer.wallet.as_ref()],
+        bump = referrer.bump,
+    )]
+    pub referrer: Option<Box<Account<'info, Referrer>>>,
+
+    /// Referrer-owned token account for `mint`, required when a referral fee is due
+    #[account(mut)]
+    pub referral_vault: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// CHECK: PDA delegate that signs the pull from `customer_token`
+    #[account(
+        seeds = [Platform::DELEGATE_SEED],
+        bump,
+    )]
+    pub payment_delegate: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub memo_program: Option<Program<'info, Memo>>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> ChargeSubscription<'info> {
+    pub fn charge_subscription(&mut self, bumps: &ChargeSubscriptionBumps) -> Result<()> {
+        require!(self.platform_config.is_active, PaymentError::PlatformInactive);
+        require!(self.merchant_account.can_accept_payments(), PaymentError::MerchantInactive);
+        require!(
+            self.subscription.status == SubscriptionStatus::Active,
+            PaymentError::SubscriptionNotActive
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        require!(now >= self.subscription.next_charge_at, PaymentError::SubscriptionNotDue);
+        let delegated_amount = self.subscription.delegated_amount
+            .checked_sub(self.plan.amount)
+            .ok_or(PaymentError::InsufficientDelegation)?;
+        let payment_id = self.subscription.next_payment_id(&self.subscription.key());
+
+        self.platform_config
+            .limits_for(&self.merchant_account)
+            .check(self.plan.amount, &self.customer_account, &self.merchant_account, now)?;
+
+        let fees = apply_referral(
+            FeeBreakdown::calculate(&self.platform_config, &self.merchant_account, &self.merchant_stats, self.plan.amount)?,
+            &self.merchant_account,
+            &self.mint.key(),
+            self.referrer.as_deref(),
+            self.referral_vault.as_deref(),
+        )?;
+        let seeds = &[Platform::DELEGATE_SEED, &[bumps.payment_delegate]];
+        let (fees, transfer_fee_amount) = settle_payment(
+            PaymentSettlement {
+                token_transfer: TokenTransfer {
+                    token_program: &self.token_program,
+                    mint: &self.mint,
+                    memo_program: self.memo_program.as_ref(),
+                },
+                from: self.customer_token.to_account_info(),
+                authority: self.payment_delegate.to_account_info(),
+                signer_seeds: &[&seeds[..]],
+                customer: Some(&mut self.customer_account),
+                merchant: &mut self.merchant_account,
+                merchant_stats: &mut self.merchant_stats,
+                merchant_token: Some(self.merchant_token.to_account_info()),
+                platform_treasury: self.platform_treasury.to_account_info(),
+                merchant_fee_token: self.merchant_fee_token.as_ref().map(|account| account.to_account_info()),
+                referral_vault: self.referral_vault.as_ref().map(|account| account.to_account_info()),
+                merchant_vault: self.merchant_vault.as_deref_mut(),
+                merchant_vault_tokens: self.merchant_vault_tokens.as_deref_mut(),
+                merchant_reserve: self.merchant_reserve.as_deref_mut(),
+                merchant_reserve_tokens: self.merchant_reserve_tokens.as_deref_mut(),
+            },
+            fees,
+            &payment_id,
+        )?;
+
+        let mut payment = Payment::completed(
+            payment_id,
+            self.subscription.customer,
+            self.merchant_account.key(),
+            self.mint.key(),
+            &fees,
+            transfer_fee_amount,
+            bumps.payment_account,
+        )?;
+        if fees.referral_fee > 0 {
+            payment.referrer = self.referrer.as_ref().map(|referrer| referrer.key());
+        }
+        payment.subscription = Some(self.subscription.key());
+        payment.sequence = self.subscription.cycles_charged;
+        self.payment_account.set_inner(payment);
+
+        // Advance the schedule without letting a late crank charge several cycles back to back
+        self.delegation.release(self.plan.amount);
+        let subscription = &mut self.subscription;
+        subscription.delegated_amount = delegated_amount;
+        subscription.cycles_charged = subscription.cycles_charged
+            .checked_add(1)
+            .ok_or(PaymentError::CalculationError)?;
+        let next_charge_at = subscription.next_charge_at
+            .checked_add(self.plan.interval)
+            .ok_or(PaymentError::CalculationError)?;
+        subscription.next_charge_at = if next_charge_at > now {
+            next_charge_at
+        } else {
+            now.checked_add(self.plan.interval).ok_or(PaymentError::CalculationError)?
+        };
+        if self.plan.max_cycles > 0 && subscription.cycles_charged >= self.plan.max_cycles {
+            subscription.status = SubscriptionStatus::Completed;
+        }
+
+        msg!(
+            "Subscription {} charged {} (cycle {}), next charge at {}",
+            subscription.key(),
+            fees.amount,
+            subscription.cycles_charged,
+            subscription.next_charge_at
+        );
+
+        emit!(PaymentProcessed::new(self.payment_account.key(), &self.payment_account));
+        emit!(SubscriptionCharged {
+            subscription: subscription.key(),
+            payment: self.payment_account.key(),
+            cycle: subscription.cycles_charged,
+            amount: fees.amount,
+            next_charge_at: subscription.next_charge_at,
+            status: subscription.status,
+            timestamp: now,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct UpdateSubscription<'info> {
+    /// The subscribed customer
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Subscription::SEED, subscription.plan.as_ref(), subscription.customer.as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+}
+
+impl<'info> UpdateSubscription<'info> {
+    pub fn pause_subscription(&mut self) -> Result<()> {
+        require_keys_eq!(self.authority.key(), self.subscription.customer, PaymentError::Unauthorized);
+        require!(
+            self.subscription.status == SubscriptionStatus::Active,
+            PaymentError::SubscriptionNotActive
+        );
+
+        self.set_status(SubscriptionStatus::Paused)
+    }
+
+    pub fn resume_subscription(&mut self) -> Result<()> {
+        require_keys_eq!(self.authority.key(), self.subscription.customer, PaymentError::Unauthorized);
+        require!(
+            self.subscription.status == SubscriptionStatus::Paused,
+            PaymentError::SubscriptionNotPaused
+        );
+
+        // Missed cycles while paused are skipped, not billed on resume
+        let now = Clock::get()?.unix_timestamp;
+        self.subscription.next_charge_at = self.subscription.next_charge_at.max(now);
+        self.set_status(SubscriptionStatus::Active)
+    }
+
+    fn set_status(&mut self, status: SubscriptionStatus) -> Result<()> {
+        set_subscription_status(&mut self.subscription, status, self.authority.key())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelSubscription<'info> {
+    /// The subscribed customer, or the merchant authority
+    pub authority: Signer<'info>,
+
+    #[account(
+        address = subscription.merchant,
+    )]
+    pub merchant_account: Account<'info, Merchant>,
+
+    #[account(
+        mut,
+        seeds = [Subscription::SEED, subscription.plan.as_ref(), subscription.customer.as_ref()],
+        bump = subscription.bump,
+    )]
+    pub subscription: Account<'info, Subscription>,
+
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        address = subscription.customer_token,
+        token::mint = mint,
+        token::token_program = token_program,
+    )]
+    pub customer_token: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [Delegation::SEED, customer_token.key().as_ref()],
+        bump = delegation.bump,
+    )]
+    pub delegation: Account<'info, Delegation>,
+
+    /// CHECK: PDA approved as delegate; holds no data
+    #[account(
+        seeds = [Platform::DELEGATE_SEED],
+        bump,
+    )]
+    pub payment_delegate: UncheckedAccount<'info>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> CancelSubscription<'info> {
+    /// Stops billing and takes what is left of the subscription's delegation off the token
+    /// account's total. Only the token account owner can change an approval, so a merchant
+    /// cancellation leaves the approval itself in place until the customer next approves or
+    /// revokes on that account; nothing can be charged against this subscription any more.
+    pub fn cancel_subscription(&mut self) -> Result<()> {
+        let authority = self.authority.key();
+        let is_customer = authority == self.subscription.customer;
+        require!(
+            is_customer || authority == self.merchant_account.authority,
+            PaymentError::Unauthorized
+        );
+        require!(
+            matches!(self.subscription.status, SubscriptionStatus::Active | SubscriptionStatus::Paused),
+            PaymentError::SubscriptionNotActive
+        );
+
+        if is_customer {
+            reduce_delegate(
+                &self.token_program,
+                &self.mint,
+                &self.customer_token,
+                &mut self.delegation,
+                self.payment_delegate.to_account_info(),
+                self.authority.to_account_info(),
+                self.subscription.delegated_amount,
+            )?;
+        } else {
+            self.delegation.release(self.subscription.delegated_amount);
+        }
+        self.subscription.delegated_amount = 0;
+
+        self.subscription.cancelled_at = Some(Clock::get()?.unix_timestamp);
+        set_subscription_status(&mut self.subscription, SubscriptionStatus::Cancelled, authority)
+    }
+}
+
+fn set_subscription_status(
+    subscription: &mut Account<Subscription>,
+    status: SubscriptionStatus,
+    changed_by: Pubkey,
+) -> Result<()> {
+    subscription.status = status;
+
+    msg!("Subscription {} is now {:?}", subscription.key(), status);
+
+    emit!(SubscriptionStatusChanged {
+        subscription: subscription.key(),
+        status,
+        changed_by,
+        next_charge_at: subscription.next_charge_at,
+        timestamp: Clock::get()?.unix_timestamp,
+    });
+
+    Ok(())
+}
+
+pub fn create_plan_handler(
+    ctx: Context<CreateSubscriptionPlan>,
+    plan_id: String,
+    amount: u64,
+    interval: i64,
+    trial_period: i64,
+    max_cycles: u32,
+) -> Result<()> {
+    let bump = ctx.bumps.plan;
+    ctx.accounts.create_subscription_plan(plan_id, amount, interval, trial_period, max_cycles, bump)
+}
+
+pub fn set_plan_active_handler(ctx: Context<UpdateSubscriptionPlan>, is_active: bool) -> Result<()> {
+    ctx.accounts.set_subscription_plan_active(is_active)
+}
+
+pub fn subscribe_handler(ctx: Context<Subscribe>, delegate_amount: u64) -> Result<()> {
+    ctx.accounts.subscribe(delegate_amount, &ctx.bumps)
+}
+
+pub fn charge_subscription_handler(ctx: Context<ChargeSubscription>) -> Result<()> {
+    ctx.accounts.charge_subscription(&ctx.bumps)
+}
+
+pub fn pause_handler(ctx: Context<UpdateSubscription>) -> Result<()> {
+    ctx.accounts.pause_subscription()
+}
+
+pub fn resume_handler(ctx: Context<UpdateSubscription>) -> Result<()> {
+    ctx.accounts.resume_subscription()
+}
+
+pub fn cancel_subscription_handler(ctx: Context<CancelSubscription>) -> Result<()> {
+    ctx.accounts.cancel_subscription()
+}
@@ -2,38 +2,159 @@ use anchor_lang::prelude::*;
 
 use crate::state::platform::Platform;
 use crate::errors::PaymentError;
+use crate::events::{AuthorityTransferCancelled, AuthorityTransferProposed, AuthorityTransferred};
+use crate::utils::grow_account;
 
 #[derive(Accounts)]
-pub struct TransferAuthority<'info> {
-    #[account(mut)]
+pub struct ProposeAuthority<'info> {
     pub authority: Signer<'info>,
-    
-    /// CHECK: This is the new authority (can be a wallet or Squads vault)
-    pub new_authority: UncheckedAccount<'info>,
-    
+
     #[account(
         mut,
-        seeds = [b"platform_config"],
+        seeds = [Platform::SEED],
         bump = platform_config.bump,
         has_one = authority @ PaymentError::Unauthorized,
     )]
     pub platform_config: Account<'info, Platform>,
 }
 
-impl<'info> TransferAuthority<'info> {
-    pub fn transfer_authority(&mut self) -> Result<()> {
+impl<'info> ProposeAuthority<'info> {
+    /// `new_authority` can be a wallet or a Squads vault; it only takes over once it signs `accept_authority`
+    pub fn propose_authority(&mut self, new_authority: Pubkey) -> Result<()> {
+        require!(new_authority != Pubkey::default(), PaymentError::InvalidAuthority);
+        self.platform_config.pending_authority = Some(new_authority);
+
+        msg!(
+            "Authority transfer proposed from {} to {}",
+            self.platform_config.authority,
+            new_authority
+        );
+
+        emit!(AuthorityTransferProposed {
+            platform_config: self.platform_config.key(),
+            authority: self.platform_config.authority,
+            pending_authority: new_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    pub fn cancel_authority_transfer(&mut self) -> Result<()> {
+        let pending_authority = self.platform_config
+            .pending_authority
+            .take()
+            .ok_or(PaymentError::NoPendingAuthority)?;
+
+        msg!("Authority transfer to {} cancelled", pending_authority);
+
+        emit!(AuthorityTransferCancelled {
+            platform_config: self.platform_config.key(),
+            authority: self.platform_config.authority,
+            cancelled_authority: pending_authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    /// The pending authority; a Squads vault signs this via CPI
+    pub new_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        constraint = platform_config.pending_authority == Some(new_authority.key()) @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+}
+
+impl<'info> AcceptAuthority<'info> {
+    pub fn accept_authority(&mut self) -> Result<()> {
         let old_authority = self.platform_config.authority;
         self.platform_config.authority = self.new_authority.key();
-        
+        self.platform_config.pending_authority = None;
+
         msg!(
             "Authority transferred from {} to {}",
             old_authority,
             self.new_authority.key()
         );
+
+        emit!(AuthorityTransferred {
+            platform_config: self.platform_config.key(),
+            old_authority,
+            new_authority: self.new_authority.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
         Ok(())
     }
 }
 
-pub fn handler(ctx: Context<TransferAuthority>) -> Result<()> {
-    ctx.accounts.transfer_authority()
+/// Grows a `Platform` account created by an older program version to the current layout.
+/// New `Platform` fields are appended and default to zero, so the account is resized and
+/// zero-filled without deserializing it first.
+#[derive(Accounts)]
+pub struct MigratePlatformConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    /// CHECK: May not deserialize as `Platform` yet; owner and authority are checked in instruction logic
+    #[account(
+        mut,
+        seeds = [Platform::SEED],
+        bump,
+    )]
+    pub platform_config: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MigratePlatformConfig<'info> {
+    pub fn migrate_platform_config(&mut self) -> Result<()> {
+        let platform_info = self.platform_config.to_account_info();
+        require_keys_eq!(*platform_info.owner, crate::ID, PaymentError::Unauthorized);
+
+        {
+            let data = platform_info.try_borrow_data()?;
+            require!(data.len() >= 8 + 32, PaymentError::Unauthorized);
+            require!(data[..8] == *Platform::DISCRIMINATOR, PaymentError::Unauthorized);
+            // `authority` is the first field after the discriminator
+            let authority = Pubkey::try_from(&data[8..40]).map_err(|_| PaymentError::Unauthorized)?;
+            require_keys_eq!(authority, self.authority.key(), PaymentError::Unauthorized);
+        }
+
+        let new_len = 8 + Platform::INIT_SPACE;
+        let old_len = grow_account(
+            &platform_info,
+            &self.authority.to_account_info(),
+            &self.system_program.to_account_info(),
+            new_len,
+        )?;
+        if old_len >= new_len {
+            msg!("Platform config already at current size ({} bytes)", old_len);
+            return Ok(());
+        }
+
+        msg!("Platform config migrated from {} to {} bytes", old_len, new_len);
+        Ok(())
+    }
+}
+
+pub fn propose_handler(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+    ctx.accounts.propose_authority(new_authority)
+}
+
+pub fn cancel_transfer_handler(ctx: Context<ProposeAuthority>) -> Result<()> {
+    ctx.accounts.cancel_authority_transfer()
+}
+
+pub fn accept_handler(ctx: Context<AcceptAuthority>) -> Result<()> {
+    ctx.accounts.accept_authority()
+}
+
+pub fn migrate_handler(ctx: Context<MigratePlatformConfig>) -> Result<()> {
+    ctx.accounts.migrate_platform_config()
 }
@@ -0,0 +1,227 @@
+use anchor_lang::prelude::*;
+
+use crate::state::platform::{FeeTier, PaymentLimits, Platform};
+use crate::state::AcceptedMint;
+use crate::errors::PaymentError;
+use crate::events::{
+    AcceptedMintUpdated, DisputeConfigUpdated, FeeScheduleUpdated, MerchantOnboardingUpdated, PaymentLimitsUpdated,
+    PlatformConfigUpdated,
+};
+
+#[derive(Accounts)]
+pub struct UpdatePlatform<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePlatformConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [Platform::SEED],
+        bump = platform_config.bump,
+        has_one = authority @ PaymentError::Unauthorized,
+    )]
+    pub platform_config: Account<'info, Platform>,
+
+    /// Required when changing the minimum, which payments check per mint
+    #[account(
+        mut,
+        seeds = [AcceptedMint::SEED, platform_config.usdc_mint.as_ref()],
+        bump = usdc_accepted_mint.bump,
+    )]
+    pub usdc_accepted_mint: Option<Account<'info, AcceptedMint>>,
+}
+
+impl<'info> UpdatePlatformConfig<'info> {
+    /// `min_payment_amount` sets the USDC minimum and the default for mints added later;
+    /// other mints' minimums are changed with `update_accepted_mint`
+    pub fn update_platform_config(
+        &mut self,
+        fee_bps: Option<u64>,
+        min_payment_amount: Option<u64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        let platform_config = &mut self.platform_config;
+
+        if let Some(fee_bps) = fee_bps {
+            require!(fee_bps <= Platform::MAX_FEE_BPS, PaymentError::InvalidFeeBps);
+            platform_config.fee_bps = fee_bps;
+            msg!("Platform fee updated to {} bps", fee_bps);
+        }
+
+        if let Some(min_payment_amount) = min_payment_amount {
+            require!(min_payment_amount > 0, PaymentError::InvalidMinPaymentAmount);
+            let usdc_accepted_mint = self.usdc_accepted_mint.as_mut().ok_or(PaymentError::MintNotAccepted)?;
+            platform_config.min_payment_amount = min_payment_amount;
+            usdc_accepted_mint.min_payment_amount = min_payment_amount;
+            msg!("Minimum payment amount updated to {}", min_payment_amount);
+
+            emit!(AcceptedMintUpdated {
+                accepted_mint: usdc_accepted_mint.key(),
+                mint: usdc_accepted_mint.mint,
+                treasury: usdc_accepted_mint.treasury,
+                min_payment_amount,
+                is_enabled: usdc_accepted_mint.is_enabled,
+                timestamp: Clock::get()?.unix_timestamp,
+            });
+        }
+
+        if let Some(is_active) = is_active {
+            platform_config.is_active = is_active;
+            msg!("Platform {}", if is_active { "activated" } else { "paused" });
+        }
+
+        emit!(PlatformConfigUpdated {
+            platform_config: platform_config.key(),
+            authority: platform_config.authority,
+            fee_bps: platform_config.fee_bps,
+            min_payment_amount: platform_config.min_payment_amount,
+            is_active: platform_config.is_active,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+impl<'info> UpdatePlatform<'info> {
+    /// Replaces the volume tiers and the fixed fee and caps applied on top of them
+    pub fn set_fee_schedule(
+        &mut self,
+        fee_tiers: Vec<FeeTier>,
+        fixed_fee: u64,
+        min_fee: u64,
+        max_fee: u64,
+    ) -> Result<()> {
+        require!(fee_tiers.len() <= Platform::MAX_FEE_TIERS, PaymentError::InvalidFeeSchedule);
+        require!(
+            fee_tiers.iter().all(|tier| tier.fee_bps <= Platform::MAX_FEE_BPS),
+            PaymentError::InvalidFeeBps
+        );
+        require!(
+            fee_tiers.windows(2).all(|pair| pair[0].min_volume < pair[1].min_volume),
+            PaymentError::InvalidFeeSchedule
+        );
+        require!(max_fee == 0 || min_fee <= max_fee, PaymentError::InvalidFeeSchedule);
+
+        let platform_config = &mut self.platform_config;
+        platform_config.fee_tiers = fee_tiers;
+        platform_config.fixed_fee = fixed_fee;
+        platform_config.min_fee = min_fee;
+        platform_config.max_fee = max_fee;
+
+        msg!(
+            "Fee schedule set: {} tiers, fixed {}, min {}, max {}",
+            platform_config.fee_tiers.len(),
+            fixed_fee,
+            min_fee,
+            max_fee
+        );
+
+        emit!(FeeScheduleUpdated {
+            platform_config: platform_config.key(),
+            fee_tiers: platform_config.fee_tiers.clone(),
+            fixed_fee,
+            min_fee,
+            max_fee,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_dispute_config(&mut self, dispute_window: i64, arbiter: Option<Pubkey>) -> Result<()> {
+        require!(dispute_window >= 0, PaymentError::InvalidDisputeWindow);
+
+        let platform_config = &mut self.platform_config;
+        platform_config.dispute_window = dispute_window;
+        platform_config.arbiter = arbiter;
+
+        msg!("Dispute window set to {}s", dispute_window);
+
+        emit!(DisputeConfigUpdated {
+            platform_config: platform_config.key(),
+            dispute_window,
+            arbiter,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sets the default limits for merchants without an override
+    pub fn set_payment_limits(&mut self, limits: PaymentLimits) -> Result<()> {
+        self.platform_config.limits = limits;
+
+        msg!(
+            "Payment limits set: max {}, customer daily {}, merchant daily {}",
+            limits.max_payment_amount,
+            limits.customer_daily_limit,
+            limits.merchant_daily_limit
+        );
+
+        emit!(PaymentLimitsUpdated {
+            merchant: None,
+            limits: Some(limits),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_merchant_onboarding(&mut self, require_merchant_approval: bool) -> Result<()> {
+        let platform_config = &mut self.platform_config;
+        platform_config.require_merchant_approval = require_merchant_approval;
+
+        msg!("Merchant approval required: {}", require_merchant_approval);
+
+        emit!(MerchantOnboardingUpdated {
+            platform_config: platform_config.key(),
+            require_merchant_approval,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+pub fn update_config_handler(
+    ctx: Context<UpdatePlatformConfig>,
+    fee_bps: Option<u64>,
+    min_payment_amount: Option<u64>,
+    is_active: Option<bool>,
+) -> Result<()> {
+    ctx.accounts.update_platform_config(fee_bps, min_payment_amount, is_active)
+}
+
+pub fn dispute_config_handler(ctx: Context<UpdatePlatform>, dispute_window: i64, arbiter: Option<Pubkey>) -> Result<()> {
+    ctx.accounts.set_dispute_config(dispute_window, arbiter)
+}
+
+pub fn fee_schedule_handler(
+    ctx: Context<UpdatePlatform>,
+    fee_tiers: Vec<FeeTier>,
+    fixed_fee: u64,
+    min_fee: u64,
+    max_fee: u64,
+) -> Result<()> {
+    ctx.accounts.set_fee_schedule(fee_tiers, fixed_fee, min_fee, max_fee)
+}
+
+pub fn payment_limits_handler(ctx: Context<UpdatePlatform>, limits: PaymentLimits) -> Result<()> {
+    ctx.accounts.set_payment_limits(limits)
+}
+
+pub fn merchant_onboarding_handler(ctx: Context<UpdatePlatform>, require_merchant_approval: bool) -> Result<()> {
+    ctx.accounts.set_merchant_onboarding(require_merchant_approval)
+}
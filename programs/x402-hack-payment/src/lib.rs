@@ -1,40 +1,421 @@
+// The on-chain IDL instructions `#[program]` generates call the deprecated
+// `AccountInfo::realloc` from a `__private` module it emits at the crate root, where no
+// item attribute can reach. Allow it there only, and keep the lint on for our own code.
+#![cfg_attr(not(feature = "no-idl"), allow(deprecated))]
+
 use anchor_lang::prelude::*;
 
+#[warn(deprecated)]
 pub mod instructions;
+#[warn(deprecated)]
 pub mod errors;
+#[warn(deprecated)]
+pub mod events;
+#[warn(deprecated)]
 pub mod state;
+#[warn(deprecated)]
+pub mod utils;
 
 use instructions::*;
+use state::{DisputeResolution, FeeTier, PaymentLimits, SplitRecipient};
 
 
 declare_id!("339A4zncMj8fbM2zvEopYXu6TZqRieJKebDiXCKwquA5");
 
+#[warn(deprecated)]
 #[program]
 pub mod x402_hack_payment {
     use super::*;
 
     pub fn set_platform_config(ctx: Context<InitializePlatform>, fee_bps: u64, min_payment_amount: u64) -> Result<()> {
-        instructions::platform::handler(ctx, fee_bps, min_payment_amount)
+        instructions::platform::set_platform_config_handler(ctx, fee_bps, min_payment_amount)
+    }
+
+    /// Changing the minimum requires the USDC `AcceptedMint`
+    pub fn update_platform_config(
+        ctx: Context<UpdatePlatformConfig>,
+        fee_bps: Option<u64>,
+        min_payment_amount: Option<u64>,
+        is_active: Option<bool>,
+    ) -> Result<()> {
+        instructions::update_platform::update_config_handler(ctx, fee_bps, min_payment_amount, is_active)
+    }
+
+    pub fn add_accepted_mint(
+        ctx: Context<AddAcceptedMint>,
+        min_payment_amount: Option<u64>,
+        allow_restricted_extensions: bool,
+    ) -> Result<()> {
+        instructions::accepted_mint::add_handler(ctx, min_payment_amount, allow_restricted_extensions)
+    }
+
+    /// Admin only; registers `Platform.usdc_mint` with the original treasury on a platform
+    /// created before mints were registered individually
+    pub fn register_usdc_mint(ctx: Context<RegisterUsdcMint>) -> Result<()> {
+        instructions::accepted_mint::register_usdc_handler(ctx)
+    }
+
+    pub fn update_accepted_mint(
+        ctx: Context<UpdateAcceptedMint>,
+        min_payment_amount: Option<u64>,
+        is_enabled: Option<bool>,
+    ) -> Result<()> {
+        instructions::accepted_mint::update_mint_handler(ctx, min_payment_amount, is_enabled)
     }
 
     pub fn initialize_merchant(ctx: Context<InitializeMerchant>, merchant_id: String, fee_bps: u16) -> Result<()> {
-        instructions::initialize::handler(ctx, merchant_id, fee_bps)
+        instructions::initialize::initialize_merchant_handler(ctx, merchant_id, fee_bps)
+    }
+
+    /// Apply for `merchant_id` when the platform requires merchant approval
+    pub fn submit_merchant_application(
+        ctx: Context<SubmitMerchantApplication>,
+        merchant_id: String,
+        details_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::onboarding::submit_handler(ctx, merchant_id, details_hash)
+    }
+
+    /// Admin only; rejection closes the application
+    pub fn review_merchant_application(ctx: Context<ReviewMerchantApplication>, approve: bool) -> Result<()> {
+        instructions::onboarding::review_handler(ctx, approve)
+    }
+
+    /// Admin only; reserve a merchant ID for `holder`, or block it entirely with `None`
+    pub fn reserve_merchant_id(ctx: Context<ReserveMerchantId>, merchant_id: String, holder: Option<Pubkey>) -> Result<()> {
+        instructions::onboarding::reserve_id_handler(ctx, merchant_id, holder)
+    }
+
+    pub fn release_merchant_id(ctx: Context<ReleaseMerchantId>) -> Result<()> {
+        instructions::onboarding::release_id_handler(ctx)
+    }
+
+    /// Permissionless; creates the stats account for merchants registered before stats existed
+    pub fn init_merchant_stats(ctx: Context<InitMerchantStats>) -> Result<()> {
+        instructions::merchant::init_stats_handler(ctx)
+    }
+
+    /// Change the settlement wallet, merchant-side fee or fee recipient. A new settlement wallet
+    /// is validated like at registration and gets its token account for the passed mint.
+    pub fn update_merchant(
+        ctx: Context<UpdateMerchantConfig>,
+        settlement_wallet: Option<Pubkey>,
+        fee_bps: Option<u16>,
+        fee_recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        instructions::merchant::update_merchant_handler(ctx, settlement_wallet, fee_bps, fee_recipient)
+    }
+
+    pub fn set_merchant_active(ctx: Context<UpdateMerchant>, is_active: bool) -> Result<()> {
+        instructions::merchant::set_active_handler(ctx, is_active)
+    }
+
+    pub fn propose_merchant_authority(ctx: Context<UpdateMerchant>, new_authority: Pubkey) -> Result<()> {
+        instructions::merchant::propose_authority_handler(ctx, new_authority)
+    }
+
+    /// Delegate intent and quote signing to a key other than the merchant authority
+    pub fn set_merchant_signing_key(ctx: Context<UpdateMerchant>, signing_key: Option<Pubkey>) -> Result<()> {
+        instructions::merchant::signing_key_handler(ctx, signing_key)
+    }
+
+    /// Settle into a token account owned by the settlement wallet instead of its ATA; omit the account to reset
+    pub fn set_settlement_token(ctx: Context<SetSettlementToken>) -> Result<()> {
+        instructions::merchant::settlement_token_handler(ctx)
+    }
+
+    pub fn accept_merchant_authority(ctx: Context<AcceptMerchantAuthority>) -> Result<()> {
+        instructions::merchant::accept_authority_handler(ctx)
     }
 
-    pub fn process_payment(ctx: Context<ProcessPayment>, payment_id: String, amount: u64) -> Result<()> {
-        instructions::payment::handler(ctx, payment_id, amount)
+    /// Platform authority override to suspend or reinstate a merchant
+    pub fn set_merchant_suspended(ctx: Context<SuspendMerchant>, suspended: bool) -> Result<()> {
+        instructions::merchant::suspend_handler(ctx, suspended)
+    }
+
+    /// Admin only; replaces a merchant's limits, or clears the override with `None`
+    pub fn set_merchant_limits(ctx: Context<SuspendMerchant>, limits: Option<PaymentLimits>) -> Result<()> {
+        instructions::merchant::limits_handler(ctx, limits)
+    }
+
+    /// Admin only; hold the merchant's proceeds in its `MerchantVault`, or `None` to settle directly
+    pub fn set_merchant_hold_period(ctx: Context<SuspendMerchant>, hold_period: Option<i64>) -> Result<()> {
+        instructions::merchant::hold_period_handler(ctx, hold_period)
+    }
+
+    /// Admin only; hold `reserve_bps` of the merchant's payouts in its `MerchantReserve` for `reserve_period` seconds
+    pub fn set_merchant_reserve(ctx: Context<SuspendMerchant>, reserve_bps: u16, reserve_period: i64) -> Result<()> {
+        instructions::merchant::set_reserve_handler(ctx, reserve_bps, reserve_period)
+    }
+
+    /// Permissionless; required before a merchant on held settlement can be paid in `mint`
+    pub fn open_merchant_vault(ctx: Context<OpenMerchantVault>) -> Result<()> {
+        instructions::merchant_vault::open_merchant_vault_handler(ctx)
+    }
+
+    /// Withdraw every released tranche to the merchant's settlement account
+    pub fn withdraw_merchant_balance(ctx: Context<WithdrawMerchantBalance>) -> Result<()> {
+        instructions::merchant_vault::withdraw_handler(ctx)
+    }
+
+    /// Permissionless; required before a merchant holding a reserve can be paid in `mint`
+    pub fn open_merchant_reserve(ctx: Context<OpenMerchantReserve>) -> Result<()> {
+        instructions::merchant_reserve::open_reserve_handler(ctx)
+    }
+
+    /// Permissionless crank; pays matured reserve tranches to the merchant's settlement account
+    pub fn release_reserve(ctx: Context<ReleaseReserve>) -> Result<()> {
+        instructions::merchant_reserve::release_reserve_handler(ctx)
+    }
+
+    /// Requires a preceding Ed25519 instruction with the merchant's signed payment intent
+    pub fn process_payment(ctx: Context<ProcessPayment>, payment_id: String, amount: u64, expiry: i64) -> Result<()> {
+        instructions::payment::process_payment_handler(ctx, payment_id, amount, expiry)
     }
 
     pub fn claim_platform_fees(ctx: Context<ClaimPlatformFees>) -> Result<()> {
-        instructions::claim::handler(ctx)
+        instructions::claim::claim_fees_handler(ctx)
+    }
+
+    /// Register a partner who earns a share of the platform fee
+    pub fn register_referrer(ctx: Context<RegisterReferrer>, wallet: Pubkey, share_bps: u16) -> Result<()> {
+        instructions::referral::register_handler(ctx, wallet, share_bps)
+    }
+
+    pub fn update_referrer(ctx: Context<UpdateReferrer>, share_bps: Option<u16>, is_active: Option<bool>) -> Result<()> {
+        instructions::referral::update_referrer_handler(ctx, share_bps, is_active)
+    }
+
+    /// Permanently attribute a merchant to a referrer
+    pub fn set_merchant_referrer(ctx: Context<SetMerchantReferrer>) -> Result<()> {
+        instructions::referral::set_merchant_referrer_handler(ctx)
+    }
+
+    pub fn open_referral_vault(ctx: Context<OpenReferralVault>) -> Result<()> {
+        instructions::referral::open_vault_handler(ctx)
+    }
+
+    pub fn claim_referral_fees(ctx: Context<ClaimReferralFees>) -> Result<()> {
+        instructions::referral::claim_handler(ctx)
     }
 
     pub fn refund_payment(ctx: Context<RefundPayment>) -> Result<()> {
-        instructions::refund::handler(ctx)
+        instructions::refund::refund_handler(ctx)
+    }
+
+    pub fn refund_payment_partial(ctx: Context<RefundPayment>, amount: u64) -> Result<()> {
+        instructions::refund::partial_handler(ctx, amount)
+    }
+
+    /// Step one of a platform authority transfer; the new key must call `accept_authority`
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        instructions::transfer::propose_handler(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        instructions::transfer::accept_handler(ctx)
+    }
+
+    pub fn cancel_authority_transfer(ctx: Context<ProposeAuthority>) -> Result<()> {
+        instructions::transfer::cancel_transfer_handler(ctx)
+    }
+
+    /// Resize a platform config created by an older program version
+    pub fn migrate_platform_config(ctx: Context<MigratePlatformConfig>) -> Result<()> {
+        instructions::transfer::migrate_handler(ctx)
+    }
+
+    /// Permissionless; grows a merchant created by an older program version to the current layout
+    pub fn migrate_merchant(ctx: Context<MigrateMerchant>) -> Result<()> {
+        instructions::merchant::migrate_merchant_handler(ctx)
+    }
+
+    /// Permissionless; grows a customer created by an older program version to the current layout
+    pub fn migrate_customer(ctx: Context<MigrateCustomer>) -> Result<()> {
+        instructions::payment::migrate_customer_handler(ctx)
+    }
+
+    /// Permissionless; grows a payment made by an older program version so it can be refunded
+    pub fn migrate_payment(ctx: Context<MigratePayment>) -> Result<()> {
+        instructions::refund::migrate_payment_handler(ctx)
+    }
+
+    pub fn create_subscription_plan(
+        ctx: Context<CreateSubscriptionPlan>,
+        plan_id: String,
+        amount: u64,
+        interval: i64,
+        trial_period: i64,
+        max_cycles: u32,
+    ) -> Result<()> {
+        instructions::subscription::create_plan_handler(ctx, plan_id, amount, interval, trial_period, max_cycles)
+    }
+
+    pub fn set_subscription_plan_active(ctx: Context<UpdateSubscriptionPlan>, is_active: bool) -> Result<()> {
+        instructions::subscription::set_plan_active_handler(ctx, is_active)
+    }
+
+    /// Subscribe to a plan, approving the program's payment delegate for `delegate_amount`
+    pub fn subscribe(ctx: Context<Subscribe>, delegate_amount: u64) -> Result<()> {
+        instructions::subscription::subscribe_handler(ctx, delegate_amount)
+    }
+
+    /// Permissionless crank: charge a due subscription.
+    /// The payment is seeded by `[b"payment", subscription, cycles_charged.to_le_bytes()]`.
+    pub fn charge_subscription(ctx: Context<ChargeSubscription>) -> Result<()> {
+        instructions::subscription::charge_subscription_handler(ctx)
+    }
+
+    pub fn pause_subscription(ctx: Context<UpdateSubscription>) -> Result<()> {
+        instructions::subscription::pause_handler(ctx)
+    }
+
+    pub fn resume_subscription(ctx: Context<UpdateSubscription>) -> Result<()> {
+        instructions::subscription::resume_handler(ctx)
+    }
+
+    /// Cancel a subscription; callable by the customer, which also takes back its remaining
+    /// delegate approval, or the merchant authority
+    pub fn cancel_subscription(ctx: Context<CancelSubscription>) -> Result<()> {
+        instructions::subscription::cancel_subscription_handler(ctx)
+    }
+
+    /// Pay a merchant-signed x402 quote and record an access receipt for the resource
+    pub fn pay_for_resource(
+        ctx: Context<PayForResource>,
+        resource_hash: [u8; 32],
+        amount: u64,
+        nonce: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        instructions::resource::pay_resource_handler(ctx, resource_hash, amount, nonce, expiry)
+    }
+
+    /// Divide the merchant's share of split payments between payout wallets
+    pub fn set_split_config(ctx: Context<SetSplitConfig>, recipients: Vec<SplitRecipient>) -> Result<()> {
+        instructions::split::set_config_handler(ctx, recipients)
+    }
+
+    /// `process_payment` with the merchant share split per `SplitConfig`; recipient token
+    /// accounts are passed as remaining accounts in config order
+    pub fn process_split_payment<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ProcessSplitPayment<'info>>,
+        payment_id: String,
+        amount: u64,
+        expiry: i64,
+    ) -> Result<()> {
+        instructions::split::payment_handler(ctx, payment_id, amount, expiry)
+    }
+
+    pub fn create_invoice(
+        ctx: Context<CreateInvoice>,
+        invoice_id: String,
+        amount_due: u64,
+        due_date: i64,
+        payer: Option<Pubkey>,
+        memo_hash: [u8; 32],
+    ) -> Result<()> {
+        instructions::invoice::create_invoice_handler(ctx, invoice_id, amount_due, due_date, payer, memo_hash)
+    }
+
+    /// Pay all or part of an invoice's outstanding balance; the payment is recorded as
+    /// `{invoice_id}-{payment_count}` and seeded by `[b"payment", invoice, payment_count.to_le_bytes()]`
+    pub fn pay_invoice(ctx: Context<PayInvoice>, amount: u64) -> Result<()> {
+        instructions::invoice::pay_handler(ctx, amount)
+    }
+
+    pub fn mark_invoice_overdue(ctx: Context<MarkInvoiceOverdue>) -> Result<()> {
+        instructions::invoice::overdue_handler(ctx)
+    }
+
+    /// Pay into a per-payment escrow vault held until delivery, against a merchant-signed escrow intent
+    pub fn create_escrow_payment(
+        ctx: Context<CreateEscrowPayment>,
+        payment_id: String,
+        amount: u64,
+        delivery_deadline: i64,
+        expiry: i64,
+    ) -> Result<()> {
+        instructions::escrow::create_escrow_handler(ctx, payment_id, amount, delivery_deadline, expiry)
+    }
+
+    pub fn confirm_delivery(ctx: Context<ConfirmDelivery>) -> Result<()> {
+        instructions::escrow::confirm_handler(ctx)
+    }
+
+    /// Pay out escrowed funds to the merchant
+    pub fn release_escrow(ctx: Context<ReleaseEscrow>) -> Result<()> {
+        instructions::escrow::release_escrow_handler(ctx)
+    }
+
+    /// Refund an escrow whose delivery deadline passed without confirmation
+    pub fn expire_escrow(ctx: Context<ExpireEscrow>) -> Result<()> {
+        instructions::escrow::expire_handler(ctx)
+    }
+
+    /// Volume-based fee tiers plus an optional fixed fee and min/max caps
+    pub fn set_fee_schedule(
+        ctx: Context<UpdatePlatform>,
+        fee_tiers: Vec<FeeTier>,
+        fixed_fee: u64,
+        min_fee: u64,
+        max_fee: u64,
+    ) -> Result<()> {
+        instructions::update_platform::fee_schedule_handler(ctx, fee_tiers, fixed_fee, min_fee, max_fee)
+    }
+
+    /// Require an approved `MerchantApplication` before `initialize_merchant`
+    pub fn set_merchant_onboarding(ctx: Context<UpdatePlatform>, require_merchant_approval: bool) -> Result<()> {
+        instructions::update_platform::merchant_onboarding_handler(ctx, require_merchant_approval)
+    }
+
+    /// Default risk limits; merchants may be given overrides with `set_merchant_limits`
+    pub fn set_payment_limits(ctx: Context<UpdatePlatform>, limits: PaymentLimits) -> Result<()> {
+        instructions::update_platform::payment_limits_handler(ctx, limits)
+    }
+
+    /// Set how long customers may dispute payments, and disputes stay open, and who besides the
+    /// admin may resolve them
+    pub fn set_dispute_config(ctx: Context<UpdatePlatform>, dispute_window: i64, arbiter: Option<Pubkey>) -> Result<()> {
+        instructions::update_platform::dispute_config_handler(ctx, dispute_window, arbiter)
+    }
+
+    /// Dispute an escrowed payment, freezing its escrow; completed payments are refunded by the merchant instead
+    pub fn open_dispute(ctx: Context<OpenDispute>, evidence_hash: [u8; 32]) -> Result<()> {
+        instructions::dispute::open_dispute_handler(ctx, evidence_hash)
+    }
+
+    pub fn submit_dispute_evidence(ctx: Context<SubmitDisputeEvidence>, evidence_hash: [u8; 32]) -> Result<()> {
+        instructions::dispute::evidence_handler(ctx, evidence_hash)
+    }
+
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, resolution: DisputeResolution) -> Result<()> {
+        instructions::dispute::resolve_handler(ctx, resolution)
+    }
+
+    /// Permissionless: unfreeze the escrow of a dispute left unresolved for the dispute window
+    pub fn lapse_dispute(ctx: Context<LapseDispute>) -> Result<()> {
+        instructions::dispute::lapse_handler(ctx)
+    }
+
+    /// Let a merchant charge up to `limit_per_period` every `period_length` seconds
+    pub fn approve_allowance(
+        ctx: Context<ApproveAllowance>,
+        limit_per_period: u64,
+        period_length: i64,
+        expires_at: Option<i64>,
+        delegate_amount: u64,
+    ) -> Result<()> {
+        instructions::allowance::approve_handler(ctx, limit_per_period, period_length, expires_at, delegate_amount)
+    }
+
+    pub fn revoke_allowance(ctx: Context<RevokeAllowance>) -> Result<()> {
+        instructions::allowance::revoke_handler(ctx)
     }
 
-    pub fn transfer_authority(ctx: Context<TransferAuthority>) -> Result<()> {
-        instructions::transfer::handler(ctx)
+    /// Merchant-initiated one-click charge against a customer's allowance
+    pub fn charge_with_allowance(ctx: Context<ChargeWithAllowance>, payment_id: String, amount: u64) -> Result<()> {
+        instructions::allowance::charge_allowance_handler(ctx, payment_id, amount)
     }
 
     /// Issue a private receipt for a payment using Inco Lightning
@@ -0,0 +1,21 @@
+use anchor_lang::prelude::*;
+
+/// A token the platform accepts for payments, with its own fee treasury and minimum
+#[account]
+#[derive(InitSpace)]
+pub struct AcceptedMint {
+    pub mint: Pubkey,
+    /// Platform fee token account for this mint, owned by the platform config PDA
+    pub treasury: Pubkey,
+    /// Minimum payment in the mint's base units
+    pub min_payment_amount: u64,
+    pub decimals: u8,
+    pub is_enabled: bool,
+    /// Platform authority whitelisted this mint despite restricted Token-2022 extensions
+    pub restricted_extensions_allowed: bool,
+    pub bump: u8,
+}
+
+impl AcceptedMint {
+    pub const SEED: &'static [u8] = b"accepted_mint";
+}
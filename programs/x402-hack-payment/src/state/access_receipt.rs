@@ -0,0 +1,47 @@
+use anchor_lang::prelude::*;
+
+/// Proof that `customer` paid a merchant's quote for `resource_hash`, checked by the
+/// merchant's HTTP server before answering an x402 request
+#[account]
+#[derive(InitSpace)]
+pub struct AccessReceipt {
+    pub merchant: Pubkey,
+    pub customer: Pubkey,
+    pub resource_hash: [u8; 32],
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Quote nonce; one receipt per merchant and nonce prevents replay
+    pub nonce: u64,
+    pub quote_expiry: i64,
+    pub paid_at: i64,
+    pub bump: u8,
+}
+
+impl AccessReceipt {
+    pub const SEED: &'static [u8] = b"access_receipt";
+    const QUOTE_DOMAIN: &'static [u8] = b"x402-resource-quote";
+
+    /// Bytes the merchant authority or signing key signs to issue a price quote.
+    /// The quote names its customer so nobody else can redeem its nonce first.
+    pub fn quote_message(
+        merchant: &Pubkey,
+        customer: &Pubkey,
+        resource_hash: &[u8; 32],
+        mint: &Pubkey,
+        amount: u64,
+        nonce: u64,
+        expiry: i64,
+    ) -> Vec<u8> {
+        [
+            Self::QUOTE_DOMAIN,
+            merchant.as_ref(),
+            customer.as_ref(),
+            resource_hash,
+            mint.as_ref(),
+            &amount.to_le_bytes(),
+            &nonce.to_le_bytes(),
+            &expiry.to_le_bytes(),
+        ]
+        .concat()
+    }
+}
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::PaymentError;
+
+/// A customer's standing permission for one merchant to charge up to
+/// `limit_per_period` every `period_length` seconds via the payment delegate
+#[account]
+#[derive(InitSpace)]
+pub struct SpendingAllowance {
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    /// Token account the customer approved the payment delegate on
+    pub customer_token: Pubkey,
+    pub limit_per_period: u64,
+    pub period_length: i64,
+    pub period_start: i64,
+    pub spent_in_period: u64,
+    pub expires_at: Option<i64>,
+    /// Delegation approved through this allowance and not yet charged, handed back on revoke
+    pub delegated_amount: u64,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl SpendingAllowance {
+    pub const SEED: &'static [u8] = b"spending_allowance";
+
+    /// Rolls the period forward if it has elapsed, then records `amount` against the limit
+    pub fn spend(&mut self, amount: u64, now: i64) -> Result<()> {
+        if let Some(expires_at) = self.expires_at {
+            require!(now < expires_at, PaymentError::AllowanceExpired);
+        }
+
+        let elapsed = now.saturating_sub(self.period_start);
+        if elapsed >= self.period_length {
+            // Keep periods aligned to the original start
+            let periods = elapsed / self.period_length;
+            self.period_start = self.period_start
+                .checked_add(periods.checked_mul(self.period_length).ok_or(PaymentError::CalculationError)?)
+                .ok_or(PaymentError::CalculationError)?;
+            self.spent_in_period = 0;
+        }
+
+        let spent = self.spent_in_period
+            .checked_add(amount)
+            .ok_or(PaymentError::CalculationError)?;
+        require!(spent <= self.limit_per_period, PaymentError::AllowanceExceeded);
+        self.spent_in_period = spent;
+
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::PaymentError;
+use crate::state::{MerchantStats, SECONDS_PER_DAY};
+
+/// Fields added after the original layout go after `bump`, and must decode from zero bytes,
+/// so `migrate_customer` can grow existing accounts in place.
 #[account]
 #[derive(InitSpace)]
 pub struct Customer {
@@ -8,5 +13,77 @@ pub struct Customer {
     pub transaction_count: u64,
     pub created_at: i64,
     pub bump: u8,
+    /// Day number `spent_today` covers
+    pub spend_day: u32,
+    pub spent_today: u64,
+    /// 30-day month number `spent_this_month` covers
+    pub spend_month: u32,
+    pub spent_this_month: u64,
+    pub refund_count: u64,
+    pub total_refunded: u64,
+}
+
+impl Customer {
+    pub const SEED: &'static [u8] = b"customer";
+
+    /// Fills in a customer created by `init_if_needed`; no-op for existing customers
+    pub fn init_if_new(&mut self, customer: Pubkey, now: i64, bump: u8) {
+        if self.customer == Pubkey::default() {
+            self.customer = customer;
+            self.total_spent = 0;
+            self.transaction_count = 0;
+            self.created_at = now;
+            self.bump = bump;
+            self.spend_day = 0;
+            self.spent_today = 0;
+            self.spend_month = 0;
+            self.spent_this_month = 0;
+            self.refund_count = 0;
+            self.total_refunded = 0;
+        }
+    }
+
+    /// Spend so far today, treating a stale day as zero
+    pub fn spent_on(&self, now: i64) -> u64 {
+        if self.spend_day == (now / SECONDS_PER_DAY) as u32 { self.spent_today } else { 0 }
+    }
+
+    pub fn record_payment(&mut self, amount: u64) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        let day = (now / SECONDS_PER_DAY) as u32;
+        let month = (now / (SECONDS_PER_DAY * MerchantStats::DAYS_PER_MONTH)) as u32;
+        if self.spend_day != day {
+            self.spend_day = day;
+            self.spent_today = 0;
+        }
+        if self.spend_month != month {
+            self.spend_month = month;
+            self.spent_this_month = 0;
+        }
+        self.spent_today = self.spent_today
+            .checked_add(amount)
+            .ok_or(PaymentError::CalculationError)?;
+        self.spent_this_month = self.spent_this_month
+            .checked_add(amount)
+            .ok_or(PaymentError::CalculationError)?;
+
+        self.transaction_count = self.transaction_count
+            .checked_add(1)
+            .ok_or(PaymentError::CalculationError)?;
+        self.total_spent = self.total_spent
+            .checked_add(amount)
+            .ok_or(PaymentError::CalculationError)?;
+        Ok(())
+    }
+
+    pub fn record_refund(&mut self, amount: u64) -> Result<()> {
+        self.refund_count = self.refund_count
+            .checked_add(1)
+            .ok_or(PaymentError::CalculationError)?;
+        self.total_refunded = self.total_refunded
+            .checked_add(amount)
+            .ok_or(PaymentError::CalculationError)?;
+        Ok(())
+    }
 }
 
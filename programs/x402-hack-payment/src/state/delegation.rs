@@ -0,0 +1,33 @@
+use anchor_lang::prelude::*;
+
+/// Delegation the payment delegate holds on one customer token account, summed over every
+/// allowance and subscription drawing on it. SPL keeps a single delegate amount per token
+/// account, so approvals are always set to this total rather than added to what is on chain.
+#[account]
+#[derive(InitSpace)]
+pub struct Delegation {
+    pub token_account: Pubkey,
+    /// Outstanding delegation approved through allowances and subscriptions and not yet charged
+    pub approved_amount: u64,
+    pub bump: u8,
+}
+
+impl Delegation {
+    pub const SEED: &'static [u8] = b"delegation";
+
+    pub fn init_if_new(&mut self, token_account: Pubkey, bump: u8) {
+        if self.token_account == Pubkey::default() {
+            self.token_account = token_account;
+            self.bump = bump;
+        }
+    }
+
+    /// Takes up to `amount` off the total and returns what was taken. The approval itself is
+    /// left alone: SPL already takes charges off it, and anything else is brought back in line
+    /// the next time the owner approves or reduces the delegation.
+    pub fn release(&mut self, amount: u64) -> u64 {
+        let released = amount.min(self.approved_amount);
+        self.approved_amount -= released;
+        released
+    }
+}
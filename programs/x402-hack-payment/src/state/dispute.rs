@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+
+/// A customer's challenge against an escrowed payment, resolved by the platform authority or arbiter
+/// within the platform's dispute window, after which it lapses
+#[account]
+#[derive(InitSpace)]
+pub struct Dispute {
+    pub payment: Pubkey,
+    pub customer: Pubkey,
+    pub merchant: Pubkey,
+    /// Hash of the customer's off-chain evidence bundle
+    pub customer_evidence: [u8; 32],
+    /// Hash of the merchant's off-chain evidence bundle
+    pub merchant_evidence: Option<[u8; 32]>,
+    pub status: DisputeStatus,
+    /// Amount returned to the customer on resolution
+    pub refunded_amount: u64,
+    pub opened_at: i64,
+    pub resolved_at: Option<i64>,
+    pub resolved_by: Option<Pubkey>,
+    pub bump: u8,
+}
+
+impl Dispute {
+    pub const SEED: &'static [u8] = b"dispute";
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum DisputeStatus {
+    Open,
+    Refunded,
+    PartiallyRefunded,
+    Rejected,
+    /// Not resolved within the platform's dispute window; the escrow is unfrozen as it was
+    Lapsed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DisputeResolution {
+    /// Return the whole payment to the customer
+    RefundFull,
+    /// Return `amount` to the customer and release the rest to the merchant
+    RefundPartial { amount: u64 },
+    /// Release the payment to the merchant
+    Reject,
+}
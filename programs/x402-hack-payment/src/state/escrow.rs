@@ -0,0 +1,45 @@
+use anchor_lang::prelude::*;
+
+/// Holds an escrowed payment's funds in `vault` until release or expiry
+#[account]
+#[derive(InitSpace)]
+pub struct Escrow {
+    pub payment: Pubkey,
+    /// Token account owned by this PDA
+    pub vault: Pubkey,
+    pub delivery_deadline: i64,
+    pub delivered_at: Option<i64>,
+    /// Set when the vault is paid out to the merchant or back to the customer
+    pub closed_at: Option<i64>,
+    pub bump: u8,
+    /// Frozen while a dispute is open; neither release nor expiry is possible until it is resolved or lapses
+    pub disputed: bool,
+}
+
+impl Escrow {
+    pub const SEED: &'static [u8] = b"escrow";
+    pub const VAULT_SEED: &'static [u8] = b"escrow_vault";
+    const INTENT_DOMAIN: &'static [u8] = b"x402-escrow-intent";
+
+    /// Bytes the merchant signs to open an escrow. Its own domain keeps a direct payment intent
+    /// from opening an escrow, and the deadline is bound so the payer can't pick its own.
+    pub fn intent_message(
+        merchant: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+        delivery_deadline: i64,
+        expiry: i64,
+        payment_id: &str,
+    ) -> Vec<u8> {
+        [
+            Self::INTENT_DOMAIN,
+            merchant.as_ref(),
+            mint.as_ref(),
+            &amount.to_le_bytes(),
+            &delivery_deadline.to_le_bytes(),
+            &expiry.to_le_bytes(),
+            payment_id.as_bytes(),
+        ]
+        .concat()
+    }
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::PaymentError;
+
+/// An amount a merchant is owed, settled by one or more `pay_invoice` payments
+#[account]
+#[derive(InitSpace)]
+pub struct Invoice {
+    pub merchant: Pubkey,
+    #[max_len(32)]
+    pub invoice_id: String,
+    pub mint: Pubkey,
+    pub amount_due: u64,
+    pub amount_paid: u64,
+    pub due_date: i64,
+    /// Only this wallet may pay; `None` lets anyone pay
+    pub payer: Option<Pubkey>,
+    /// Hash of the off-chain invoice document
+    pub memo_hash: [u8; 32],
+    pub status: InvoiceStatus,
+    pub payment_count: u32,
+    pub created_at: i64,
+    pub paid_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl Invoice {
+    pub const SEED: &'static [u8] = b"invoice";
+
+    pub fn remaining_amount(&self) -> u64 {
+        self.amount_due.saturating_sub(self.amount_paid)
+    }
+
+    /// Status after the latest payment or deadline check
+    pub fn current_status(&self, now: i64) -> InvoiceStatus {
+        if self.amount_paid >= self.amount_due {
+            InvoiceStatus::Paid
+        } else if now > self.due_date {
+            InvoiceStatus::Overdue
+        } else if self.amount_paid > 0 {
+            InvoiceStatus::PartiallyPaid
+        } else {
+            InvoiceStatus::Open
+        }
+    }
+
+    /// Takes a refunded payment back off the balance, reopening the invoice if it was paid
+    pub fn record_refund(&mut self, amount: u64, now: i64) -> Result<()> {
+        self.amount_paid = self.amount_paid
+            .checked_sub(amount)
+            .ok_or(PaymentError::CalculationError)?;
+        self.status = self.current_status(now);
+        if self.status != InvoiceStatus::Paid {
+            self.paid_at = None;
+        }
+        Ok(())
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum InvoiceStatus {
+    Open,
+    PartiallyPaid,
+    Paid,
+    /// Past `due_date` with a balance outstanding; still payable
+    Overdue,
+}
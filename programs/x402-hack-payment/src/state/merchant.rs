@@ -1,12 +1,21 @@
 use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
 
+use crate::errors::PaymentError;
+use crate::state::{PaymentLimits, SECONDS_PER_DAY};
+use crate::utils::FeeBreakdown;
+
+/// Fields added after the original layout go after `bump`, and must decode from zero bytes,
+/// so `migrate_merchant` can grow existing accounts in place.
 #[account]
 #[derive(InitSpace)]
 pub struct Merchant {
-    #[max_len(200)]
+    /// Merchants registered before IDs were validated may hold up to 200 bytes here
+    #[max_len(32)]
     pub merchant_id: String,
     pub authority: Pubkey,
     pub settlement_wallet: Pubkey,
+    /// Merchant-side fee in bps, withheld from merchant proceeds and sent to `fee_recipient`
     pub fee: u16,
     pub volume: u64,
     pub total_fees: u64,
@@ -14,4 +23,117 @@ pub struct Merchant {
     pub created_at: i64,
     pub is_active: bool,
     pub bump: u8,
-}
\ No newline at end of file
+    /// Set by the platform authority; overrides `is_active` until lifted
+    pub suspended: bool,
+    /// Proposed new authority, must sign `accept_merchant_authority`
+    pub pending_authority: Option<Pubkey>,
+    /// Wallet whose token account receives the merchant-side fee; `migrate_merchant` sets it to
+    /// the settlement wallet on accounts created before it existed
+    pub fee_recipient: Pubkey,
+    /// Key that signs payment intents and quotes in place of `authority`, e.g. a checkout server's hot key
+    pub signing_key: Option<Pubkey>,
+    /// `Referrer` credited on every payment to this merchant; set once
+    pub referrer: Option<Pubkey>,
+    /// Refunds issued; payments stay counted in `transaction_count`, `volume` and `total_fees`
+    pub refund_count: u64,
+    /// Merchant share returned to customers
+    pub refunded_volume: u64,
+    /// Platform fees returned to customers
+    pub refunded_fees: u64,
+    /// Per-merchant risk limits set by the platform authority, replacing the platform defaults
+    pub limits: Option<PaymentLimits>,
+    /// Day number `received_today` covers
+    pub receive_day: u32,
+    /// Gross payments taken in today
+    pub received_today: u64,
+    /// Token account owned by `settlement_wallet` that receives proceeds in its mint instead of the ATA
+    pub settlement_token: Option<Pubkey>,
+    /// Set by the platform authority: payment proceeds wait this many seconds in the
+    /// `MerchantVault` before they can be withdrawn. `None` settles straight to the merchant.
+    pub hold_period: Option<i64>,
+    /// Set by the platform authority: share of each merchant payout held
+    /// in the `MerchantReserve` for `reserve_period` seconds. Zero disables the reserve.
+    pub reserve_bps: u16,
+    pub reserve_period: i64,
+}
+
+impl Merchant {
+    pub const MAX_FEE_BPS: u16 = 1000;
+    /// Longest `merchant_id` usable as a PDA seed. Must match the `max_len` of `merchant_id`
+    pub const MAX_ID_LEN: usize = 32;
+
+    /// Account size for a merchant whose ID is `id_len` bytes, longer than `MAX_ID_LEN` for some older IDs
+    pub fn space(id_len: usize) -> usize {
+        8 + Self::INIT_SPACE - Self::MAX_ID_LEN + id_len.max(Self::MAX_ID_LEN)
+    }
+
+    /// Accepts lowercase ASCII letters, digits, `-` and `_`, so IDs cannot imitate each other
+    /// with look-alike or invisible characters
+    pub fn validate_id(merchant_id: &str) -> Result<()> {
+        require!(
+            !merchant_id.is_empty()
+                && merchant_id.len() <= Self::MAX_ID_LEN
+                && merchant_id
+                    .bytes()
+                    .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-' || byte == b'_'),
+            PaymentError::InvalidMerchantId
+        );
+        Ok(())
+    }
+
+    pub fn can_accept_payments(&self) -> bool {
+        self.is_active && !self.suspended
+    }
+
+    /// Whether `key` may sign payment intents and resource quotes for this merchant
+    pub fn is_signer(&self, key: &Pubkey) -> bool {
+        *key == self.authority || self.signing_key == Some(*key)
+    }
+
+    /// Gross payments taken in so far today, treating a stale day as zero
+    pub fn received_on(&self, now: i64) -> u64 {
+        if self.receive_day == (now / SECONDS_PER_DAY) as u32 { self.received_today } else { 0 }
+    }
+
+    /// Whether `token` may receive this merchant's proceeds in `mint`: the registered
+    /// `settlement_token`, or else the settlement wallet's ATA
+    pub fn is_settlement_token(&self, token: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> bool {
+        self.settlement_token == Some(*token)
+            || *token == get_associated_token_address_with_program_id(&self.settlement_wallet, mint, token_program)
+    }
+
+    pub fn record_payment(&mut self, fees: &FeeBreakdown) -> Result<()> {
+        let day = (Clock::get()?.unix_timestamp / SECONDS_PER_DAY) as u32;
+        if self.receive_day != day {
+            self.receive_day = day;
+            self.received_today = 0;
+        }
+        self.received_today = self.received_today
+            .checked_add(fees.amount)
+            .ok_or(PaymentError::CalculationError)?;
+
+        self.transaction_count = self.transaction_count
+            .checked_add(1)
+            .ok_or(PaymentError::CalculationError)?;
+        self.volume = self.volume
+            .checked_add(fees.merchant_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        self.total_fees = self.total_fees
+            .checked_add(fees.fee)
+            .ok_or(PaymentError::CalculationError)?;
+        Ok(())
+    }
+
+    pub fn record_refund(&mut self, merchant_amount: u64, fee_amount: u64) -> Result<()> {
+        self.refund_count = self.refund_count
+            .checked_add(1)
+            .ok_or(PaymentError::CalculationError)?;
+        self.refunded_volume = self.refunded_volume
+            .checked_add(merchant_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        self.refunded_fees = self.refunded_fees
+            .checked_add(fee_amount)
+            .ok_or(PaymentError::CalculationError)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::PaymentError;
+
+/// A request to register `merchant_id`, required by `initialize_merchant` while
+/// `Platform.require_merchant_approval` is set. Rejected applications are closed, freeing the ID.
+#[account]
+#[derive(InitSpace)]
+pub struct MerchantApplication {
+    #[max_len(32)]
+    pub merchant_id: String,
+    /// Becomes the merchant authority; the only key that may initialize the merchant
+    pub applicant: Pubkey,
+    /// Hash of the off-chain business details submitted for review
+    pub details_hash: [u8; 32],
+    pub status: ApplicationStatus,
+    pub submitted_at: i64,
+    pub reviewed_at: Option<i64>,
+    pub bump: u8,
+}
+
+impl MerchantApplication {
+    pub const SEED: &'static [u8] = b"merchant_application";
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum ApplicationStatus {
+    Pending,
+    Approved,
+}
+
+/// A merchant ID held back by the platform, e.g. a brand name, claimable only by `holder`
+#[account]
+#[derive(InitSpace)]
+pub struct ReservedMerchantId {
+    #[max_len(32)]
+    pub merchant_id: String,
+    /// Key allowed to register the ID; `None` blocks it entirely
+    pub holder: Option<Pubkey>,
+    pub reserved_at: i64,
+    pub bump: u8,
+}
+
+impl ReservedMerchantId {
+    pub const SEED: &'static [u8] = b"reserved_merchant_id";
+
+    /// Errors unless `claimant` may register the ID whose reservation PDA is `reservation`.
+    /// An empty PDA means the ID is not reserved.
+    pub fn check_claim(reservation: &AccountInfo, claimant: &Pubkey) -> Result<()> {
+        if reservation.data_is_empty() {
+            return Ok(());
+        }
+        require_keys_eq!(*reservation.owner, crate::ID, PaymentError::MerchantIdReserved);
+        let reserved = Self::try_deserialize(&mut &reservation.try_borrow_data()?[..])?;
+        require!(reserved.holder == Some(*claimant), PaymentError::MerchantIdReserved);
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Tranche, SECONDS_PER_DAY};
+
+/// Per-mint rolling reserve of a merchant. `reserve_bps` of each payment's merchant
+/// share is locked here for `reserve_period`, then paid to the settlement account by `release_reserve`.
+#[account]
+#[derive(InitSpace)]
+pub struct MerchantReserve {
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    /// Token account owned by this PDA
+    pub vault: Pubkey,
+    /// Reserved funds, at most one tranche per `TRANCHE_SPAN`
+    #[max_len(32)]
+    pub pending: Vec<Tranche>,
+    pub bump: u8,
+}
+
+impl MerchantReserve {
+    pub const SEED: &'static [u8] = b"merchant_reserve";
+    pub const TOKENS_SEED: &'static [u8] = b"merchant_reserve_tokens";
+    /// Must match the `max_len` of `pending`
+    pub const MAX_TRANCHES: usize = 32;
+    /// Reserves taken within one week share a tranche
+    pub const TRANCHE_SPAN: i64 = 7 * SECONDS_PER_DAY;
+    /// Longest reserve period; keeps weekly tranches within `MAX_TRANCHES`
+    pub const MAX_RESERVE_PERIOD: i64 = 180 * SECONDS_PER_DAY;
+
+    pub fn deposit(&mut self, amount: u64, reserve_period: i64, now: i64) -> Result<()> {
+        let release_at = Tranche::release_time(now, reserve_period, Self::TRANCHE_SPAN)?;
+        Tranche::add(&mut self.pending, amount, release_at, Self::MAX_TRANCHES)
+    }
+
+    /// Removes tranches released by `now`, returning their total
+    pub fn release(&mut self, now: i64) -> Result<u64> {
+        Tranche::release_matured(&mut self.pending, now)
+    }
+
+    /// Takes up to `amount` for a refund, latest tranches first, returning what was taken
+    pub fn debit(&mut self, amount: u64) -> u64 {
+        Tranche::take(&mut self.pending, amount)
+    }
+
+    pub fn held(&self) -> u64 {
+        self.pending
+            .iter()
+            .map(|tranche| tranche.amount)
+            .fold(0u64, u64::saturating_add)
+    }
+}
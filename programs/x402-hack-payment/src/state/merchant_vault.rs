@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::PaymentError;
+use crate::state::SECONDS_PER_DAY;
+
+/// Funds locked until `release_at`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct Tranche {
+    pub amount: u64,
+    pub release_at: i64,
+}
+
+impl Tranche {
+    /// `hold` seconds after `now`, rounded up to a multiple of `span` so deposits within
+    /// one span share a tranche
+    pub fn release_time(now: i64, hold: i64, span: i64) -> Result<i64> {
+        let release_at = now.checked_add(hold).ok_or(PaymentError::CalculationError)?;
+        let spans = release_at
+            .checked_add(span - 1)
+            .ok_or(PaymentError::CalculationError)?
+            / span;
+        Ok(spans.checked_mul(span).ok_or(PaymentError::CalculationError)?)
+    }
+
+    /// Adds `amount` to the tranche releasing at `release_at`, opening one if there is none
+    pub fn add(tranches: &mut Vec<Tranche>, amount: u64, release_at: i64, max: usize) -> Result<()> {
+        if let Some(tranche) = tranches.iter_mut().find(|tranche| tranche.release_at == release_at) {
+            tranche.amount = tranche.amount
+                .checked_add(amount)
+                .ok_or(PaymentError::CalculationError)?;
+            return Ok(());
+        }
+        require!(tranches.len() < max, PaymentError::TooManyTranches);
+        tranches.push(Tranche { amount, release_at });
+        Ok(())
+    }
+
+    /// Removes tranches released by `now`, returning their total
+    pub fn release_matured(tranches: &mut Vec<Tranche>, now: i64) -> Result<u64> {
+        let mut released = 0u64;
+        for tranche in tranches.iter().filter(|tranche| tranche.release_at <= now) {
+            released = released
+                .checked_add(tranche.amount)
+                .ok_or(PaymentError::CalculationError)?;
+        }
+        tranches.retain(|tranche| tranche.release_at > now);
+        Ok(released)
+    }
+
+    /// Takes up to `amount` from the latest-releasing tranches first, returning what was taken
+    pub fn take(tranches: &mut Vec<Tranche>, amount: u64) -> u64 {
+        tranches.sort_by_key(|tranche| tranche.release_at);
+        let mut taken = 0;
+        while let Some(tranche) = tranches.last_mut() {
+            let share = tranche.amount.min(amount - taken);
+            tranche.amount -= share;
+            taken += share;
+            if tranche.amount > 0 {
+                break;
+            }
+            tranches.pop();
+        }
+        taken
+    }
+}
+
+/// Per-mint balance of a merchant on held settlement. Proceeds wait in `pending` for the
+/// merchant's hold period, then become `available` to `withdraw_merchant_balance`.
+#[account]
+#[derive(InitSpace)]
+pub struct MerchantVault {
+    pub merchant: Pubkey,
+    pub mint: Pubkey,
+    /// Token account owned by this PDA
+    pub vault: Pubkey,
+    /// Released proceeds the merchant may withdraw
+    pub available: u64,
+    /// Proceeds still on hold, at most one tranche per release day
+    #[max_len(32)]
+    pub pending: Vec<Tranche>,
+    pub bump: u8,
+}
+
+impl MerchantVault {
+    pub const SEED: &'static [u8] = b"merchant_vault";
+    pub const TOKENS_SEED: &'static [u8] = b"merchant_vault_tokens";
+    /// Must match the `max_len` of `pending`
+    pub const MAX_TRANCHES: usize = 32;
+    /// Longest hold; keeps daily tranches within `MAX_TRANCHES`
+    pub const MAX_HOLD_PERIOD: i64 = 30 * SECONDS_PER_DAY;
+
+    /// Moves tranches released by `now` into `available`
+    pub fn mature(&mut self, now: i64) -> Result<()> {
+        let released = Tranche::release_matured(&mut self.pending, now)?;
+        self.available = self.available
+            .checked_add(released)
+            .ok_or(PaymentError::CalculationError)?;
+        Ok(())
+    }
+
+    pub fn deposit(&mut self, amount: u64, hold_period: i64, now: i64) -> Result<()> {
+        self.mature(now)?;
+        let release_at = Tranche::release_time(now, hold_period, SECONDS_PER_DAY)?;
+        Tranche::add(&mut self.pending, amount, release_at, Self::MAX_TRANCHES)
+    }
+
+    /// Takes up to `amount` for a refund, from held proceeds first, returning what was taken
+    pub fn debit(&mut self, amount: u64) -> u64 {
+        let held = Tranche::take(&mut self.pending, amount);
+        let available = self.available.min(amount - held);
+        self.available -= available;
+        held + available
+    }
+
+    pub fn held(&self) -> u64 {
+        self.pending
+            .iter()
+            .map(|tranche| tranche.amount)
+            .fold(0u64, u64::saturating_add)
+    }
+}
@@ -1,11 +1,39 @@
+pub mod access_receipt;
+pub mod accepted_mint;
+pub mod allowance;
 pub mod merchant;
+pub mod merchant_application;
+pub mod merchant_reserve;
+pub mod merchant_vault;
 pub mod platform;
 pub mod customer;
+pub mod delegation;
+pub mod dispute;
+pub mod escrow;
+pub mod invoice;
 pub mod payment;
 pub mod private_receipt;
+pub mod referrer;
+pub mod split;
+pub mod stats;
+pub mod subscription;
 
+pub use access_receipt::*;
+pub use accepted_mint::*;
+pub use allowance::*;
 pub use merchant::*;
+pub use merchant_application::*;
+pub use merchant_reserve::*;
+pub use merchant_vault::*;
 pub use platform::*;
 pub use customer::*;
+pub use delegation::*;
+pub use dispute::*;
+pub use escrow::*;
+pub use invoice::*;
 pub use payment::*;
-pub use private_receipt::*;
\ No newline at end of file
+pub use private_receipt::*;
+pub use referrer::*;
+pub use split::*;
+pub use stats::*;
+pub use subscription::*;
\ No newline at end of file
@@ -1,27 +1,379 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::PaymentError;
+use crate::utils::FeeBreakdown;
+
+/// Fields added after the original layout go after `bump`, and must decode from zero bytes,
+/// so `migrate_payment` can grow existing accounts in place.
 #[account]
-#[derive(InitSpace)]
+#[derive(InitSpace, Default)]
 pub struct Payment {
     #[max_len(64)]
     pub payment_id: String,
     pub customer: Pubkey,
     pub merchant: Pubkey,
     pub amount: u64,
+    /// Platform fee sent to the treasury
     pub fee_amount: u64,
+    /// Net amount settled to the merchant
     pub merchant_amount: u64,
     pub status: PaymentStatus,
     pub created_at: i64,
     pub refunded_at: Option<i64>,
     pub bump: u8,
+    /// Zero on payments made before multi-mint support until `migrate_payment` sets the USDC mint
+    pub mint: Pubkey,
+    /// Merchant-side fee sent to the merchant's fee recipient
+    pub merchant_fee_amount: u64,
+    /// Token-2022 transfer fees withheld by the mint across all legs
+    pub transfer_fee_amount: u64,
+    /// `amount` less transfer fees, i.e. what merchant, fee recipient and treasury received
+    pub net_amount: u64,
+    pub refunded_amount: u64,
+    /// Invoice this payment was applied to, if any
+    pub invoice: Option<Pubkey>,
+    pub referrer: Option<Pubkey>,
+    /// Portion of `fee_amount` paid to `referrer` rather than the treasury
+    pub referral_fee_amount: u64,
+    /// `Platform.fee_tiers` index the fee was charged at; `None` for the base rate
+    pub fee_tier: Option<u8>,
+    /// Portion of `merchant_amount` held in the merchant's reserve
+    pub reserve_amount: u64,
+    /// What the customer actually received across refunds: `refunded_amount` less the
+    /// Token-2022 transfer fees withheld on the way back
+    pub refunded_net_amount: u64,
+    /// Subscription this payment billed, if any
+    pub subscription: Option<Pubkey>,
+    /// Position of this payment within its subscription or invoice, which seeds its address
+    pub sequence: u32,
 }
 
 impl Payment {
+    /// New payments are seeded by `[SEED, merchant, payment_id]`; older ones by `[SEED, payment_id]`.
+    /// Subscription charges and invoice payments are seeded by `[SEED, subscription or invoice, sequence]`
+    /// so no other payment can claim their id.
     pub const SEED: &'static [u8] = b"payment";
+    const INTENT_DOMAIN: &'static [u8] = b"x402-payment-intent";
+
+    /// Whether `key` is this payment's PDA under the subscription- or invoice-scoped, merchant-scoped or
+    /// legacy global seeds
+    pub fn is_address(&self, key: &Pubkey) -> bool {
+        let bump = [self.bump];
+        let sequence = self.sequence.to_le_bytes();
+        let scoped: &[&[u8]] = &[Self::SEED, self.merchant.as_ref(), self.payment_id.as_bytes(), &bump];
+        let legacy: &[&[u8]] = &[Self::SEED, self.payment_id.as_bytes(), &bump];
+        let mut candidates = vec![scoped, legacy];
+        // Invoice payments made before invoice-scoped seeds are still merchant-scoped
+        let parent = self.subscription.or(self.invoice);
+        let parent_seeds;
+        if let Some(parent) = &parent {
+            parent_seeds = [Self::SEED, parent.as_ref(), &sequence, &bump];
+            candidates.push(&parent_seeds);
+        }
+        candidates
+            .iter()
+            .any(|seeds| Pubkey::create_program_address(seeds, &crate::ID).is_ok_and(|address| address == *key))
+    }
+
+    /// Whether this payment predates multi-mint support and still needs `upgrade_legacy`
+    pub fn is_legacy(&self) -> bool {
+        self.mint == Pubkey::default()
+    }
+
+    /// Fills in the fields a payment made before multi-mint support decodes as zero.
+    /// Those payments were all in `usdc_mint`, carried no transfer or merchant-side fees,
+    /// and could only be refunded in full.
+    pub fn upgrade_legacy(&mut self, usdc_mint: Pubkey) {
+        self.mint = usdc_mint;
+        self.net_amount = self.amount;
+        if self.status == PaymentStatus::Refunded {
+            self.refunded_amount = self.amount;
+            self.refunded_net_amount = self.amount;
+        }
+    }
+
+    /// Bytes the merchant signs to issue a payment intent; `payment_id` is last so it needs no length prefix
+    pub fn intent_message(merchant: &Pubkey, mint: &Pubkey, amount: u64, expiry: i64, payment_id: &str) -> Vec<u8> {
+        [
+            Self::INTENT_DOMAIN,
+            merchant.as_ref(),
+            mint.as_ref(),
+            &amount.to_le_bytes(),
+            &expiry.to_le_bytes(),
+            payment_id.as_bytes(),
+        ]
+        .concat()
+    }
+
+    /// A settled payment record for `fees`
+    pub fn completed(
+        payment_id: String,
+        customer: Pubkey,
+        merchant: Pubkey,
+        mint: Pubkey,
+        fees: &FeeBreakdown,
+        transfer_fee_amount: u64,
+        bump: u8,
+    ) -> Result<Self> {
+        Ok(Self {
+            payment_id,
+            customer,
+            merchant,
+            mint,
+            amount: fees.amount,
+            fee_amount: fees.fee,
+            merchant_fee_amount: fees.merchant_fee,
+            referral_fee_amount: fees.referral_fee,
+            fee_tier: fees.fee_tier,
+            reserve_amount: fees.reserve,
+            merchant_amount: fees.merchant_amount,
+            transfer_fee_amount,
+            net_amount: fees.amount
+                .checked_sub(transfer_fee_amount)
+                .ok_or(PaymentError::CalculationError)?,
+            status: PaymentStatus::Completed,
+            created_at: Clock::get()?.unix_timestamp,
+            bump,
+            ..Default::default()
+        })
+    }
+
+    /// Fee split recorded for this payment
+    pub fn fees(&self) -> FeeBreakdown {
+        FeeBreakdown {
+            amount: self.amount,
+            fee: self.fee_amount,
+            merchant_fee: self.merchant_fee_amount,
+            merchant_amount: self.merchant_amount,
+            referral_fee: self.referral_fee_amount,
+            fee_tier: self.fee_tier,
+            reserve: self.reserve_amount,
+        }
+    }
+
+    /// Merchant-side split of an escrow vault holding `vault_balance` after `refunded` of it went
+    /// back to the customer. The platform, merchant-side and referral legs keep their pro-rata
+    /// share and the merchant gets what is left, so rounding and any deposit transfer fee
+    /// shortfall come out of its share and the legs never add up to more than the vault holds.
+    pub fn escrow_release(&self, refunded: u64, vault_balance: u64) -> Result<FeeBreakdown> {
+        let kept = |part: u64| -> Result<u64> {
+            let returned = self.pro_rata(part, refunded).ok_or(PaymentError::CalculationError)?;
+            part.checked_sub(returned).ok_or(PaymentError::CalculationError.into())
+        };
+        let fee = kept(self.fee_amount)?;
+        let merchant_fee = kept(self.merchant_fee_amount)?;
+        let merchant_amount = vault_balance
+            .checked_sub(refunded)
+            .and_then(|left| left.checked_sub(fee))
+            .and_then(|left| left.checked_sub(merchant_fee))
+            .ok_or(PaymentError::CalculationError)?;
+
+        Ok(FeeBreakdown {
+            amount: self.amount
+                .checked_sub(refunded)
+                .ok_or(PaymentError::CalculationError)?,
+            fee,
+            merchant_fee,
+            merchant_amount,
+            referral_fee: kept(self.referral_fee_amount)?,
+            fee_tier: self.fee_tier,
+            // The merchant's reserve, if any, is applied when the escrow is released
+            reserve: 0,
+        })
+    }
+
+    pub fn remaining_amount(&self) -> u64 {
+        self.amount.saturating_sub(self.refunded_amount)
+    }
+
+    /// Portion of `part` attributable to `refunded` of `amount`.
+    /// Cumulative so that successive partial refunds sum to exactly `part`.
+    pub fn pro_rata(&self, part: u64, refunded: u64) -> Option<u64> {
+        if self.amount == 0 {
+            return Some(0);
+        }
+        let share = (refunded as u128)
+            .checked_mul(part as u128)?
+            .checked_div(self.amount as u128)?;
+        u64::try_from(share).ok()
+    }
+
+    /// Share of `part` covered by a refund of `amount` on top of what was already refunded
+    pub fn refund_share(&self, part: u64, amount: u64) -> Option<u64> {
+        let already = self.refunded_amount;
+        self.pro_rata(part, already.checked_add(amount)?)?
+            .checked_sub(self.pro_rata(part, already)?)
+    }
 }
 
-#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq, InitSpace, Default, Debug)]
 pub enum PaymentStatus {
+    #[default]
     Completed,
     Refunded,
+    PartiallyRefunded,
+    /// Funds held in an escrow vault awaiting release
+    Escrowed,
+    /// Escrowed funds paid out to the merchant
+    Released,
+    /// Escrowed funds returned to the customer after the delivery deadline
+    Expired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `Payment` as laid out before any fields were added
+    #[derive(AnchorSerialize)]
+    struct LegacyPayment {
+        payment_id: String,
+        customer: Pubkey,
+        merchant: Pubkey,
+        amount: u64,
+        fee_amount: u64,
+        merchant_amount: u64,
+        status: PaymentStatus,
+        created_at: i64,
+        refunded_at: Option<i64>,
+        bump: u8,
+    }
+
+    /// Size the old program allocated for every payment
+    const LEGACY_SPACE: usize = 8 + 4 + 64 + 32 + 32 + 8 + 8 + 8 + 1 + 8 + 9 + 1;
+
+    fn legacy_account(payment_id: &str, status: PaymentStatus, refunded_at: Option<i64>) -> (Pubkey, Vec<u8>) {
+        let (address, bump) = Pubkey::find_program_address(&[Payment::SEED, payment_id.as_bytes()], &crate::ID);
+        let legacy = LegacyPayment {
+            payment_id: payment_id.to_string(),
+            customer: Pubkey::new_unique(),
+            merchant: Pubkey::new_unique(),
+            amount: 1_000_000,
+            fee_amount: 10_000,
+            merchant_amount: 990_000,
+            status,
+            created_at: 1_700_000_000,
+            refunded_at,
+            bump,
+        };
+        let mut data = Payment::DISCRIMINATOR.to_vec();
+        legacy.serialize(&mut data).unwrap();
+        data.resize(LEGACY_SPACE, 0);
+        (address, data)
+    }
+
+    /// What `migrate_payment` does to the account data before deserializing it
+    fn migrate(mut data: Vec<u8>, usdc_mint: Pubkey) -> Payment {
+        data.resize(8 + Payment::INIT_SPACE, 0);
+        let mut payment = Payment::try_deserialize(&mut &data[..]).unwrap();
+        assert!(payment.is_legacy());
+        payment.upgrade_legacy(usdc_mint);
+        payment
+    }
+
+    #[test]
+    fn legacy_payment_does_not_fit_the_old_allocation() {
+        let (_, data) = legacy_account("order-1", PaymentStatus::Completed, None);
+        assert!(Payment::try_deserialize(&mut &data[..]).is_err());
+    }
+
+    #[test]
+    fn migrated_legacy_payment_is_refundable_in_full() {
+        let usdc_mint = Pubkey::new_unique();
+        let (address, data) = legacy_account("order-1", PaymentStatus::Completed, None);
+        let payment = migrate(data, usdc_mint);
+
+        assert!(payment.is_address(&address));
+        assert_eq!(payment.payment_id, "order-1");
+        assert_eq!(payment.mint, usdc_mint);
+        assert_eq!(payment.status, PaymentStatus::Completed);
+        assert_eq!(payment.net_amount, payment.amount);
+        assert_eq!(payment.remaining_amount(), 1_000_000);
+        assert_eq!(payment.refund_share(payment.fee_amount, payment.amount), Some(10_000));
+        assert_eq!(payment.invoice, None);
+        assert_eq!(payment.referral_fee_amount, 0);
+    }
+
+    #[test]
+    fn migrated_refunded_legacy_payment_has_nothing_left() {
+        let (_, data) = legacy_account("order-2", PaymentStatus::Refunded, Some(1_700_000_100));
+        let payment = migrate(data, Pubkey::new_unique());
+
+        assert_eq!(payment.status, PaymentStatus::Refunded);
+        assert_eq!(payment.refunded_at, Some(1_700_000_100));
+        assert_eq!(payment.remaining_amount(), 0);
+    }
+
+    fn escrowed(merchant_fee_amount: u64, referral_fee_amount: u64) -> Payment {
+        Payment {
+            amount: 1_000_000,
+            fee_amount: 10_000,
+            merchant_fee_amount,
+            referral_fee_amount,
+            merchant_amount: 990_000 - merchant_fee_amount,
+            status: PaymentStatus::Escrowed,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn escrow_release_after_uneven_refund_fits_the_vault() {
+        let fees = escrowed(0, 0).escrow_release(333_333, 1_000_000).unwrap();
+
+        assert_eq!(fees.amount, 666_667);
+        assert_eq!(fees.fee, 6_667);
+        assert_eq!(fees.merchant_amount, 660_000);
+        assert_eq!(fees.fee + fees.merchant_fee + fees.merchant_amount, 1_000_000 - 333_333);
+    }
+
+    #[test]
+    fn escrow_release_takes_rounding_and_deposit_shortfall_from_the_merchant() {
+        // The deposit was charged a 1,000 transfer fee, so the vault holds 999,000
+        let fees = escrowed(5_000, 2_000).escrow_release(333_333, 999_000).unwrap();
+
+        assert_eq!(fees.fee, 6_667);
+        assert_eq!(fees.merchant_fee, 3_334);
+        assert_eq!(fees.referral_fee, 1_334);
+        assert_eq!(fees.merchant_amount, 655_666);
+        assert_eq!(fees.fee + fees.merchant_fee + fees.merchant_amount, 999_000 - 333_333);
+    }
+
+    #[test]
+    fn escrow_release_without_refund_pays_the_whole_vault() {
+        let fees = escrowed(5_000, 2_000).escrow_release(0, 999_000).unwrap();
+
+        assert_eq!(fees.fee, 10_000);
+        assert_eq!(fees.merchant_fee, 5_000);
+        assert_eq!(fees.merchant_amount, 984_000);
+    }
+
+    #[test]
+    fn subscription_charge_is_addressed_by_its_cycle() {
+        let subscription = Pubkey::new_unique();
+        let (address, bump) =
+            Pubkey::find_program_address(&[Payment::SEED, subscription.as_ref(), &3u32.to_le_bytes()], &crate::ID);
+        let mut payment = Payment {
+            payment_id: "sub-charge".to_string(),
+            merchant: Pubkey::new_unique(),
+            subscription: Some(subscription),
+            sequence: 3,
+            bump,
+            ..Default::default()
+        };
+        assert!(payment.is_address(&address));
+
+        payment.sequence = 4;
+        assert!(!payment.is_address(&address));
+        payment.sequence = 3;
+        payment.subscription = None;
+        assert!(!payment.is_address(&address));
+    }
+
+    #[test]
+    fn legacy_address_is_not_accepted_for_another_payment() {
+        let (address, data) = legacy_account("order-3", PaymentStatus::Completed, None);
+        let mut payment = migrate(data, Pubkey::new_unique());
+        payment.payment_id = "order-4".to_string();
+        assert!(!payment.is_address(&address));
+    }
 }
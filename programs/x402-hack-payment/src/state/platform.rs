@@ -1,19 +1,120 @@
 use anchor_lang::prelude::*;
 
+use crate::errors::PaymentError;
+use crate::state::{Customer, Merchant};
+use crate::utils::calculate_bps;
+
+/// New fields must be appended at the end so `migrate_platform_config` can grow
+/// existing accounts in place.
 #[account]
 #[derive(InitSpace)]
 pub struct Platform {
     pub authority: Pubkey,              // Can be a wallet OR a Squads vault
+    /// Treasury for `usdc_mint`; other mints have their own in `AcceptedMint`
     pub treasury: Pubkey,
     pub usdc_mint: Pubkey,
+    /// Default minimum for newly accepted mints
     pub min_payment_amount: u64,
     pub fee_bps: u64,
     pub is_active: bool,
     pub bump: u8,
     pub treasury_bump: u8,
+    /// Proposed authority awaiting `accept_authority`
+    pub pending_authority: Option<Pubkey>,
+    /// Seconds after a payment during which the customer may open a dispute, and after opening
+    /// within which it must be resolved before it lapses; 0 disables disputes
+    pub dispute_window: i64,
+    /// Key allowed to resolve disputes alongside `authority`
+    pub arbiter: Option<Pubkey>,
+    /// Volume discounts, ascending by `min_volume`; below the first tier `fee_bps` applies
+    #[max_len(8)]
+    pub fee_tiers: Vec<FeeTier>,
+    /// Flat fee added to every payment's bps fee
+    pub fixed_fee: u64,
+    /// Floor for the platform fee of a payment
+    pub min_fee: u64,
+    /// Cap for the platform fee of a payment; 0 = uncapped
+    pub max_fee: u64,
+    /// Risk limits for merchants without their own override
+    pub limits: PaymentLimits,
+    /// New merchants need an approved `MerchantApplication`
+    pub require_merchant_approval: bool,
 }
 
 impl Platform {
     pub const SEED: &'static [u8] = b"platform_config";
     pub const TREASURY_SEED: &'static [u8] = b"platform_treasury";
+    pub const MAX_FEE_BPS: u64 = 1000;
+    /// PDA customers approve as SPL delegate for program-initiated pulls
+    pub const DELEGATE_SEED: &'static [u8] = b"payment_delegate";
+
+    /// Must match the `max_len` of `fee_tiers`
+    pub const MAX_FEE_TIERS: usize = 8;
+    /// Index of the tier applying to a merchant with `volume` in the current window
+    pub fn fee_tier(&self, volume: u64) -> Option<u8> {
+        self.fee_tiers
+            .iter()
+            .rposition(|tier| volume >= tier.min_volume)
+            .map(|index| index as u8)
+    }
+
+    /// Platform fee on `amount` and the tier it was charged at
+    pub fn platform_fee(&self, amount: u64, volume: u64) -> Result<(u64, Option<u8>)> {
+        let tier = self.fee_tier(volume);
+        let bps = tier.map_or(self.fee_bps, |index| self.fee_tiers[index as usize].fee_bps);
+
+        let mut fee = calculate_bps(amount, bps)?.saturating_add(self.fixed_fee).max(self.min_fee);
+        if self.max_fee > 0 {
+            fee = fee.min(self.max_fee);
+        }
+        Ok((fee, tier))
+    }
+
+    /// The merchant's override, or the platform defaults
+    pub fn limits_for(&self, merchant: &Merchant) -> PaymentLimits {
+        merchant.limits.unwrap_or(self.limits)
+    }
+
+    pub fn can_resolve_disputes(&self, key: &Pubkey) -> bool {
+        *key == self.authority || self.arbiter == Some(*key)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct FeeTier {
+    /// Merchant volume in the current window at which this tier starts
+    pub min_volume: u64,
+    pub fee_bps: u64,
+}
+
+/// Risk limits on payments; 0 disables a limit
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace, Debug)]
+pub struct PaymentLimits {
+    /// Largest single payment
+    pub max_payment_amount: u64,
+    /// Most a customer may spend per day across all merchants
+    pub customer_daily_limit: u64,
+    /// Most a merchant may take in per day
+    pub merchant_daily_limit: u64,
+}
+
+impl PaymentLimits {
+    /// Rejects a payment of `amount` that would breach any limit
+    pub fn check(&self, amount: u64, customer: &Customer, merchant: &Merchant, now: i64) -> Result<()> {
+        require!(
+            self.max_payment_amount == 0 || amount <= self.max_payment_amount,
+            PaymentError::PaymentAboveMaximum
+        );
+        require!(
+            self.customer_daily_limit == 0
+                || customer.spent_on(now).saturating_add(amount) <= self.customer_daily_limit,
+            PaymentError::CustomerDailyLimitExceeded
+        );
+        require!(
+            self.merchant_daily_limit == 0
+                || merchant.received_on(now).saturating_add(amount) <= self.merchant_daily_limit,
+            PaymentError::MerchantDailyLimitExceeded
+        );
+        Ok(())
+    }
 }
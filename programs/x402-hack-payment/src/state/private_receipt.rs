@@ -35,5 +35,6 @@ pub struct PrivateReceipt {
 }
 
 impl PrivateReceipt {
+    /// Seeded by `[SEED, merchant, payment_id]`; receipts issued earlier use `[SEED, payment_id]`
     pub const SEED: &'static [u8] = b"private_receipt";
 }
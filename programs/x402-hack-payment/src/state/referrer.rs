@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+
+/// A partner registered by the platform authority to receive `share_bps` of the platform
+/// fee on payments it refers. Shares accrue in per-mint vaults owned by this PDA.
+#[account]
+#[derive(InitSpace)]
+pub struct Referrer {
+    pub wallet: Pubkey,
+    /// Share of the platform fee, in bps of the fee
+    pub share_bps: u16,
+    pub is_active: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl Referrer {
+    pub const SEED: &'static [u8] = b"referrer";
+    pub const VAULT_SEED: &'static [u8] = b"referral_vault";
+
+    /// The referrer PDA's fee vault for `mint`
+    pub fn vault_address(referrer: &Pubkey, mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(&[Self::VAULT_SEED, referrer.as_ref(), mint.as_ref()], &crate::ID).0
+    }
+}
@@ -0,0 +1,63 @@
+use anchor_lang::prelude::*;
+
+use crate::utils::BPS_DENOMINATOR;
+
+/// How a merchant's share of split payments is divided between payout wallets
+#[account]
+#[derive(InitSpace)]
+pub struct SplitConfig {
+    pub merchant: Pubkey,
+    #[max_len(8)]
+    pub recipients: Vec<SplitRecipient>,
+    pub updated_at: i64,
+    pub bump: u8,
+}
+
+impl SplitConfig {
+    pub const SEED: &'static [u8] = b"split_config";
+    /// Must match the `max_len` of `recipients` and `PaymentSplit::shares`
+    pub const MAX_RECIPIENTS: usize = 8;
+
+    /// Shares must be non-zero, go to distinct wallets and sum to exactly 100%
+    pub fn is_valid(recipients: &[SplitRecipient]) -> bool {
+        let total: u64 = recipients.iter().map(|recipient| recipient.bps as u64).sum();
+        let distinct = recipients
+            .iter()
+            .enumerate()
+            .all(|(i, recipient)| recipients[..i].iter().all(|other| other.wallet != recipient.wallet));
+
+        !recipients.is_empty()
+            && recipients.len() <= Self::MAX_RECIPIENTS
+            && recipients.iter().all(|recipient| recipient.bps > 0)
+            && distinct
+            && total == BPS_DENOMINATOR
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct SplitRecipient {
+    pub wallet: Pubkey,
+    pub bps: u16,
+}
+
+/// Per-recipient payout of a split payment. `refund_payment` still pulls the full merchant
+/// share from the merchant's settlement account; this records what each recipient received.
+#[account]
+#[derive(InitSpace)]
+pub struct PaymentSplit {
+    pub payment: Pubkey,
+    #[max_len(8)]
+    pub shares: Vec<SplitShare>,
+    pub bump: u8,
+}
+
+impl PaymentSplit {
+    pub const SEED: &'static [u8] = b"payment_split";
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub struct SplitShare {
+    pub wallet: Pubkey,
+    pub token_account: Pubkey,
+    pub amount: u64,
+}
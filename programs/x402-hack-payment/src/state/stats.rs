@@ -0,0 +1,168 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::PaymentError;
+use crate::utils::FeeBreakdown;
+
+pub const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+/// Payments and refunds within one day or month
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, InitSpace, Debug)]
+pub struct StatsBucket {
+    /// Day or month number since the Unix epoch this bucket covers
+    pub period: u32,
+    /// Merchant share settled
+    pub volume: u64,
+    /// Platform fees charged
+    pub fees: u64,
+    pub payments: u32,
+    /// Amount returned to customers
+    pub refunded: u64,
+    /// Merchant share of `refunded`, netted out of `volume` for fee tiers
+    pub refunded_volume: u64,
+    pub refunds: u32,
+}
+
+impl StatsBucket {
+    /// The bucket for `period`, cleared if it still holds an older period
+    fn current(&mut self, period: u32) -> &mut Self {
+        if self.period != period {
+            *self = Self { period, ..Self::default() };
+        }
+        self
+    }
+}
+
+/// Ring buffers of daily and 30-day merchant statistics, so dashboards and fee tiers can
+/// read recent volume without an indexer
+#[account]
+#[derive(InitSpace)]
+pub struct MerchantStats {
+    pub merchant: Pubkey,
+    pub daily: [StatsBucket; 32],
+    /// 30-day months
+    pub monthly: [StatsBucket; 12],
+    pub bump: u8,
+}
+
+impl MerchantStats {
+    pub const SEED: &'static [u8] = b"merchant_stats";
+    pub const DAYS: usize = 32;
+    pub const MONTHS: usize = 12;
+    pub const DAYS_PER_MONTH: i64 = 30;
+    /// Days of volume counted towards fee tiers
+    pub const ROLLING_DAYS: u32 = 30;
+
+    pub fn new(merchant: Pubkey, bump: u8) -> Self {
+        Self {
+            merchant,
+            daily: [StatsBucket::default(); Self::DAYS],
+            monthly: [StatsBucket::default(); Self::MONTHS],
+            bump,
+        }
+    }
+
+    fn day(now: i64) -> u32 {
+        (now / SECONDS_PER_DAY) as u32
+    }
+
+    fn month(now: i64) -> u32 {
+        (now / (SECONDS_PER_DAY * Self::DAYS_PER_MONTH)) as u32
+    }
+
+    fn buckets(&mut self, now: i64) -> [&mut StatsBucket; 2] {
+        let (day, month) = (Self::day(now), Self::month(now));
+        let daily = self.daily[day as usize % Self::DAYS].current(day);
+        let monthly = self.monthly[month as usize % Self::MONTHS].current(month);
+        [daily, monthly]
+    }
+
+    pub fn record_payment(&mut self, fees: &FeeBreakdown) -> Result<()> {
+        for bucket in self.buckets(Clock::get()?.unix_timestamp) {
+            bucket.volume = bucket.volume
+                .checked_add(fees.merchant_amount)
+                .ok_or(PaymentError::CalculationError)?;
+            bucket.fees = bucket.fees
+                .checked_add(fees.fee)
+                .ok_or(PaymentError::CalculationError)?;
+            bucket.payments = bucket.payments
+                .checked_add(1)
+                .ok_or(PaymentError::CalculationError)?;
+        }
+        Ok(())
+    }
+
+    pub fn record_refund(&mut self, amount: u64, merchant_amount: u64) -> Result<()> {
+        for bucket in self.buckets(Clock::get()?.unix_timestamp) {
+            bucket.refunded = bucket.refunded
+                .checked_add(amount)
+                .ok_or(PaymentError::CalculationError)?;
+            bucket.refunded_volume = bucket.refunded_volume
+                .checked_add(merchant_amount)
+                .ok_or(PaymentError::CalculationError)?;
+            bucket.refunds = bucket.refunds
+                .checked_add(1)
+                .ok_or(PaymentError::CalculationError)?;
+        }
+        Ok(())
+    }
+
+    /// Merchant share settled over the last `ROLLING_DAYS` days, including today, less the merchant
+    /// share refunded in that window, so paying and refunding oneself does not climb the fee tiers.
+    /// Netted over the whole window so a refund on a later day still cancels the payment.
+    pub fn rolling_volume(&self, now: i64) -> u64 {
+        let today = Self::day(now);
+        let (volume, refunded) = self.daily
+            .iter()
+            .filter(|bucket| bucket.period <= today && today - bucket.period < Self::ROLLING_DAYS)
+            .fold((0u64, 0u64), |(volume, refunded), bucket| {
+                (volume.saturating_add(bucket.volume), refunded.saturating_add(bucket.refunded_volume))
+            });
+        volume.saturating_sub(refunded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 20_000 * SECONDS_PER_DAY + 3_600;
+
+    fn stats_with(days: &[(i64, u64, u64)]) -> MerchantStats {
+        let mut stats = MerchantStats::new(Pubkey::new_unique(), 255);
+        for &(days_ago, volume, refunded) in days {
+            let day = MerchantStats::day(NOW - days_ago * SECONDS_PER_DAY);
+            let bucket = stats.daily[day as usize % MerchantStats::DAYS].current(day);
+            bucket.volume += volume;
+            bucket.refunded_volume += refunded;
+        }
+        stats
+    }
+
+    #[test]
+    fn rolling_volume_nets_out_refunds() {
+        let stats = stats_with(&[(0, 1_000, 1_000), (1, 500, 0)]);
+        assert_eq!(stats.rolling_volume(NOW), 500);
+    }
+
+    #[test]
+    fn refund_on_a_later_day_cancels_the_payment() {
+        let stats = stats_with(&[(3, 1_000, 0), (0, 0, 1_000)]);
+        assert_eq!(stats.rolling_volume(NOW), 0);
+    }
+
+    #[test]
+    fn rolling_volume_ignores_days_outside_the_window() {
+        let stats = stats_with(&[(30, 1_000, 0), (29, 200, 0)]);
+        assert_eq!(stats.rolling_volume(NOW), 200);
+    }
+
+    #[test]
+    fn rolling_volume_nets_out_only_the_merchant_share_of_refunds() {
+        // Two 10,000 payments at a 150 bps fee settle 9,850 each; refunding one returns the full
+        // 10,000, of which only the merchant's 9,850 came out of volume
+        let mut stats = stats_with(&[(0, 19_700, 9_850)]);
+        let day = MerchantStats::day(NOW);
+        stats.daily[day as usize % MerchantStats::DAYS].refunded = 10_000;
+        assert_eq!(stats.rolling_volume(NOW), 9_850);
+    }
+}
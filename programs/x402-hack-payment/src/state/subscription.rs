@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+
+/// A recurring price offered by a merchant
+#[account]
+#[derive(InitSpace)]
+pub struct SubscriptionPlan {
+    pub merchant: Pubkey,
+    #[max_len(32)]
+    pub plan_id: String,
+    pub mint: Pubkey,
+    /// Charged every `interval` seconds
+    pub amount: u64,
+    pub interval: i64,
+    /// Seconds before the first charge
+    pub trial_period: i64,
+    /// Number of charges before the subscription completes; 0 = unlimited
+    pub max_cycles: u32,
+    /// Inactive plans accept no new subscribers; existing subscriptions keep billing
+    pub is_active: bool,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+impl SubscriptionPlan {
+    pub const SEED: &'static [u8] = b"subscription_plan";
+}
+
+/// A customer's subscription to a plan, billed by the permissionless `charge_subscription` crank
+/// through the program's payment delegate on `customer_token`
+#[account]
+#[derive(InitSpace)]
+pub struct Subscription {
+    pub customer: Pubkey,
+    pub plan: Pubkey,
+    pub merchant: Pubkey,
+    /// Token account the customer approved the payment delegate on
+    pub customer_token: Pubkey,
+    pub status: SubscriptionStatus,
+    pub next_charge_at: i64,
+    pub cycles_charged: u32,
+    pub created_at: i64,
+    pub cancelled_at: Option<i64>,
+    pub bump: u8,
+    /// What is left of the payment delegate approval the customer gave this subscription
+    pub delegated_amount: u64,
+}
+
+impl Subscription {
+    pub const SEED: &'static [u8] = b"subscription";
+
+    /// Payment ID recorded for the next charge; its address is seeded by the subscription and cycle instead
+    pub fn next_payment_id(&self, subscription: &Pubkey) -> String {
+        let key = subscription.to_string();
+        format!("sub-{}-{}", &key[..16], self.cycles_charged)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, InitSpace, Debug)]
+pub enum SubscriptionStatus {
+    Active,
+    Paused,
+    Cancelled,
+    Completed,
+}
@@ -0,0 +1,621 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::ed25519_program;
+use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::associated_token::{
+    create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create,
+};
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_spl::memo::{build_memo, BuildMemo, Memo};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    memo_transfer::memo_required, transfer_fee::TransferFeeConfig, BaseStateWithExtensions,
+    ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::{Account as SplTokenAccount, Mint as SplMint};
+use anchor_spl::token_interface::{
+    approve_checked, revoke, transfer_checked, ApproveChecked, Mint, Revoke, TokenAccount, TokenInterface,
+    TransferChecked,
+};
+
+use crate::errors::PaymentError;
+use crate::state::{Customer, Delegation, Merchant, MerchantReserve, MerchantStats, MerchantVault, Platform, Referrer};
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Mint extensions that let a third party seize, freeze or block funds the program custodies
+pub const RESTRICTED_MINT_EXTENSIONS: [ExtensionType; 5] = [
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+    ExtensionType::NonTransferable,
+    ExtensionType::DefaultAccountState,
+    ExtensionType::ConfidentialTransferMint,
+];
+
+/// `amount * bps / 10_000`, rounded down
+pub fn calculate_bps(amount: u64, bps: u64) -> Result<u64> {
+    let value = (amount as u128)
+        .checked_mul(bps as u128)
+        .ok_or(PaymentError::CalculationError)?
+        .checked_div(BPS_DENOMINATOR as u128)
+        .ok_or(PaymentError::CalculationError)?;
+    u64::try_from(value).map_err(|_| PaymentError::CalculationError.into())
+}
+
+/// Platform and merchant-side fees withheld from a gross payment amount
+#[derive(Clone, Copy)]
+pub struct FeeBreakdown {
+    pub amount: u64,
+    pub fee: u64,
+    pub merchant_fee: u64,
+    pub merchant_amount: u64,
+    /// Portion of `fee` routed to a referrer instead of the treasury
+    pub referral_fee: u64,
+    /// Index into `Platform.fee_tiers` the fee was charged at; `None` for the base rate
+    pub fee_tier: Option<u8>,
+    /// Portion of `merchant_amount` held in the merchant's reserve instead of paid out
+    pub reserve: u64,
+}
+
+impl FeeBreakdown {
+    /// Both fees are computed on the gross amount and withheld from the merchant's share.
+    /// The platform fee follows the schedule tier for the merchant's 30-day volume net of refunds.
+    pub fn calculate(platform: &Platform, merchant: &Merchant, stats: &MerchantStats, amount: u64) -> Result<Self> {
+        let volume = stats.rolling_volume(Clock::get()?.unix_timestamp);
+        let merchant_fee = calculate_bps(amount, merchant.fee as u64)?;
+        let (fee, fee_tier) = platform.platform_fee(amount, volume)?;
+        // Fixed and minimum fees never take more than the merchant's share
+        let fee = fee.min(amount.saturating_sub(merchant_fee));
+        let merchant_amount = amount
+            .checked_sub(fee)
+            .and_then(|remaining| remaining.checked_sub(merchant_fee))
+            .ok_or(PaymentError::CalculationError)?;
+
+        Ok(Self {
+            amount,
+            fee,
+            merchant_fee,
+            merchant_amount,
+            referral_fee: 0,
+            fee_tier,
+            reserve: 0,
+        })
+    }
+
+    /// Routes `share_bps` of the platform fee to a referrer
+    pub fn with_referral(mut self, share_bps: u16) -> Result<Self> {
+        self.referral_fee = calculate_bps(self.fee, share_bps as u64)?;
+        Ok(self)
+    }
+
+    /// Holds `reserve_bps` of the merchant share back in the merchant's reserve
+    pub fn with_reserve(mut self, reserve_bps: u16) -> Result<Self> {
+        self.reserve = calculate_bps(self.merchant_amount, reserve_bps as u64)?;
+        Ok(self)
+    }
+}
+
+/// Routes the referrer's share of the platform fee on a payment to `merchant`.
+/// A merchant's referrer is sticky; unreferred merchants' payments may name one.
+pub fn apply_referral(
+    fees: FeeBreakdown,
+    merchant: &Merchant,
+    mint: &Pubkey,
+    referrer: Option<&Account<Referrer>>,
+    referral_vault: Option<&InterfaceAccount<TokenAccount>>,
+) -> Result<FeeBreakdown> {
+    if let Some(sticky) = merchant.referrer {
+        let referrer = referrer.ok_or(PaymentError::ReferrerRequired)?;
+        require_keys_eq!(referrer.key(), sticky, PaymentError::InvalidReferrer);
+    }
+    match referrer.filter(|referrer| referrer.is_active) {
+        Some(referrer) => {
+            check_referral_vault(referral_vault, &referrer.key(), mint)?;
+            fees.with_referral(referrer.share_bps)
+        }
+        None => Ok(fees),
+    }
+}
+
+/// Errors unless `referral_vault` is the referrer's fee vault for `mint`, the only account
+/// `claim_referral_fees` can pay out of
+pub fn check_referral_vault(
+    referral_vault: Option<&InterfaceAccount<TokenAccount>>,
+    referrer: &Pubkey,
+    mint: &Pubkey,
+) -> Result<()> {
+    let vault = referral_vault.ok_or(PaymentError::MissingReferralVault)?;
+    require_keys_eq!(vault.key(), Referrer::vault_address(referrer, mint), PaymentError::InvalidReferrer);
+    require_keys_eq!(vault.owner, *referrer, PaymentError::InvalidReferrer);
+    require_keys_eq!(vault.mint, *mint, PaymentError::InvalidReferrer);
+    Ok(())
+}
+
+/// Adds `amount` to the delegation tracked for the token account and approves the payment
+/// delegate PDA for the new total
+pub fn approve_delegate<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    delegation: &mut Delegation,
+    delegate: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    delegation.approved_amount = delegation.approved_amount
+        .checked_add(amount)
+        .ok_or(PaymentError::CalculationError)?;
+    set_delegate_approval(token_program, mint, token_account, delegate, owner, delegation.approved_amount)
+}
+
+/// Takes up to `amount` off the delegation tracked for the token account and approves the
+/// payment delegate PDA for what remains, revoking it at zero. Returns the amount taken back.
+pub fn reduce_delegate<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    delegation: &mut Delegation,
+    delegate: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    amount: u64,
+) -> Result<u64> {
+    let released = delegation.release(amount);
+    set_delegate_approval(token_program, mint, token_account, delegate, owner, delegation.approved_amount)?;
+    Ok(released)
+}
+
+/// Sets the payment delegate's approval on `token_account` to exactly `amount`
+fn set_delegate_approval<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_account: &InterfaceAccount<'info, TokenAccount>,
+    delegate: AccountInfo<'info>,
+    owner: AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    if amount == 0 {
+        let cpi_ctx = CpiContext::new(
+            token_program.to_account_info(),
+            Revoke {
+                source: token_account.to_account_info(),
+                authority: owner,
+            },
+        );
+        return revoke(cpi_ctx);
+    }
+
+    let cpi_ctx = CpiContext::new(
+        token_program.to_account_info(),
+        ApproveChecked {
+            to: token_account.to_account_info(),
+            mint: mint.to_account_info(),
+            delegate,
+            authority: owner,
+        },
+    );
+    approve_checked(cpi_ctx, amount, mint.decimals)
+}
+
+/// Checks `settlement_wallet` can receive payouts and creates its ATA for `mint` if missing, so the
+/// first payment can land. Wallets and multisig vaults are system-owned; accounts owned by other
+/// programs are rejected.
+pub fn init_settlement_wallet<'info>(
+    settlement_wallet: &AccountInfo<'info>,
+    settlement_token: &AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    payer: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(
+        settlement_wallet.key() != Pubkey::default() && *settlement_wallet.owner == System::id(),
+        PaymentError::InvalidSettlementWallet
+    );
+    require_keys_eq!(
+        settlement_token.key(),
+        get_associated_token_address_with_program_id(settlement_wallet.key, &mint.key(), &token_program.key()),
+        PaymentError::InvalidSettlementToken
+    );
+
+    let cpi_ctx = CpiContext::new(
+        associated_token_program.to_account_info(),
+        Create {
+            payer,
+            associated_token: settlement_token.clone(),
+            authority: settlement_wallet.clone(),
+            mint: mint.to_account_info(),
+            system_program: system_program.to_account_info(),
+            token_program: token_program.to_account_info(),
+        },
+    );
+    create_idempotent(cpi_ctx)
+}
+
+/// Errors unless `account` is owned by this program and holds an account of type `T`.
+/// Used by migrations, which must run before the account deserializes as `T`.
+pub fn check_migratable<T: Discriminator>(account: &AccountInfo) -> Result<()> {
+    require_keys_eq!(*account.owner, crate::ID, PaymentError::InvalidMigrationAccount);
+    let data = account.try_borrow_data()?;
+    require!(data.starts_with(T::DISCRIMINATOR), PaymentError::InvalidMigrationAccount);
+    Ok(())
+}
+
+/// Grows `account` to `new_len` bytes, topping up rent from `payer` and zero-filling the new
+/// bytes. Returns the previous length; accounts already that large are left untouched.
+pub fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    new_len: usize,
+) -> Result<usize> {
+    let old_len = account.data_len();
+    if old_len >= new_len {
+        return Ok(old_len);
+    }
+
+    let shortfall = Rent::get()?.minimum_balance(new_len).saturating_sub(account.lamports());
+    if shortfall > 0 {
+        let cpi_ctx = CpiContext::new(
+            system_program.clone(),
+            Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        );
+        transfer(cpi_ctx, shortfall)?;
+    }
+
+    account.resize(new_len)?;
+    account.try_borrow_mut_data()?[old_len..].fill(0);
+    Ok(old_len)
+}
+
+/// Where the legs of a payment are sent
+struct SettlementAccounts<'info> {
+    /// `None` when the caller pays the merchant share out itself
+    merchant_token: Option<AccountInfo<'info>>,
+    platform_treasury: AccountInfo<'info>,
+    /// Required when the merchant charges a merchant-side fee
+    merchant_fee_token: Option<AccountInfo<'info>>,
+    /// Required when the payment carries a referral fee
+    referral_vault: Option<AccountInfo<'info>>,
+    /// Required when part of the merchant share is reserved
+    reserve_vault: Option<AccountInfo<'info>>,
+}
+
+/// Merchant vault and reserve a payment pays into while the merchant's settlement is held back
+struct HeldSettlement<'a, 'info> {
+    merchant: &'a Merchant,
+    /// Required while the merchant has a hold period
+    vault: Option<&'a mut Account<'info, MerchantVault>>,
+    vault_tokens: Option<&'a mut InterfaceAccount<'info, TokenAccount>>,
+    /// Required while the merchant holds a reserve
+    reserve: Option<&'a mut Account<'info, MerchantReserve>>,
+    reserve_tokens: Option<&'a mut InterfaceAccount<'info, TokenAccount>>,
+}
+
+impl<'a, 'info> HeldSettlement<'a, 'info> {
+    /// Withholds the merchant's reserve share of `fees`
+    fn apply_reserve(&self, fees: FeeBreakdown) -> Result<FeeBreakdown> {
+        if self.merchant.reserve_bps == 0 {
+            return Ok(fees);
+        }
+        match (&self.reserve, &self.reserve_tokens) {
+            (Some(reserve), Some(tokens)) if tokens.key() == reserve.vault => fees.with_reserve(self.merchant.reserve_bps),
+            _ => err!(PaymentError::MissingMerchantReserve),
+        }
+    }
+
+    /// Where the merchant share goes: its vault while it has a hold period, otherwise `merchant_token`
+    fn merchant_destination(&self, merchant_token: AccountInfo<'info>) -> Result<AccountInfo<'info>> {
+        match (self.merchant.hold_period, &self.vault, &self.vault_tokens) {
+            (None, _, _) => Ok(merchant_token),
+            (Some(_), Some(vault), Some(tokens)) if tokens.key() == vault.vault => Ok(tokens.to_account_info()),
+            _ => err!(PaymentError::MissingMerchantVault),
+        }
+    }
+
+    fn reserve_vault(&self) -> Option<AccountInfo<'info>> {
+        self.reserve_tokens.as_ref().map(|account| account.to_account_info())
+    }
+
+    /// Books what the settlement paid into the vault and reserve as new tranches
+    fn record(self, fees: &FeeBreakdown) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp;
+        if let (Some(hold_period), Some(vault), Some(tokens)) = (self.merchant.hold_period, self.vault, self.vault_tokens) {
+            vault.deposit(received(tokens)?, hold_period, now)?;
+        }
+        if fees.reserve > 0 {
+            if let (Some(reserve), Some(tokens)) = (self.reserve, self.reserve_tokens) {
+                reserve.deposit(received(tokens)?, self.merchant.reserve_period, now)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Everything a payment settles through: the account it is paid from, where each leg goes
+/// and the books it is recorded in
+pub struct PaymentSettlement<'a, 'info> {
+    pub token_transfer: TokenTransfer<'a, 'info>,
+    pub from: AccountInfo<'info>,
+    pub authority: AccountInfo<'info>,
+    pub signer_seeds: &'a [&'a [&'a [u8]]],
+    /// `None` when the customer's spend was recorded as the funds were taken, as escrows do
+    pub customer: Option<&'a mut Customer>,
+    pub merchant: &'a mut Merchant,
+    pub merchant_stats: &'a mut MerchantStats,
+    /// Receives the merchant share, or the merchant vault does while the merchant has a hold
+    /// period. `None` when the caller pays the merchant share out itself, as split payments do.
+    pub merchant_token: Option<AccountInfo<'info>>,
+    pub platform_treasury: AccountInfo<'info>,
+    /// Required when the merchant charges a merchant-side fee
+    pub merchant_fee_token: Option<AccountInfo<'info>>,
+    /// Required when the payment carries a referral fee
+    pub referral_vault: Option<AccountInfo<'info>>,
+    /// Required while the merchant has a hold period
+    pub merchant_vault: Option<&'a mut Account<'info, MerchantVault>>,
+    pub merchant_vault_tokens: Option<&'a mut InterfaceAccount<'info, TokenAccount>>,
+    /// Required while the merchant holds a reserve
+    pub merchant_reserve: Option<&'a mut Account<'info, MerchantReserve>>,
+    pub merchant_reserve_tokens: Option<&'a mut InterfaceAccount<'info, TokenAccount>>,
+}
+
+/// Withholds the merchant's reserve from `fees`, pays every leg of the payment, books what
+/// the vault and reserve received and records the payment for the customer and merchant.
+/// Returns the fees as settled and the total transfer fee withheld by the mint.
+pub fn settle_payment(settlement: PaymentSettlement, fees: FeeBreakdown, memo: &str) -> Result<(FeeBreakdown, u64)> {
+    let PaymentSettlement {
+        token_transfer,
+        from,
+        authority,
+        signer_seeds,
+        customer,
+        merchant,
+        merchant_stats,
+        merchant_token,
+        platform_treasury,
+        merchant_fee_token,
+        referral_vault,
+        merchant_vault,
+        merchant_vault_tokens,
+        merchant_reserve,
+        merchant_reserve_tokens,
+    } = settlement;
+
+    let held = HeldSettlement {
+        merchant,
+        vault: merchant_vault,
+        vault_tokens: merchant_vault_tokens,
+        reserve: merchant_reserve,
+        reserve_tokens: merchant_reserve_tokens,
+    };
+    let fees = held.apply_reserve(fees)?;
+
+    let destinations = SettlementAccounts {
+        merchant_token: merchant_token
+            .map(|merchant_token| held.merchant_destination(merchant_token))
+            .transpose()?,
+        platform_treasury,
+        merchant_fee_token,
+        referral_vault,
+        reserve_vault: held.reserve_vault(),
+    };
+    // Token-2022 transfer fees are withheld in the destination accounts
+    let transfer_fee_amount = token_transfer.settle(from, authority, signer_seeds, &destinations, &fees, memo)?;
+
+    // Hold what actually arrived, net of any Token-2022 transfer fee
+    held.record(&fees)?;
+
+    if let Some(customer) = customer {
+        customer.record_payment(fees.amount)?;
+    }
+    merchant.record_payment(&fees)?;
+    merchant_stats.record_payment(&fees)?;
+
+    Ok((fees, transfer_fee_amount))
+}
+
+/// What arrived in `tokens` since it was loaded, net of any Token-2022 transfer fee
+fn received(tokens: &mut InterfaceAccount<TokenAccount>) -> Result<u64> {
+    let balance = tokens.amount;
+    tokens.reload()?;
+    tokens.amount
+        .checked_sub(balance)
+        .ok_or(PaymentError::CalculationError.into())
+}
+
+/// Restricted extensions present on `mint`; always empty for legacy SPL Token mints
+pub fn restricted_mint_extensions(mint: &AccountInfo) -> Result<Vec<ExtensionType>> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<SplMint>::unpack(&data)?;
+    let extensions = state.get_extension_types()?;
+    Ok(RESTRICTED_MINT_EXTENSIONS
+        .into_iter()
+        .filter(|extension| extensions.contains(extension))
+        .collect())
+}
+
+/// Fee the mint's transfer-fee extension withholds when moving `amount` this epoch
+pub fn transfer_fee(mint: &AccountInfo, amount: u64) -> Result<u64> {
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<SplMint>::unpack(&data)?;
+    match state.get_extension::<TransferFeeConfig>() {
+        Ok(config) => config
+            .calculate_epoch_fee(Clock::get()?.epoch, amount)
+            .ok_or(PaymentError::CalculationError.into()),
+        Err(_) => Ok(0),
+    }
+}
+
+/// Whether `token_account` has the memo-transfer extension requiring incoming memos
+pub fn requires_memo(token_account: &AccountInfo) -> Result<bool> {
+    let data = token_account.try_borrow_data()?;
+    let state = StateWithExtensions::<SplTokenAccount>::unpack(&data)?;
+    Ok(memo_required(&state))
+}
+
+/// Checks that the instruction immediately before the current one is an Ed25519 program
+/// instruction verifying a single signature over exactly `message`. Returns the signing key.
+pub fn verify_ed25519_signature(instructions: &AccountInfo, message: &[u8]) -> Result<Pubkey> {
+    let current = load_current_index_checked(instructions)?;
+    require!(current > 0, PaymentError::InvalidSignature);
+    let ix = load_instruction_at_checked((current - 1) as usize, instructions)?;
+    require_keys_eq!(ix.program_id, ed25519_program::ID, PaymentError::InvalidSignature);
+
+    // [num_signatures, padding, 7 x u16 offsets, ...]
+    let data = &ix.data;
+    require!(data.len() >= 16 && data[0] == 1, PaymentError::InvalidSignature);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]);
+    let public_key_offset = read_u16(6) as usize;
+    let message_offset = read_u16(10) as usize;
+    let message_size = read_u16(12) as usize;
+
+    // Signature, key and message must all live in the Ed25519 instruction itself
+    for index_at in [4, 8, 14] {
+        require!(read_u16(index_at) == u16::MAX, PaymentError::InvalidSignature);
+    }
+
+    let public_key = data
+        .get(public_key_offset..public_key_offset + 32)
+        .ok_or(PaymentError::InvalidSignature)?;
+    let signed = data
+        .get(message_offset..message_offset + message_size)
+        .ok_or(PaymentError::InvalidSignature)?;
+    require!(signed == message, PaymentError::InvalidSignature);
+
+    Pubkey::try_from(public_key).map_err(|_| PaymentError::InvalidSignature.into())
+}
+
+/// Token program, mint and optional memo program shared by every transfer in an instruction
+pub struct TokenTransfer<'a, 'info> {
+    pub token_program: &'a Interface<'info, TokenInterface>,
+    pub mint: &'a InterfaceAccount<'info, Mint>,
+    pub memo_program: Option<&'a Program<'info, Memo>>,
+}
+
+impl<'a, 'info> TokenTransfer<'a, 'info> {
+    /// Moves `amount` with `transfer_checked`, writing `memo` first when the destination
+    /// requires one. Returns the transfer fee withheld by the mint.
+    pub fn transfer(
+        &self,
+        from: AccountInfo<'info>,
+        to: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+        memo: &str,
+    ) -> Result<u64> {
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        if requires_memo(&to)? {
+            let memo_program = self.memo_program.ok_or(PaymentError::MemoProgramRequired)?;
+            build_memo(
+                CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+                memo.as_bytes(),
+            )?;
+        }
+
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from,
+                mint: self.mint.to_account_info(),
+                to,
+                authority,
+            },
+            signer_seeds,
+        );
+        transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+
+        transfer_fee(&self.mint.to_account_info(), amount)
+    }
+
+    /// Splits a payment from `from` into its merchant, treasury and merchant-fee legs.
+    /// Returns the total transfer fee withheld by the mint.
+    fn settle(
+        &self,
+        from: AccountInfo<'info>,
+        authority: AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        destinations: &SettlementAccounts<'info>,
+        fees: &FeeBreakdown,
+        memo: &str,
+    ) -> Result<u64> {
+        let mut transfer_fee_amount = 0;
+
+        // Transfer merchant amount to merchant, less any reserve
+        if let Some(merchant_token) = destinations.merchant_token.clone() {
+            let payout = fees.merchant_amount
+                .checked_sub(fees.reserve)
+                .ok_or(PaymentError::CalculationError)?;
+            transfer_fee_amount += self.transfer(
+                from.clone(),
+                merchant_token,
+                authority.clone(),
+                signer_seeds,
+                payout,
+                memo,
+            )?;
+        }
+
+        if fees.reserve > 0 {
+            let reserve_vault = destinations.reserve_vault
+                .clone()
+                .ok_or(PaymentError::MissingMerchantReserve)?;
+            transfer_fee_amount += self.transfer(
+                from.clone(),
+                reserve_vault,
+                authority.clone(),
+                signer_seeds,
+                fees.reserve,
+                memo,
+            )?;
+        }
+
+        // Transfer fee to platform treasury, less any referrer share
+        let treasury_fee = fees.fee
+            .checked_sub(fees.referral_fee)
+            .ok_or(PaymentError::CalculationError)?;
+        transfer_fee_amount += self.transfer(
+            from.clone(),
+            destinations.platform_treasury.clone(),
+            authority.clone(),
+            signer_seeds,
+            treasury_fee,
+            memo,
+        )?;
+
+        if fees.referral_fee > 0 {
+            let referral_vault = destinations.referral_vault
+                .clone()
+                .ok_or(PaymentError::MissingReferralVault)?;
+            transfer_fee_amount += self.transfer(
+                from.clone(),
+                referral_vault,
+                authority.clone(),
+                signer_seeds,
+                fees.referral_fee,
+                memo,
+            )?;
+        }
+
+        // Transfer merchant-side fee to the merchant's fee recipient
+        if fees.merchant_fee > 0 {
+            let merchant_fee_token = destinations.merchant_fee_token
+                .clone()
+                .ok_or(PaymentError::MissingMerchantFeeAccount)?;
+            transfer_fee_amount += self.transfer(
+                from,
+                merchant_fee_token,
+                authority,
+                signer_seeds,
+                fees.merchant_fee,
+                memo,
+            )?;
+        }
+
+        Ok(transfer_fee_amount)
+    }
+}
@@ -84,6 +84,11 @@ async function main() {
         const idl = JSON.parse(fs.readFileSync(idlPath, "utf-8"));
         const program = new Program(idl, provider);
 
+        const [acceptedMintPDA] = PublicKey.findProgramAddressSync(
+            [Buffer.from("accepted_mint"), USDC_MINT.toBuffer()],
+            program.programId
+        );
+
         console.log("\n🚀 Claiming fees...");
 
         // Call claim_platform_fees
@@ -92,9 +97,10 @@ async function main() {
             .accounts({
                 authority: authority.publicKey,
                 platformConfig: platformConfigPDA,
-                platformTreasuryUsdc: treasuryAta,
-                authorityUsdc: authorityAta,
-                usdcMint: USDC_MINT,
+                acceptedMint: acceptedMintPDA,
+                platformTreasury: treasuryAta,
+                authorityToken: authorityAta,
+                mint: USDC_MINT,
                 tokenProgram: TOKEN_PROGRAM_ID,
                 associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
             })
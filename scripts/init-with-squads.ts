@@ -92,24 +92,24 @@ async function main() {
     if (existingAccount) {
         console.log("\n⚠️  Platform already initialized. Checking authority...");
 
-        // Try to transfer authority to Squads if we're still the authority
+        // Try to propose Squads as the new authority if we're still the authority
         try {
-            console.log("\n🔄 Transferring authority to Squads...");
+            console.log("\n🔄 Proposing Squads as platform authority...");
 
             const tx = await program.methods
-                .transferAuthority()
+                .proposeAuthority(SQUADS_VAULT)
                 .accounts({
                     authority: payer.publicKey,
-                    newAuthority: SQUADS_VAULT,
                     platformConfig: platformConfigPDA,
                 })
                 .signers([payer])
                 .rpc();
 
-            console.log(`\n✅ Authority transferred to Squads!`);
+            console.log(`\n✅ Authority transfer proposed to Squads!`);
             console.log(`   Transaction: https://explorer.solana.com/tx/${tx}?cluster=devnet`);
             console.log(`\n🛡️  Squads Vault: ${SQUADS_VAULT.toBase58()}`);
             console.log(`   Dashboard: https://devnet.squads.so/squads/${SQUADS_VAULT.toBase58()}/home`);
+            console.log(`\nCreate a transaction in Squads calling 'accept_authority' to complete the transfer`);
 
         } catch (error: any) {
             if (error.message?.includes("Unauthorized")) {
@@ -141,30 +141,30 @@ async function main() {
         console.log(`\n✅ Platform initialized!`);
         console.log(`   Transaction: https://explorer.solana.com/tx/${initTx}?cluster=devnet`);
 
-        // Step 2: Transfer authority to Squads
-        console.log("\n🔄 Transferring authority to Squads...");
+        // Step 2: Propose Squads as authority (the vault must accept)
+        console.log("\n🔄 Proposing Squads as platform authority...");
 
         const transferTx = await program.methods
-            .transferAuthority()
+            .proposeAuthority(SQUADS_VAULT)
             .accounts({
                 authority: payer.publicKey,
-                newAuthority: SQUADS_VAULT,
                 platformConfig: platformConfigPDA,
             })
             .signers([payer])
             .rpc();
 
-        console.log(`\n✅ Authority transferred to Squads!`);
+        console.log(`\n✅ Authority transfer proposed to Squads!`);
         console.log(`   Transaction: https://explorer.solana.com/tx/${transferTx}?cluster=devnet`);
 
         console.log("\n" + "=".repeat(50));
         console.log("🎉 SETUP COMPLETE!");
         console.log("=".repeat(50));
-        console.log(`\n🛡️  Platform Authority: ${SQUADS_VAULT.toBase58()}`);
+        console.log(`\n🛡️  Pending Platform Authority: ${SQUADS_VAULT.toBase58()}`);
         console.log(`💰 Treasury: ${platformTreasuryPDA.toBase58()}`);
         console.log(`\n📊 Squads Dashboard:`);
         console.log(`   https://devnet.squads.so/squads/${SQUADS_VAULT.toBase58()}/home`);
-        console.log(`\nTo claim fees, create a transaction in Squads calling 'claim_platform_fees'`);
+        console.log(`\nCreate a transaction in Squads calling 'accept_authority' to complete the transfer`);
+        console.log(`To claim fees, create a transaction in Squads calling 'claim_platform_fees'`);
 
     } catch (error: any) {
         if (error.message?.includes("already in use")) {
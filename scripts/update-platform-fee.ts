@@ -6,8 +6,7 @@
 
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
-import { PublicKey, SystemProgram } from "@solana/web3.js";
-import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { PublicKey } from "@solana/web3.js";
 import { X402HackPayment } from "../target/types/x402_hack_payment";
 
 async function main() {
@@ -23,41 +22,29 @@ async function main() {
     // Minimum payment: 10000 = 0.01 USDC (6 decimals)
     const MIN_PAYMENT_AMOUNT = 10000;
 
-    // USDC Devnet mint
-    const USDC_MINT = new PublicKey("4zMMC9srt5Ri5X14GAgXhaHii3GnPAEERYPJgZJDncDU");
-
     // Derive PDAs
     const [platformConfigPDA] = PublicKey.findProgramAddressSync(
         [Buffer.from("platform_config")],
         program.programId
     );
 
-    const [platformTreasuryPDA] = PublicKey.findProgramAddressSync(
-        [Buffer.from("platform_treasury")],
-        program.programId
-    );
-
     console.log("=== Update Platform Config ===");
     console.log(`Program ID: ${program.programId.toBase58()}`);
     console.log(`Platform Config PDA: ${platformConfigPDA.toBase58()}`);
-    console.log(`Platform Treasury PDA: ${platformTreasuryPDA.toBase58()}`);
     console.log(`New Fee: ${NEW_FEE_BPS} bps (${NEW_FEE_BPS / 100}%)`);
     console.log(`Min Payment: ${MIN_PAYMENT_AMOUNT} (${MIN_PAYMENT_AMOUNT / 1_000_000} USDC)`);
     console.log("");
 
     try {
         const tx = await program.methods
-            .setPlatformConfig(
+            .updatePlatformConfig(
                 new anchor.BN(NEW_FEE_BPS),
-                new anchor.BN(MIN_PAYMENT_AMOUNT)
+                new anchor.BN(MIN_PAYMENT_AMOUNT),
+                null // leave is_active unchanged
             )
             .accountsStrict({
                 authority: provider.wallet.publicKey,
                 platformConfig: platformConfigPDA,
-                platformTreasury: platformTreasuryPDA,
-                usdcMint: USDC_MINT,
-                systemProgram: SystemProgram.programId,
-                tokenProgram: TOKEN_PROGRAM_ID,
             })
             .rpc();
 
@@ -71,6 +58,7 @@ async function main() {
         console.log("Updated Platform Config:");
         console.log(`  Fee: ${config.feeBps.toString()} bps (${Number(config.feeBps) / 100}%)`);
         console.log(`  Min Payment: ${config.minPaymentAmount.toString()}`);
+        console.log(`  Active: ${config.isActive}`);
         console.log(`  Authority: ${config.authority.toBase58()}`);
 
     } catch (error) {
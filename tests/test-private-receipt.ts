@@ -28,9 +28,9 @@ const INCO_LIGHTNING_PROGRAM_ID = new PublicKey('5sjEbPiqgZrYwR31ahR6Uk9wf5awoX6
 const SETTLR_PROGRAM_ID = new PublicKey('339A4zncMj8fbM2zvEopYXu6TZqRieJKebDiXCKwquA5');
 
 // Helper to derive private receipt PDA
-function findPrivateReceiptPda(paymentId: string): [PublicKey, number] {
+function findPrivateReceiptPda(merchant: PublicKey, paymentId: string): [PublicKey, number] {
     return PublicKey.findProgramAddressSync(
-        [Buffer.from('private_receipt'), Buffer.from(paymentId)],
+        [Buffer.from('private_receipt'), merchant.toBuffer(), Buffer.from(paymentId)],
         SETTLR_PROGRAM_ID
     );
 }
@@ -113,7 +113,7 @@ async function main() {
     console.log('   Merchant:', merchantWallet.toBase58());
 
     // Derive PDAs
-    const [privateReceiptPda, receiptBump] = findPrivateReceiptPda(paymentId);
+    const [privateReceiptPda, receiptBump] = findPrivateReceiptPda(merchantWallet, paymentId);
     console.log('\n🔑 Private Receipt PDA:', privateReceiptPda.toBase58());
 
     // Create encrypted amount (mock - would use Inco encryption API in production)
@@ -1,8 +1,16 @@
 import * as anchor from "@coral-xyz/anchor";
 import { BN, Program } from "@coral-xyz/anchor";
-import { Keypair, PublicKey, SystemProgram, Transaction } from "@solana/web3.js";
+import {
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+} from "@solana/web3.js";
 import {
   createMint,
+  getAssociatedTokenAddressSync,
   getOrCreateAssociatedTokenAccount,
   mintTo,
   TOKEN_PROGRAM_ID,
@@ -23,7 +31,9 @@ describe("x402-hack-payment", () => {
   let platformConfigPDA: PublicKey;
   let platformTreasuryPDA: PublicKey;
   let usdcMint: PublicKey;
+  let usdcAcceptedMintPDA: PublicKey;
   let merchantAccountPDA: PublicKey;
+  let merchantStatsPDA: PublicKey;
   let merchantBump: number;
   const settlementWallet = Keypair.generate();
 
@@ -49,6 +59,11 @@ describe("x402-hack-payment", () => {
     );
     console.log(`\nMerchant Account PDA: ${merchantAccountPDA.toBase58()}`);
 
+    [merchantStatsPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("merchant_stats"), merchantAccountPDA.toBuffer()],
+      program.programId,
+    );
+
     usdcMint = await createMint(
       connection,
       authority.payer,
@@ -57,6 +72,11 @@ describe("x402-hack-payment", () => {
       6 // USDC has 6 decimal places
     );
     console.log(`\nUSDC Mint: ${usdcMint.toBase58()}`);
+
+    [usdcAcceptedMintPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from("accepted_mint"), usdcMint.toBuffer()],
+      program.programId,
+    );
   });
 
   it("Platform is initialized!", async () => {
@@ -70,6 +90,7 @@ describe("x402-hack-payment", () => {
         platformConfig: platformConfigPDA,
         platformTreasury: platformTreasuryPDA,
         usdcMint: usdcMint,
+        usdcAcceptedMint: usdcAcceptedMintPDA,
         systemProgram: SystemProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
@@ -90,8 +111,21 @@ describe("x402-hack-payment", () => {
       .accountsStrict({
         payer: authority.publicKey,
         merchantAccount: merchantAccountPDA,
+        merchantStats: merchantStatsPDA,
         platformConfig: platformConfigPDA,
+        reservedMerchantId: PublicKey.findProgramAddressSync(
+          [Buffer.from("reserved_merchant_id"), Buffer.from(MERCHANT_ID)],
+          program.programId,
+        )[0],
+        // Merchant approval is off by default
+        merchantApplication: null,
         settlementWallet: settlementWallet.publicKey,
+        mint: usdcMint,
+        acceptedMint: usdcAcceptedMintPDA,
+        // Created by initialize_merchant so the first payment can settle
+        settlementToken: getAssociatedTokenAddressSync(usdcMint, settlementWallet.publicKey),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -112,7 +146,7 @@ describe("x402-hack-payment", () => {
 
     // Derive payment account PDA
     const [paymentAccountPDA] = PublicKey.findProgramAddressSync(
-      [Buffer.from("payment"), Buffer.from(paymentId)],
+      [Buffer.from("payment"), merchantAccountPDA.toBuffer(), Buffer.from(paymentId)],
       program.programId,
     );
     console.log(`\nPayment Account PDA: ${paymentAccountPDA.toBase58()}`);
@@ -154,22 +188,53 @@ describe("x402-hack-payment", () => {
 
     // Process payment
     const paymentAmount = 20000; // 0.02 USDC
+    const expiry = Math.floor(Date.now() / 1000) + 600;
+
+    // Merchant authority signs the payment intent: merchant || mint || amount || expiry || payment_id
+    const intent = Buffer.concat([
+      Buffer.from("x402-payment-intent"),
+      merchantAccountPDA.toBuffer(),
+      usdcMint.toBuffer(),
+      new BN(paymentAmount).toArrayLike(Buffer, "le", 8),
+      new BN(expiry).toArrayLike(Buffer, "le", 8),
+      Buffer.from(paymentId),
+    ]);
+    const intentSignatureIx = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: authority.payer.secretKey,
+      message: intent,
+    });
+
     const tx = await program.methods
-      .processPayment(paymentId, new BN(paymentAmount))
+      .processPayment(paymentId, new BN(paymentAmount), new BN(expiry))
       .accountsStrict({
         payer: user.publicKey,
         platformConfig: platformConfigPDA,
         paymentAccount: paymentAccountPDA,
         customerAccount: customerAccountPDA,
         merchantAccount: merchantAccountPDA,
-        usdcMint: usdcMint,
-        customerUsdc: customerUsdcAccount.address,
-        merchantUsdc: merchantUsdcAccount.address,
-        platformTreasuryUsdc: platformTreasuryPDA,
+        merchantStats: merchantStatsPDA,
+        mint: usdcMint,
+        acceptedMint: usdcAcceptedMintPDA,
+        customerToken: customerUsdcAccount.address,
+        merchantToken: merchantUsdcAccount.address,
+        // Only needed when the merchant has a hold period
+        merchantVault: null,
+        merchantVaultTokens: null,
+        // Only needed when the merchant holds a reserve
+        merchantReserve: null,
+        merchantReserveTokens: null,
+        // Fee recipient defaults to the settlement wallet
+        merchantFeeToken: merchantUsdcAccount.address,
+        platformTreasury: platformTreasuryPDA,
+        referrer: null,
+        referralVault: null,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        memoProgram: null,
         systemProgram: SystemProgram.programId,
       })
+      .preInstructions([intentSignatureIx])
       .signers([user])
       .rpc();
     console.log(`\nTransaction signature: ${tx}`);
@@ -0,0 +1,45 @@
+import { createMint, TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  addMint,
+  authority,
+  connection,
+  createCustomer,
+  createMerchant,
+  expectError,
+  pay,
+  pda,
+  platform,
+  program,
+  tokenBalance,
+} from "./setup";
+
+describe("accepted mints", () => {
+  before(async () => {
+    await platform();
+  });
+
+  it("takes payments in a second mint into that mint's treasury", async () => {
+    const mint = await createMint(connection, authority.payer, authority.publicKey, null, 6);
+    await addMint(mint, TOKEN_PROGRAM_ID);
+    const merchant = await createMerchant("eurc", { mint });
+    const customer = await createCustomer(mint);
+
+    await pay(merchant, customer, 100000);
+    expect(merchant.treasury.toBase58()).to.equal(pda.mintTreasury(mint).toBase58());
+    expect(await tokenBalance(merchant.treasury)).to.equal(2500);
+  });
+
+  it("rejects payments in a disabled mint", async () => {
+    const mint = await createMint(connection, authority.payer, authority.publicKey, null, 6);
+    await addMint(mint, TOKEN_PROGRAM_ID);
+    const merchant = await createMerchant("eurc", { mint });
+    const customer = await createCustomer(mint);
+
+    await program.methods
+      .updateAcceptedMint(null, false)
+      .accountsStrict({ authority: authority.publicKey, platformConfig: pda.platform(), acceptedMint: merchant.acceptedMint })
+      .rpc();
+    await expectError(pay(merchant, customer, 100000), "MintNotAccepted");
+  });
+});
@@ -0,0 +1,144 @@
+import { BN } from "@coral-xyz/anchor";
+import { SystemProgram } from "@solana/web3.js";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  connection,
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  directSettlement,
+  eventOf,
+  expectError,
+  MerchantFixture,
+  pda,
+  platform,
+  program,
+  uniqueId,
+} from "./setup";
+
+describe("spending allowances", () => {
+  let merchant: MerchantFixture;
+
+  before(async () => {
+    await platform();
+    merchant = await createMerchant("allowance");
+  });
+
+  function approve(customer: CustomerFixture, limitPerPeriod: number, delegateAmount: number, target = merchant) {
+    return program.methods
+      .approveAllowance(new BN(limitPerPeriod), new BN(86400), null, new BN(delegateAmount))
+      .accountsStrict({
+        customer: customer.wallet.publicKey,
+        merchantAccount: target.merchant,
+        allowance: pda.allowance(customer.wallet.publicKey, target.merchant),
+        customerAccount: customer.customer,
+        mint: target.mint,
+        acceptedMint: target.acceptedMint,
+        customerToken: customer.token,
+        delegation: pda.delegation(customer.token),
+        paymentDelegate: pda.paymentDelegate(),
+        tokenProgram: target.tokenProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer.wallet])
+      .rpc();
+  }
+
+  function charge(customer: CustomerFixture, amount: number, target = merchant) {
+    const paymentId = uniqueId("allow");
+    return program.methods
+      .chargeWithAllowance(paymentId, new BN(amount))
+      .accountsStrict({
+        authority: target.authority.publicKey,
+        platformConfig: pda.platform(),
+        merchantAccount: target.merchant,
+        merchantStats: target.stats,
+        allowance: pda.allowance(customer.wallet.publicKey, target.merchant),
+        customerAccount: customer.customer,
+        paymentAccount: pda.payment(target.merchant, paymentId),
+        mint: target.mint,
+        acceptedMint: target.acceptedMint,
+        customerToken: customer.token,
+        delegation: pda.delegation(customer.token),
+        merchantToken: target.settlementToken,
+        ...directSettlement(target),
+        platformTreasury: target.treasury,
+        paymentDelegate: pda.paymentDelegate(),
+        tokenProgram: target.tokenProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([target.authority])
+      .rpc();
+  }
+
+  function revoke(customer: CustomerFixture, target = merchant) {
+    return program.methods
+      .revokeAllowance()
+      .accountsStrict({
+        customer: customer.wallet.publicKey,
+        allowance: pda.allowance(customer.wallet.publicKey, target.merchant),
+        mint: target.mint,
+        customerToken: customer.token,
+        delegation: pda.delegation(customer.token),
+        paymentDelegate: pda.paymentDelegate(),
+        tokenProgram: target.tokenProgram,
+      })
+      .signers([customer.wallet])
+      .rpc({ commitment: "confirmed" });
+  }
+
+  it("lets the merchant charge within the allowance", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await approve(customer, 50000, 100000);
+
+    await charge(customer, 30000);
+    const allowance = await program.account.spendingAllowance.fetch(
+      pda.allowance(customer.wallet.publicKey, merchant.merchant),
+    );
+    expect(allowance.spentInPeriod.toNumber()).to.equal(30000);
+    expect(allowance.delegatedAmount.toNumber()).to.equal(70000);
+  });
+
+  it("rejects a charge over the period limit", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await approve(customer, 50000, 100000);
+    await charge(customer, 30000);
+
+    await expectError(charge(customer, 30000), "AllowanceExceeded");
+  });
+
+  it("takes the uncharged delegation back on revoke", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await approve(customer, 50000, 100000);
+    await charge(customer, 30000);
+
+    const revoked = await eventOf(await revoke(customer), "AllowanceRevoked");
+    expect(revoked.releasedAmount.toNumber()).to.equal(70000);
+    expect((await getAccount(connection, customer.token)).delegate).to.be.null;
+    await expectError(charge(customer, 10000), "AccountNotInitialized");
+  });
+
+  it("keeps the delegation of other allowances on the token account when one is revoked", async () => {
+    const other = await createMerchant("allowance");
+    const customer = await createCustomer(merchant.mint);
+    await approve(customer, 50000, 100000);
+    await approve(customer, 50000, 50000, other);
+    expect((await getAccount(connection, customer.token)).delegatedAmount).to.equal(150000n);
+    await charge(customer, 30000);
+
+    const revoked = await eventOf(await revoke(customer), "AllowanceRevoked");
+    expect(revoked.releasedAmount.toNumber()).to.equal(70000);
+    expect((await getAccount(connection, customer.token)).delegatedAmount).to.equal(50000n);
+    await charge(customer, 20000, other);
+  });
+
+  it("rejects a charge beyond the delegation approved through the allowance", async () => {
+    const other = await createMerchant("allowance");
+    const customer = await createCustomer(merchant.mint);
+    await approve(customer, 50000, 10000);
+    await approve(customer, 50000, 100000, other);
+
+    await expectError(charge(customer, 20000), "AllowanceDelegationExceeded");
+  });
+});
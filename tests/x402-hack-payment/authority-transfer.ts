@@ -0,0 +1,66 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import { authority, expectError, platform, PlatformFixture, program } from "./setup";
+
+describe("platform authority transfer", () => {
+  let fixture: PlatformFixture;
+  let admin: { authority: PublicKey; platformConfig: PublicKey };
+
+  before(async () => {
+    fixture = await platform();
+    admin = { authority: authority.publicKey, platformConfig: fixture.config };
+  });
+
+  it("hands authority over only once the new authority accepts", async () => {
+    const next = Keypair.generate();
+    await program.methods.proposeAuthority(next.publicKey).accountsStrict(admin).rpc();
+    let config = await program.account.platform.fetch(fixture.config);
+    expect(config.authority.toBase58()).to.equal(authority.publicKey.toBase58());
+    expect(config.pendingAuthority.toBase58()).to.equal(next.publicKey.toBase58());
+
+    await program.methods
+      .acceptAuthority()
+      .accountsStrict({ newAuthority: next.publicKey, platformConfig: fixture.config })
+      .signers([next])
+      .rpc();
+    config = await program.account.platform.fetch(fixture.config);
+    expect(config.authority.toBase58()).to.equal(next.publicKey.toBase58());
+    expect(config.pendingAuthority).to.be.null;
+
+    // Hand it back so the remaining specs keep the provider wallet as authority
+    await program.methods
+      .proposeAuthority(authority.publicKey)
+      .accountsStrict({ authority: next.publicKey, platformConfig: fixture.config })
+      .signers([next])
+      .rpc();
+    await program.methods
+      .acceptAuthority()
+      .accountsStrict({ newAuthority: authority.publicKey, platformConfig: fixture.config })
+      .rpc();
+  });
+
+  it("rejects acceptance by anyone but the proposed authority", async () => {
+    const next = Keypair.generate();
+    const impostor = Keypair.generate();
+    await program.methods.proposeAuthority(next.publicKey).accountsStrict(admin).rpc();
+
+    await expectError(
+      program.methods
+        .acceptAuthority()
+        .accountsStrict({ newAuthority: impostor.publicKey, platformConfig: fixture.config })
+        .signers([impostor])
+        .rpc(),
+      "Unauthorized",
+    );
+
+    await program.methods.cancelAuthorityTransfer().accountsStrict(admin).rpc();
+    await expectError(
+      program.methods
+        .acceptAuthority()
+        .accountsStrict({ newAuthority: next.publicKey, platformConfig: fixture.config })
+        .signers([next])
+        .rpc(),
+      "Unauthorized",
+    );
+  });
+});
@@ -0,0 +1,178 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createEscrow,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  fund,
+  heldSettlement,
+  MerchantFixture,
+  now,
+  pda,
+  platform,
+  PlatformFixture,
+  program,
+  releaseEscrow,
+  sleep,
+  tokenBalance,
+} from "./setup";
+
+describe("disputes", () => {
+  let fixture: PlatformFixture;
+  let merchant: MerchantFixture;
+  let previousWindow: BN;
+  let previousArbiter: PublicKey | null;
+  const admin = () => ({ authority: authority.publicKey, platformConfig: fixture.config });
+
+  before(async () => {
+    fixture = await platform();
+    merchant = await createMerchant("disputes");
+    ({ disputeWindow: previousWindow, arbiter: previousArbiter } = await program.account.platform.fetch(fixture.config));
+    await program.methods.setDisputeConfig(new BN(86400), null).accountsStrict(admin()).rpc();
+  });
+
+  after(async () => {
+    await program.methods.setDisputeConfig(previousWindow, previousArbiter).accountsStrict(admin()).rpc();
+  });
+
+  function openDispute(customer: Keypair, payment: PublicKey) {
+    return program.methods
+      .openDispute(Array(32).fill(1))
+      .accountsStrict({
+        customer: customer.publicKey,
+        platformConfig: fixture.config,
+        paymentAccount: payment,
+        escrow: pda.escrow(payment),
+        dispute: pda.dispute(payment),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer])
+      .rpc();
+  }
+
+  function resolve(payment: PublicKey, customer: CustomerFixture, amount: number) {
+    return program.methods
+      .resolveDispute({ refundPartial: { amount: new BN(amount) } })
+      .accountsStrict({
+        resolver: authority.publicKey,
+        platformConfig: fixture.config,
+        dispute: pda.dispute(payment),
+        paymentAccount: payment,
+        escrow: pda.escrow(payment),
+        escrowVault: pda.escrowVault(payment),
+        customerAccount: customer.customer,
+        merchantAccount: merchant.merchant,
+        merchantStats: merchant.stats,
+        mint: merchant.mint,
+        acceptedMint: merchant.acceptedMint,
+        customerToken: customer.token,
+        merchantToken: merchant.settlementToken,
+        ...heldSettlement(merchant),
+        merchantFeeToken: merchant.settlementToken,
+        platformTreasury: merchant.treasury,
+        referralVault: null,
+        tokenProgram: merchant.tokenProgram,
+        memoProgram: null,
+      })
+      .rpc();
+  }
+
+  function lapse(payment: PublicKey) {
+    return program.methods
+      .lapseDispute()
+      .accountsStrict({
+        caller: authority.publicKey,
+        platformConfig: fixture.config,
+        dispute: pda.dispute(payment),
+        escrow: pda.escrow(payment),
+      })
+      .rpc();
+  }
+
+  it("lets the platform settle a dispute with a partial refund", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+    await openDispute(customer.wallet, payment);
+    await expectError(releaseEscrow(merchant, customer.wallet, payment), "EscrowDisputed");
+
+    await program.methods
+      .submitDisputeEvidence(Array(32).fill(2))
+      .accountsStrict({
+        submitter: merchant.authority.publicKey,
+        dispute: pda.dispute(payment),
+        merchantAccount: merchant.merchant,
+      })
+      .signers([merchant.authority])
+      .rpc();
+
+    const before = await tokenBalance(customer.token);
+    await resolve(payment, customer, 30000);
+
+    expect((await tokenBalance(customer.token)) - before).to.equal(30000);
+    const dispute = await program.account.dispute.fetch(pda.dispute(payment));
+    expect(dispute.status).to.deep.equal({ partiallyRefunded: {} });
+    expect(dispute.refundedAmount.toNumber()).to.equal(30000);
+    expect(dispute.merchantEvidence).to.deep.equal(Array(32).fill(2));
+    const record = await program.account.payment.fetch(payment);
+    expect(record.refundedAmount.toNumber()).to.equal(30000);
+    expect(record.refundedNetAmount.toNumber()).to.equal(30000);
+  });
+
+  it("pays out exactly what the vault holds after an uneven partial refund", async () => {
+    const customer = await createCustomer(merchant.mint, 2_000_000);
+    const payment = await createEscrow(merchant, customer, 1_000_000, now() + 3600);
+    await openDispute(customer.wallet, payment);
+    const merchantBefore = await tokenBalance(merchant.settlementToken);
+
+    await resolve(payment, customer, 333_333);
+    expect(await tokenBalance(pda.escrowVault(payment))).to.equal(0);
+    // 25,000 fee less its refunded share of 8,333; the merchant takes the rest of the 666,667
+    expect((await tokenBalance(merchant.settlementToken)) - merchantBefore).to.equal(650_000);
+    expect((await program.account.payment.fetch(payment)).refundedNetAmount.toNumber()).to.equal(333_333);
+  });
+
+  it("unfreezes the escrow of a dispute left unresolved for the window", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+    await program.methods.setDisputeConfig(new BN(5), null).accountsStrict(admin()).rpc();
+
+    try {
+      await openDispute(customer.wallet, payment);
+      await expectError(lapse(payment), "DisputeResolutionPending");
+      await sleep(7000);
+
+      await lapse(payment);
+      expect((await program.account.dispute.fetch(pda.dispute(payment))).status).to.deep.equal({ lapsed: {} });
+      expect((await program.account.escrow.fetch(pda.escrow(payment))).disputed).to.equal(false);
+      await expectError(resolve(payment, customer, 30000), "DisputeNotOpen");
+      await releaseEscrow(merchant, customer.wallet, payment);
+    } finally {
+      await program.methods.setDisputeConfig(new BN(86400), null).accountsStrict(admin()).rpc();
+    }
+  });
+
+  it("rejects a dispute opened by anyone but the customer", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+    const stranger = Keypair.generate();
+    await fund(stranger.publicKey);
+
+    await expectError(openDispute(stranger, payment), "Unauthorized");
+  });
+
+  it("rejects disputes while the platform has no dispute window", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+    await program.methods.setDisputeConfig(new BN(0), null).accountsStrict(admin()).rpc();
+
+    try {
+      await expectError(openDispute(customer.wallet, payment), "DisputeWindowClosed");
+    } finally {
+      await program.methods.setDisputeConfig(new BN(86400), null).accountsStrict(admin()).rpc();
+    }
+  });
+});
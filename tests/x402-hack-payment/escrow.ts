@@ -0,0 +1,125 @@
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createEscrow,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  MerchantFixture,
+  now,
+  pda,
+  platform,
+  program,
+  refund,
+  releaseEscrow,
+  sleep,
+  tokenBalance,
+} from "./setup";
+
+describe("escrowed payments", () => {
+  let merchant: MerchantFixture;
+
+  before(async () => {
+    await platform();
+    merchant = await createMerchant("escrow");
+  });
+
+  function expire(payment: PublicKey, customer: CustomerFixture) {
+    return program.methods
+      .expireEscrow()
+      .accountsStrict({
+        caller: authority.publicKey,
+        paymentAccount: payment,
+        escrow: pda.escrow(payment),
+        escrowVault: pda.escrowVault(payment),
+        customerAccount: customer.customer,
+        mint: merchant.mint,
+        customerToken: customer.token,
+        tokenProgram: merchant.tokenProgram,
+        memoProgram: null,
+      })
+      .rpc();
+  }
+
+  function confirmDelivery(payment: PublicKey) {
+    return program.methods
+      .confirmDelivery()
+      .accountsStrict({
+        authority: merchant.authority.publicKey,
+        merchantAccount: merchant.merchant,
+        paymentAccount: payment,
+        escrow: pda.escrow(payment),
+      })
+      .signers([merchant.authority])
+      .rpc();
+  }
+
+  it("holds funds until the customer releases them", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+    expect(await tokenBalance(pda.escrowVault(payment))).to.equal(100000);
+    const merchantBefore = await tokenBalance(merchant.settlementToken);
+
+    await confirmDelivery(payment);
+    await releaseEscrow(merchant, customer.wallet, payment);
+    expect((await tokenBalance(merchant.settlementToken)) - merchantBefore).to.equal(97500);
+    expect((await program.account.payment.fetch(payment)).status).to.deep.equal({ released: {} });
+  });
+
+  it("counts escrowed funds against the customer's spend when they are escrowed", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await createEscrow(merchant, customer, 100000, now() + 3600);
+
+    const record = await program.account.customer.fetch(customer.customer);
+    expect(record.spentToday.toNumber()).to.equal(100000);
+    expect(record.transactionCount.toNumber()).to.equal(1);
+  });
+
+  it("rejects an escrow without the merchant's signed intent", async () => {
+    const customer = await createCustomer(merchant.mint);
+
+    await expectError(createEscrow(merchant, customer, 100000, now() + 3600, Keypair.generate()), "InvalidSignature");
+  });
+
+  it("lets the merchant refund a released escrow", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const before = await tokenBalance(customer.token);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+    await releaseEscrow(merchant, customer.wallet, payment);
+
+    await refund(merchant, customer, payment);
+    expect(await tokenBalance(customer.token)).to.equal(before);
+    expect((await program.account.payment.fetch(payment)).status).to.deep.equal({ refunded: {} });
+  });
+
+  it("rejects a merchant release before the delivery deadline", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+    await confirmDelivery(payment);
+
+    await expectError(releaseEscrow(merchant, merchant.authority, payment), "EscrowNotReleasable");
+  });
+
+  it("rejects expiring an escrow before its deadline", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 3600);
+
+    await expectError(expire(payment, customer), "DeliveryDeadlineNotReached");
+  });
+
+  it("returns undelivered funds to the customer after the deadline", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const before = await tokenBalance(customer.token);
+    const payment = await createEscrow(merchant, customer, 100000, now() + 2);
+    await sleep(5000);
+
+    await expire(payment, customer);
+    expect(await tokenBalance(customer.token)).to.equal(before);
+    const record = await program.account.payment.fetch(payment);
+    expect(record.status).to.deep.equal({ expired: {} });
+    expect(record.refundedNetAmount.toNumber()).to.equal(100000);
+    expect((await program.account.customer.fetch(customer.customer)).totalRefunded.toNumber()).to.equal(100000);
+  });
+});
@@ -0,0 +1,45 @@
+import { expect } from "chai";
+import {
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  eventOf,
+  expectError,
+  MerchantFixture,
+  pay,
+  platform,
+  program,
+  refund,
+} from "./setup";
+
+describe("events", () => {
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+
+  before(async () => {
+    const { usdcMint } = await platform();
+    merchant = await createMerchant("events");
+    customer = await createCustomer(usdcMint);
+  });
+
+  it("emits PaymentProcessed and PaymentRefunded", async () => {
+    const { payment, paymentId, signature } = await pay(merchant, customer, 40000);
+    const processed = await eventOf(signature, "PaymentProcessed");
+    expect(processed.paymentId).to.equal(paymentId);
+    expect(processed.amount.toNumber()).to.equal(40000);
+    expect(processed.feeAmount.toNumber()).to.equal(1000);
+
+    const refunded = await eventOf(await refund(merchant, customer, payment, 15000), "PaymentRefunded");
+    expect(refunded.payment.toBase58()).to.equal(payment.toBase58());
+    expect(refunded.amount.toNumber()).to.equal(15000);
+  });
+
+  it("records nothing when the refund is rejected", async () => {
+    const { payment } = await pay(merchant, customer, 20000);
+
+    await expectError(refund(merchant, customer, payment, 30000), "RefundExceedsRemaining");
+    const record = await program.account.payment.fetch(payment);
+    expect(record.refundedAmount.toNumber()).to.equal(0);
+    expect(record.status).to.deep.equal({ completed: {} });
+  });
+});
@@ -0,0 +1,72 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  expectError,
+  pay,
+  platform,
+  PlatformFixture,
+  program,
+  resetPlatform,
+} from "./setup";
+
+describe("fee schedule", () => {
+  let fixture: PlatformFixture;
+  let admin: { authority: PublicKey; platformConfig: PublicKey };
+
+  before(async () => {
+    fixture = await platform();
+    admin = { authority: authority.publicKey, platformConfig: fixture.config };
+  });
+
+  afterEach(() => resetPlatform(fixture.usdcMint));
+
+  it("charges the tier matching the merchant's volume, within the fee bounds", async () => {
+    await program.methods
+      .setFeeSchedule(
+        [
+          { minVolume: new BN(0), feeBps: new BN(100) },
+          { minVolume: new BN(1_000_000_000), feeBps: new BN(50) },
+        ],
+        new BN(0),
+        new BN(0),
+        new BN(0),
+      )
+      .accountsStrict(admin)
+      .rpc();
+    const merchant = await createMerchant("tiers");
+    const customer = await createCustomer(fixture.usdcMint);
+
+    const { payment } = await pay(merchant, customer, 100000);
+    let record = await program.account.payment.fetch(payment);
+    expect(record.feeAmount.toNumber()).to.equal(1000);
+    expect(record.feeTier).to.equal(0);
+
+    await program.methods.setFeeSchedule([], new BN(0), new BN(0), new BN(300)).accountsStrict(admin).rpc();
+    const capped = await pay(merchant, customer, 100000);
+    record = await program.account.payment.fetch(capped.payment);
+    expect(record.feeAmount.toNumber()).to.equal(300);
+    expect(record.feeTier).to.be.null;
+  });
+
+  it("rejects tiers that do not increase in volume", async () => {
+    await expectError(
+      program.methods
+        .setFeeSchedule(
+          [
+            { minVolume: new BN(5000), feeBps: new BN(100) },
+            { minVolume: new BN(5000), feeBps: new BN(50) },
+          ],
+          new BN(0),
+          new BN(0),
+          new BN(0),
+        )
+        .accountsStrict(admin)
+        .rpc(),
+      "InvalidFeeSchedule",
+    );
+  });
+});
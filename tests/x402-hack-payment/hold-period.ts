@@ -0,0 +1,97 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  heldSettlement,
+  MerchantFixture,
+  pay,
+  pda,
+  platform,
+  PlatformFixture,
+  program,
+  refund,
+  tokenBalance,
+} from "./setup";
+
+describe("held settlement", () => {
+  let fixture: PlatformFixture;
+  let admin: { authority: PublicKey; platformConfig: PublicKey };
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+  const vaultTokens = () => pda.merchantVaultTokens(merchant.merchant, merchant.mint);
+
+  before(async () => {
+    fixture = await platform();
+    admin = { authority: authority.publicKey, platformConfig: fixture.config };
+    merchant = await createMerchant("held");
+    customer = await createCustomer(fixture.usdcMint);
+    await program.methods
+      .setMerchantHoldPeriod(new BN(0))
+      .accountsStrict({ ...admin, merchantAccount: merchant.merchant })
+      .rpc();
+    await program.methods
+      .openMerchantVault()
+      .accountsStrict({
+        payer: merchant.authority.publicKey,
+        merchantAccount: merchant.merchant,
+        mint: merchant.mint,
+        acceptedMint: merchant.acceptedMint,
+        merchantVault: pda.merchantVault(merchant.merchant, merchant.mint),
+        vaultTokens: vaultTokens(),
+        tokenProgram: merchant.tokenProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchant.authority])
+      .rpc();
+  });
+
+  function withdraw() {
+    return program.methods
+      .withdrawMerchantBalance()
+      .accountsStrict({
+        authority: merchant.authority.publicKey,
+        merchantAccount: merchant.merchant,
+        mint: merchant.mint,
+        merchantVault: pda.merchantVault(merchant.merchant, merchant.mint),
+        vaultTokens: vaultTokens(),
+        merchantToken: merchant.settlementToken,
+        tokenProgram: merchant.tokenProgram,
+        memoProgram: null,
+      })
+      .signers([merchant.authority])
+      .rpc();
+  }
+
+  it("holds payment proceeds in the merchant vault", async () => {
+    await pay(merchant, customer, 100000, { accounts: heldSettlement(merchant, { vault: true }) });
+
+    expect(await tokenBalance(vaultTokens())).to.equal(97500);
+    expect(await tokenBalance(merchant.settlementToken)).to.equal(0);
+    const vault = await program.account.merchantVault.fetch(pda.merchantVault(merchant.merchant, merchant.mint));
+    expect(vault.pending.reduce((sum, tranche) => sum + tranche.amount.toNumber(), 0)).to.equal(97500);
+  });
+
+  it("refunds held proceeds out of the vault", async () => {
+    const { payment } = await pay(merchant, customer, 100000, { accounts: heldSettlement(merchant, { vault: true }) });
+    const vaultBefore = await tokenBalance(vaultTokens());
+    const customerBefore = await tokenBalance(customer.token);
+
+    await refund(merchant, customer, payment, 40000, heldSettlement(merchant, { vault: true }));
+    expect((await tokenBalance(customer.token)) - customerBefore).to.equal(40000);
+    expect(await tokenBalance(vaultTokens())).to.be.below(vaultBefore);
+    expect(await tokenBalance(merchant.settlementToken)).to.equal(0);
+  });
+
+  it("rejects a withdrawal before the hold ends", async () => {
+    await expectError(withdraw(), "NoBalanceToWithdraw");
+  });
+
+  it("rejects a payment that skips the vault", async () => {
+    await expectError(pay(merchant, customer, 100000), "MissingMerchantVault");
+  });
+});
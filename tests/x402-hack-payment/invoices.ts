@@ -0,0 +1,139 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { ASSOCIATED_TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  directSettlement,
+  expectError,
+  MerchantFixture,
+  now,
+  pda,
+  platform,
+  program,
+  refund,
+  uniqueId,
+} from "./setup";
+
+describe("invoices", () => {
+  let merchant: MerchantFixture;
+
+  before(async () => {
+    await platform();
+    merchant = await createMerchant("invoices");
+  });
+
+  async function createInvoice(amountDue: number, payer: PublicKey | null = null) {
+    const invoiceId = uniqueId("inv");
+    const invoice = pda.invoice(merchant.merchant, invoiceId);
+    await program.methods
+      .createInvoice(invoiceId, new BN(amountDue), new BN(now() + 7 * 86400), payer, Array(32).fill(0))
+      .accountsStrict({
+        authority: merchant.authority.publicKey,
+        merchantAccount: merchant.merchant,
+        invoice,
+        acceptedMint: merchant.acceptedMint,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchant.authority])
+      .rpc();
+    return invoice;
+  }
+
+  async function payInvoice(invoice: PublicKey, payer: CustomerFixture, amount: number) {
+    const { paymentCount } = await program.account.invoice.fetch(invoice);
+    const payment = pda.sequencedPayment(invoice, paymentCount);
+    await program.methods
+      .payInvoice(new BN(amount))
+      .accountsStrict({
+        payer: payer.wallet.publicKey,
+        platformConfig: pda.platform(),
+        invoice,
+        paymentAccount: payment,
+        customerAccount: payer.customer,
+        merchantAccount: merchant.merchant,
+        merchantStats: merchant.stats,
+        mint: merchant.mint,
+        acceptedMint: merchant.acceptedMint,
+        customerToken: payer.token,
+        merchantToken: merchant.settlementToken,
+        ...directSettlement(merchant),
+        platformTreasury: merchant.treasury,
+        tokenProgram: merchant.tokenProgram,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([payer.wallet])
+      .rpc();
+    return payment;
+  }
+
+  it("accepts partial payments from anyone until the invoice is paid", async () => {
+    const invoice = await createInvoice(100000);
+    const first = await createCustomer(merchant.mint);
+    const second = await createCustomer(merchant.mint);
+
+    await payInvoice(invoice, first, 40000);
+    let record = await program.account.invoice.fetch(invoice);
+    expect(record.amountPaid.toNumber()).to.equal(40000);
+    expect(record.status).to.deep.equal({ partiallyPaid: {} });
+
+    await payInvoice(invoice, second, 60000);
+    record = await program.account.invoice.fetch(invoice);
+    expect(record.status).to.deep.equal({ paid: {} });
+    expect(record.paymentCount).to.equal(2);
+  });
+
+  it("records each payment under the invoice and its payment count", async () => {
+    const invoice = await createInvoice(100000);
+    const customer = await createCustomer(merchant.mint);
+    const payment = await payInvoice(invoice, customer, 40000);
+
+    const record = await program.account.payment.fetch(payment);
+    const { invoiceId } = await program.account.invoice.fetch(invoice);
+    expect(record.paymentId).to.equal(`${invoiceId}-0`);
+    expect(record.invoice.toBase58()).to.equal(invoice.toBase58());
+  });
+
+  it("reopens a paid invoice when one of its payments is refunded", async () => {
+    const invoice = await createInvoice(50000);
+    const customer = await createCustomer(merchant.mint);
+    const payment = await payInvoice(invoice, customer, 50000);
+
+    await expectError(refund(merchant, customer, payment, 20000), "MissingInvoice");
+    await refund(merchant, customer, payment, 20000, { invoice });
+    const record = await program.account.invoice.fetch(invoice);
+    expect(record.amountPaid.toNumber()).to.equal(30000);
+    expect(record.status).to.deep.equal({ partiallyPaid: {} });
+    expect(record.paidAt).to.equal(null);
+
+    await payInvoice(invoice, customer, 20000);
+    expect((await program.account.invoice.fetch(invoice)).status).to.deep.equal({ paid: {} });
+  });
+
+  it("rejects a payment over the remaining balance", async () => {
+    const invoice = await createInvoice(100000);
+    const customer = await createCustomer(merchant.mint);
+    await payInvoice(invoice, customer, 40000);
+
+    await expectError(payInvoice(invoice, customer, 60001), "InvalidInvoicePayment");
+  });
+
+  it("rejects payments on a paid invoice", async () => {
+    const invoice = await createInvoice(20000);
+    const customer = await createCustomer(merchant.mint);
+    await payInvoice(invoice, customer, 20000);
+
+    await expectError(payInvoice(invoice, customer, 20000), "InvoiceAlreadyPaid");
+  });
+
+  it("rejects payers other than the one the invoice names", async () => {
+    const billed = await createCustomer(merchant.mint);
+    const other = await createCustomer(merchant.mint);
+    const invoice = await createInvoice(50000, billed.wallet.publicKey);
+
+    await expectError(payInvoice(invoice, other, 50000), "InvoicePayerNotAllowed");
+  });
+});
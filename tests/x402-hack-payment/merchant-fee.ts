@@ -0,0 +1,41 @@
+import { expect } from "chai";
+import {
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  pay,
+  platform,
+  program,
+  tokenBalance,
+} from "./setup";
+
+describe("merchant fee", () => {
+  let customer: CustomerFixture;
+
+  before(async () => {
+    const { usdcMint } = await platform();
+    customer = await createCustomer(usdcMint);
+  });
+
+  it("charges the merchant fee to the fee recipient", async () => {
+    const merchant = await createMerchant("merchfee", { feeBps: 100 });
+    const { payment } = await pay(merchant, customer, 100000);
+
+    const record = await program.account.payment.fetch(payment);
+    expect(record.feeAmount.toNumber()).to.equal(2500);
+    expect(record.merchantFeeAmount.toNumber()).to.equal(1000);
+    expect(record.merchantAmount.toNumber()).to.equal(96500);
+    // Fee recipient defaults to the settlement wallet, so it receives both shares
+    expect(await tokenBalance(merchant.settlementToken)).to.equal(97500);
+  });
+
+  it("rejects a payment without the fee recipient's token account", async () => {
+    const merchant = await createMerchant("merchfee", { feeBps: 100 });
+
+    await expectError(
+      pay(merchant, customer, 100000, { accounts: { merchantFeeToken: null } }),
+      "MissingMerchantFeeAccount",
+    );
+  });
+});
@@ -0,0 +1,111 @@
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createMerchant,
+  expectError,
+  fund,
+  pda,
+  platform,
+  PlatformFixture,
+  program,
+  resetPlatform,
+  uniqueId,
+} from "./setup";
+
+describe("merchant ids", () => {
+  let fixture: PlatformFixture;
+  let admin: { authority: PublicKey; platformConfig: PublicKey };
+
+  before(async () => {
+    fixture = await platform();
+    admin = { authority: authority.publicKey, platformConfig: fixture.config };
+  });
+
+  it("rejects IDs outside lowercase letters, digits, dashes and underscores", async () => {
+    await expectError(createMerchant("ids", { merchantId: `Shop-${Date.now()}` }), "InvalidMerchantId");
+  });
+
+  it("keeps a reserved ID for its holder", async () => {
+    const merchantId = uniqueId("reserved");
+    const holder = Keypair.generate();
+    await program.methods
+      .reserveMerchantId(merchantId, holder.publicKey)
+      .accountsStrict({
+        ...admin,
+        reservedMerchantId: pda.reservedMerchantId(merchantId),
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+
+    await expectError(createMerchant("ids", { merchantId }), "MerchantIdReserved");
+    const merchant = await createMerchant("ids", { merchantId, authority: holder });
+    expect((await program.account.merchant.fetch(merchant.merchant)).merchantId).to.equal(merchantId);
+  });
+
+  describe("with onboarding required", () => {
+    before(async () => {
+      await program.methods.setMerchantOnboarding(true).accountsStrict(admin).rpc();
+    });
+
+    after(() => resetPlatform(fixture.usdcMint));
+
+    it("rejects a merchant without an approved application", async () => {
+      await expectError(createMerchant("onboard"), "MerchantApplicationRequired");
+    });
+
+    // Submits an application for `merchantId` from a new applicant, approving it unless told otherwise
+    async function apply(merchantId: string, approve = true): Promise<Keypair> {
+      const applicant = Keypair.generate();
+      await fund(applicant.publicKey);
+      const application = pda.merchantApplication(merchantId);
+
+      await program.methods
+        .submitMerchantApplication(merchantId, Array(32).fill(7))
+        .accountsStrict({
+          applicant: applicant.publicKey,
+          platformConfig: fixture.config,
+          merchantAccount: pda.merchant(merchantId),
+          reservedMerchantId: pda.reservedMerchantId(merchantId),
+          merchantApplication: application,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([applicant])
+        .rpc();
+      if (approve) {
+        await program.methods
+          .reviewMerchantApplication(true)
+          .accountsStrict({ ...admin, merchantApplication: application, applicant: applicant.publicKey })
+          .rpc();
+      }
+      return applicant;
+    }
+
+    it("registers a merchant once its application is approved", async () => {
+      const merchantId = uniqueId("onboard");
+      const applicant = await apply(merchantId);
+
+      const merchant = await createMerchant("onboard", { merchantId, authority: applicant, withApplication: true });
+      expect((await program.account.merchant.fetch(merchant.merchant)).authority.toBase58()).to.equal(
+        applicant.publicKey.toBase58(),
+      );
+    });
+
+    it("rejects a pending application", async () => {
+      const merchantId = uniqueId("onboard");
+      const applicant = await apply(merchantId, false);
+
+      await expectError(
+        createMerchant("onboard", { merchantId, authority: applicant, withApplication: true }),
+        "MerchantApplicationRequired",
+      );
+    });
+
+    it("rejects an approved application used by someone other than its applicant", async () => {
+      const merchantId = uniqueId("onboard");
+      await apply(merchantId);
+
+      await expectError(createMerchant("onboard", { merchantId, withApplication: true }), "MerchantApplicationRequired");
+    });
+  });
+});
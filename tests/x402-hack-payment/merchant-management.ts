@@ -0,0 +1,94 @@
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  expectError,
+  merchantConfig,
+  pay,
+  platform,
+  PlatformFixture,
+  program,
+} from "./setup";
+
+describe("merchant management", () => {
+  let fixture: PlatformFixture;
+
+  before(async () => {
+    fixture = await platform();
+  });
+
+  it("updates the fee and deactivates the merchant", async () => {
+    const merchant = await createMerchant("manage");
+    const customer = await createCustomer(fixture.usdcMint);
+    const owner = { authority: merchant.authority.publicKey, merchantAccount: merchant.merchant };
+
+    await program.methods
+      .updateMerchant(null, 200, null)
+      .accountsStrict(merchantConfig(merchant))
+      .signers([merchant.authority])
+      .rpc();
+    expect((await program.account.merchant.fetch(merchant.merchant)).fee).to.equal(200);
+
+    await program.methods.setMerchantActive(false).accountsStrict(owner).signers([merchant.authority]).rpc();
+    await expectError(pay(merchant, customer, 20000), "MerchantInactive");
+  });
+
+  it("rotates the authority once the new authority accepts", async () => {
+    const merchant = await createMerchant("manage");
+    const next = Keypair.generate();
+
+    await program.methods
+      .proposeMerchantAuthority(next.publicKey)
+      .accountsStrict({ authority: merchant.authority.publicKey, merchantAccount: merchant.merchant })
+      .signers([merchant.authority])
+      .rpc();
+    await program.methods
+      .acceptMerchantAuthority()
+      .accountsStrict({ newAuthority: next.publicKey, merchantAccount: merchant.merchant })
+      .signers([next])
+      .rpc();
+    expect((await program.account.merchant.fetch(merchant.merchant)).authority.toBase58()).to.equal(
+      next.publicKey.toBase58(),
+    );
+
+    await expectError(
+      program.methods
+        .setMerchantActive(false)
+        .accountsStrict({ authority: merchant.authority.publicKey, merchantAccount: merchant.merchant })
+        .signers([merchant.authority])
+        .rpc(),
+      "Unauthorized",
+    );
+  });
+
+  it("rejects a fee above the maximum", async () => {
+    const merchant = await createMerchant("manage");
+    await expectError(
+      program.methods
+        .updateMerchant(null, 1001, null)
+        .accountsStrict(merchantConfig(merchant))
+        .signers([merchant.authority])
+        .rpc(),
+      "FeeTooHigh",
+    );
+  });
+
+  it("keeps a suspended merchant from reactivating itself", async () => {
+    const merchant = await createMerchant("manage");
+    await program.methods
+      .setMerchantSuspended(true)
+      .accountsStrict({ authority: authority.publicKey, platformConfig: fixture.config, merchantAccount: merchant.merchant })
+      .rpc();
+
+    await expectError(
+      program.methods
+        .setMerchantActive(true)
+        .accountsStrict({ authority: merchant.authority.publicKey, merchantAccount: merchant.merchant })
+        .signers([merchant.authority])
+        .rpc(),
+      "MerchantSuspended",
+    );
+  });
+});
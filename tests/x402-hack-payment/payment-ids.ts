@@ -0,0 +1,41 @@
+import { expect } from "chai";
+import {
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  MerchantFixture,
+  pay,
+  pda,
+  platform,
+} from "./setup";
+
+describe("payment id namespacing", () => {
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+
+  before(async () => {
+    const { usdcMint } = await platform();
+    merchant = await createMerchant("payids");
+    customer = await createCustomer(usdcMint);
+  });
+
+  it("lets two merchants use the same payment id", async () => {
+    const other = await createMerchant("payids");
+    const { paymentId, payment } = await pay(merchant, customer, 20000);
+    const second = await pay(other, customer, 20000, { paymentId });
+
+    expect(second.payment.toBase58()).to.not.equal(payment.toBase58());
+    expect(second.payment.toBase58()).to.equal(pda.payment(other.merchant, paymentId).toBase58());
+  });
+
+  it("rejects a payment account from another merchant's namespace", async () => {
+    const other = await createMerchant("payids");
+    const paymentId = `ns-${Date.now()}`;
+
+    await expectError(
+      pay(merchant, customer, 20000, { paymentId, accounts: { paymentAccount: pda.payment(other.merchant, paymentId) } }),
+      "ConstraintSeeds",
+    );
+  });
+});
@@ -0,0 +1,50 @@
+import { Keypair } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  MerchantFixture,
+  now,
+  pay,
+  platform,
+  program,
+} from "./setup";
+
+describe("payment intents", () => {
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+
+  before(async () => {
+    const { usdcMint } = await platform();
+    merchant = await createMerchant("intents");
+    customer = await createCustomer(usdcMint);
+  });
+
+  it("settles a payment the merchant signed for", async () => {
+    const { payment } = await pay(merchant, customer, 25000);
+
+    const record = await program.account.payment.fetch(payment);
+    expect(record.amount.toNumber()).to.equal(25000);
+    expect(record.customer.toBase58()).to.equal(customer.wallet.publicKey.toBase58());
+  });
+
+  it("rejects an amount other than the signed one", async () => {
+    await expectError(pay(merchant, customer, 30000, { signedAmount: 20000 }), "InvalidSignature");
+  });
+
+  it("rejects an intent signed by someone else", async () => {
+    await expectError(pay(merchant, customer, 20000, { signer: Keypair.generate() }), "InvalidSignature");
+  });
+
+  it("rejects an expired intent", async () => {
+    await expectError(pay(merchant, customer, 20000, { expiry: now() - 60 }), "PaymentIntentExpired");
+  });
+
+  it("rejects a replayed intent", async () => {
+    const { paymentId } = await pay(merchant, customer, 20000);
+
+    await expectError(pay(merchant, customer, 20000, { paymentId }), "already in use");
+  });
+});
@@ -0,0 +1,61 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey } from "@solana/web3.js";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  expectError,
+  pay,
+  platform,
+  PlatformFixture,
+  program,
+  resetPlatform,
+} from "./setup";
+
+describe("payment limits", () => {
+  let fixture: PlatformFixture;
+  let admin: { authority: PublicKey; platformConfig: PublicKey };
+
+  before(async () => {
+    fixture = await platform();
+    admin = { authority: authority.publicKey, platformConfig: fixture.config };
+  });
+
+  afterEach(() => resetPlatform(fixture.usdcMint));
+
+  it("enforces the platform's customer daily limit", async () => {
+    await program.methods
+      .setPaymentLimits({ maxPaymentAmount: new BN(0), customerDailyLimit: new BN(50000), merchantDailyLimit: new BN(0) })
+      .accountsStrict(admin)
+      .rpc();
+    const merchant = await createMerchant("limits");
+    const customer = await createCustomer(fixture.usdcMint);
+
+    await pay(merchant, customer, 30000);
+    await expectError(pay(merchant, customer, 30000), "CustomerDailyLimitExceeded");
+  });
+
+  it("enforces a merchant's own maximum over the platform default", async () => {
+    const merchant = await createMerchant("limits");
+    const customer = await createCustomer(fixture.usdcMint);
+    await program.methods
+      .setMerchantLimits({ maxPaymentAmount: new BN(25000), customerDailyLimit: new BN(0), merchantDailyLimit: new BN(0) })
+      .accountsStrict({ ...admin, merchantAccount: merchant.merchant })
+      .rpc();
+
+    await pay(merchant, customer, 25000);
+    await expectError(pay(merchant, customer, 30000), "PaymentAboveMaximum");
+  });
+
+  it("rejects limits set by anyone but the platform authority", async () => {
+    const merchant = await createMerchant("limits");
+    await expectError(
+      program.methods
+        .setMerchantLimits(null)
+        .accountsStrict({ authority: merchant.authority.publicKey, platformConfig: fixture.config, merchantAccount: merchant.merchant })
+        .signers([merchant.authority])
+        .rpc(),
+      "Unauthorized",
+    );
+  });
+});
@@ -0,0 +1,68 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  expectError,
+  pay,
+  platform,
+  PlatformFixture,
+  program,
+  resetPlatform,
+} from "./setup";
+
+describe("platform config updates", () => {
+  let fixture: PlatformFixture;
+  let admin: { authority: PublicKey; platformConfig: PublicKey };
+
+  before(async () => {
+    fixture = await platform();
+    admin = { authority: authority.publicKey, platformConfig: fixture.config };
+  });
+
+  afterEach(() => resetPlatform(fixture.usdcMint));
+
+  it("updates the fee and minimum and pauses payments", async () => {
+    await program.methods
+      .updatePlatformConfig(new BN(300), new BN(20000), null)
+      .accountsStrict({ ...admin, usdcAcceptedMint: fixture.acceptedMint })
+      .rpc();
+    const config = await program.account.platform.fetch(fixture.config);
+    expect(config.feeBps.toNumber()).to.equal(300);
+    expect(config.minPaymentAmount.toNumber()).to.equal(20000);
+    const accepted = await program.account.acceptedMint.fetch(fixture.acceptedMint);
+    expect(accepted.minPaymentAmount.toNumber()).to.equal(20000);
+
+    const merchant = await createMerchant("paused");
+    const customer = await createCustomer(fixture.usdcMint);
+    await program.methods
+      .updatePlatformConfig(null, null, false)
+      .accountsStrict({ ...admin, usdcAcceptedMint: null })
+      .rpc();
+    await expectError(pay(merchant, customer, 20000), "PlatformInactive");
+  });
+
+  it("rejects a fee above the maximum", async () => {
+    await expectError(
+      program.methods
+        .updatePlatformConfig(new BN(1001), null, null)
+        .accountsStrict({ ...admin, usdcAcceptedMint: null })
+        .rpc(),
+      "InvalidFeeBps",
+    );
+  });
+
+  it("rejects updates from anyone but the authority", async () => {
+    const stranger = Keypair.generate();
+    await expectError(
+      program.methods
+        .updatePlatformConfig(null, null, false)
+        .accountsStrict({ authority: stranger.publicKey, platformConfig: fixture.config, usdcAcceptedMint: null })
+        .signers([stranger])
+        .rpc(),
+      "Unauthorized",
+    );
+  });
+});
@@ -0,0 +1,122 @@
+import { ASSOCIATED_TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  fund,
+  MerchantFixture,
+  pay,
+  pda,
+  platform,
+  PlatformFixture,
+  program,
+  tokenAccount,
+  tokenBalance,
+} from "./setup";
+
+describe("referrals", () => {
+  let fixture: PlatformFixture;
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+  const wallet = Keypair.generate();
+  let referrer: PublicKey;
+  let referralVault: PublicKey;
+
+  function setMerchantReferrer() {
+    return program.methods
+      .setMerchantReferrer()
+      .accountsStrict({ authority: merchant.authority.publicKey, merchantAccount: merchant.merchant, referrer })
+      .signers([merchant.authority])
+      .rpc();
+  }
+
+  function claim(walletToken: PublicKey) {
+    return program.methods
+      .claimReferralFees()
+      .accountsStrict({
+        wallet: wallet.publicKey,
+        referrer,
+        mint: fixture.usdcMint,
+        referralVault,
+        walletToken,
+        tokenProgram: merchant.tokenProgram,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        memoProgram: null,
+      })
+      .signers([wallet])
+      .rpc();
+  }
+
+  before(async () => {
+    fixture = await platform();
+    merchant = await createMerchant("referred");
+    customer = await createCustomer(fixture.usdcMint);
+    await fund(wallet.publicKey);
+    referrer = pda.referrer(wallet.publicKey);
+    referralVault = pda.referralVault(referrer, fixture.usdcMint);
+
+    await program.methods
+      .registerReferrer(wallet.publicKey, 2000)
+      .accountsStrict({
+        authority: authority.publicKey,
+        platformConfig: fixture.config,
+        referrer,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await program.methods
+      .openReferralVault()
+      .accountsStrict({
+        payer: authority.publicKey,
+        referrer,
+        mint: fixture.usdcMint,
+        acceptedMint: fixture.acceptedMint,
+        referralVault,
+        tokenProgram: merchant.tokenProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+    await setMerchantReferrer();
+  });
+
+  it("routes the referrer's share of the platform fee to its vault", async () => {
+    const treasuryBefore = await tokenBalance(fixture.treasury);
+    const { payment } = await pay(merchant, customer, 100000, { accounts: { referrer, referralVault } });
+
+    const record = await program.account.payment.fetch(payment);
+    expect(record.referrer.toBase58()).to.equal(referrer.toBase58());
+    expect(record.referralFeeAmount.toNumber()).to.equal(500);
+    expect(await tokenBalance(referralVault)).to.equal(500);
+    expect((await tokenBalance(fixture.treasury)) - treasuryBefore).to.equal(2000);
+  });
+
+  it("lets the referrer claim its fees", async () => {
+    const walletToken = await tokenAccount(fixture.usdcMint, wallet.publicKey);
+    const owed = await tokenBalance(referralVault);
+
+    await claim(walletToken);
+    expect(await tokenBalance(walletToken)).to.equal(owed);
+    await expectError(claim(walletToken), "NoFeesToClaim");
+  });
+
+  it("rejects a referrer-owned token account other than its vault", async () => {
+    const stray = await tokenAccount(fixture.usdcMint, referrer);
+
+    await expectError(
+      pay(merchant, customer, 100000, { accounts: { referrer, referralVault: stray } }),
+      "InvalidReferrer",
+    );
+  });
+
+  it("rejects a payment that leaves out the merchant's referrer", async () => {
+    await expectError(pay(merchant, customer, 100000), "ReferrerRequired");
+  });
+
+  it("rejects replacing a merchant's referrer", async () => {
+    await expectError(setMerchantReferrer(), "ReferrerAlreadySet");
+  });
+});
@@ -0,0 +1,55 @@
+import { expect } from "chai";
+import {
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  MerchantFixture,
+  pay,
+  platform,
+  program,
+  refund,
+  tokenBalance,
+} from "./setup";
+
+describe("partial refunds", () => {
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+
+  before(async () => {
+    const { usdcMint } = await platform();
+    merchant = await createMerchant("refunds");
+    customer = await createCustomer(usdcMint);
+  });
+
+  it("refunds in parts and tracks what is left", async () => {
+    const { payment } = await pay(merchant, customer, 100000);
+    const before = await tokenBalance(customer.token);
+
+    await refund(merchant, customer, payment, 40000);
+    let record = await program.account.payment.fetch(payment);
+    expect(record.refundedAmount.toNumber()).to.equal(40000);
+    expect(record.status).to.deep.equal({ partiallyRefunded: {} });
+
+    await refund(merchant, customer, payment);
+    record = await program.account.payment.fetch(payment);
+    expect(record.refundedAmount.toNumber()).to.equal(100000);
+    expect(record.status).to.deep.equal({ refunded: {} });
+    expect((await tokenBalance(customer.token)) - before).to.equal(100000);
+  });
+
+  it("rejects a refund larger than what is left", async () => {
+    const { payment } = await pay(merchant, customer, 50000);
+    await refund(merchant, customer, payment, 30000);
+
+    await expectError(refund(merchant, customer, payment, 20001), "RefundExceedsRemaining");
+    await expectError(refund(merchant, customer, payment, 0), "InvalidRefundAmount");
+  });
+
+  it("rejects refunding a fully refunded payment", async () => {
+    const { payment } = await pay(merchant, customer, 20000);
+    await refund(merchant, customer, payment);
+
+    await expectError(refund(merchant, customer, payment, 1), "PaymentAlreadyRefunded");
+  });
+});
@@ -0,0 +1,102 @@
+import { BN } from "@coral-xyz/anchor";
+import { PublicKey, SystemProgram } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  heldSettlement,
+  MerchantFixture,
+  pay,
+  pda,
+  platform,
+  PlatformFixture,
+  program,
+  refund,
+  tokenBalance,
+} from "./setup";
+
+describe("rolling reserve", () => {
+  let fixture: PlatformFixture;
+  let admin: { authority: PublicKey; platformConfig: PublicKey };
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+  const reserveTokens = () => pda.merchantReserveTokens(merchant.merchant, merchant.mint);
+
+  before(async () => {
+    fixture = await platform();
+    admin = { authority: authority.publicKey, platformConfig: fixture.config };
+    merchant = await createMerchant("reserve");
+    customer = await createCustomer(fixture.usdcMint);
+    await program.methods
+      .setMerchantReserve(1000, new BN(86400))
+      .accountsStrict({ ...admin, merchantAccount: merchant.merchant })
+      .rpc();
+    await program.methods
+      .openMerchantReserve()
+      .accountsStrict({
+        payer: merchant.authority.publicKey,
+        merchantAccount: merchant.merchant,
+        mint: merchant.mint,
+        acceptedMint: merchant.acceptedMint,
+        merchantReserve: pda.merchantReserve(merchant.merchant, merchant.mint),
+        reserveTokens: reserveTokens(),
+        tokenProgram: merchant.tokenProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchant.authority])
+      .rpc();
+  });
+
+  it("holds back the reserve share of each payout", async () => {
+    const { payment } = await pay(merchant, customer, 100000, { accounts: heldSettlement(merchant, { reserve: true }) });
+
+    expect((await program.account.payment.fetch(payment)).reserveAmount.toNumber()).to.equal(9750);
+    expect(await tokenBalance(reserveTokens())).to.equal(9750);
+    expect(await tokenBalance(merchant.settlementToken)).to.equal(87750);
+  });
+
+  it("draws a full refund from the reserve first", async () => {
+    const { payment } = await pay(merchant, customer, 100000, { accounts: heldSettlement(merchant, { reserve: true }) });
+    const reserveBefore = await tokenBalance(reserveTokens());
+    const customerBefore = await tokenBalance(customer.token);
+
+    await refund(merchant, customer, payment, undefined, heldSettlement(merchant, { reserve: true }));
+    expect((await tokenBalance(customer.token)) - customerBefore).to.equal(100000);
+    expect(reserveBefore - (await tokenBalance(reserveTokens()))).to.equal(9750);
+  });
+
+  it("rejects releasing a reserve that has not matured", async () => {
+    await expectError(
+      program.methods
+        .releaseReserve()
+        .accountsStrict({
+          merchantAccount: merchant.merchant,
+          mint: merchant.mint,
+          merchantReserve: pda.merchantReserve(merchant.merchant, merchant.mint),
+          reserveTokens: reserveTokens(),
+          merchantToken: merchant.settlementToken,
+          tokenProgram: merchant.tokenProgram,
+          memoProgram: null,
+        })
+        .rpc(),
+      "NothingToRelease",
+    );
+  });
+
+  it("rejects a payment that skips the reserve", async () => {
+    await expectError(pay(merchant, customer, 100000), "MissingMerchantReserve");
+  });
+
+  it("rejects a reserve above 100%", async () => {
+    await expectError(
+      program.methods
+        .setMerchantReserve(10001, new BN(86400))
+        .accountsStrict({ ...admin, merchantAccount: merchant.merchant })
+        .rpc(),
+      "InvalidReserveConfig",
+    );
+  });
+});
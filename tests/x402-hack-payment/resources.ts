@@ -0,0 +1,102 @@
+import { BN } from "@coral-xyz/anchor";
+import { SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
+import { ASSOCIATED_TOKEN_PROGRAM_ID } from "@solana/spl-token";
+import { createHash } from "crypto";
+import { expect } from "chai";
+import {
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  directSettlement,
+  expectError,
+  MerchantFixture,
+  now,
+  pda,
+  platform,
+  program,
+  quoteInstruction,
+} from "./setup";
+
+describe("resource access", () => {
+  let merchant: MerchantFixture;
+  const resourceHash = createHash("sha256").update("GET /premium/report").digest();
+  let nextNonce = Date.now();
+
+  before(async () => {
+    await platform();
+    merchant = await createMerchant("resources");
+  });
+
+  // Submits `payer`'s payment for a quote the merchant issued to `quotedFor`
+  function payForResource(
+    payer: CustomerFixture,
+    nonce: number,
+    { quotedFor = payer, expiry = now() + 600, amount = 20000 } = {},
+  ) {
+    return program.methods
+      .payForResource(Array.from(resourceHash), new BN(amount), new BN(nonce), new BN(expiry))
+      .accountsStrict({
+        payer: payer.wallet.publicKey,
+        platformConfig: pda.platform(),
+        merchantAccount: merchant.merchant,
+        merchantStats: merchant.stats,
+        accessReceipt: pda.accessReceipt(merchant.merchant, nonce),
+        customerAccount: payer.customer,
+        mint: merchant.mint,
+        acceptedMint: merchant.acceptedMint,
+        customerToken: payer.token,
+        merchantToken: merchant.settlementToken,
+        ...directSettlement(merchant),
+        platformTreasury: merchant.treasury,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenProgram: merchant.tokenProgram,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([
+        quoteInstruction(
+          merchant.authority,
+          merchant.merchant,
+          quotedFor.wallet.publicKey,
+          resourceHash,
+          merchant.mint,
+          amount,
+          nonce,
+          expiry,
+        ),
+      ])
+      .signers([payer.wallet])
+      .rpc();
+  }
+
+  it("records an access receipt for a paid quote", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const nonce = nextNonce++;
+
+    await payForResource(customer, nonce);
+    const receipt = await program.account.accessReceipt.fetch(pda.accessReceipt(merchant.merchant, nonce));
+    expect(receipt.customer.toBase58()).to.equal(customer.wallet.publicKey.toBase58());
+    expect(Buffer.from(receipt.resourceHash)).to.deep.equal(resourceHash);
+    expect(receipt.amount.toNumber()).to.equal(20000);
+  });
+
+  it("rejects a replayed quote", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const nonce = nextNonce++;
+    await payForResource(customer, nonce);
+
+    await expectError(payForResource(customer, nonce), "already in use");
+  });
+
+  it("rejects a quote redeemed by someone other than its customer", async () => {
+    const customer = await createCustomer(merchant.mint);
+    const frontRunner = await createCustomer(merchant.mint);
+
+    await expectError(payForResource(frontRunner, nextNonce++, { quotedFor: customer }), "InvalidSignature");
+  });
+
+  it("rejects an expired quote", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await expectError(payForResource(customer, nextNonce++, { expiry: now() - 60 }), "QuoteExpired");
+  });
+});
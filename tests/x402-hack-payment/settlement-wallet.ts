@@ -0,0 +1,65 @@
+import { getAccount } from "@solana/spl-token";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  connection,
+  createMerchant,
+  expectError,
+  MerchantFixture,
+  merchantConfig,
+  platform,
+  PlatformFixture,
+  program,
+} from "./setup";
+
+describe("settlement wallet", () => {
+  let fixture: PlatformFixture;
+
+  before(async () => {
+    fixture = await platform();
+  });
+
+  it("creates the settlement token account when the merchant registers", async () => {
+    const merchant = await createMerchant("wallet");
+
+    const token = await getAccount(connection, merchant.settlementToken);
+    expect(token.owner.toBase58()).to.equal(merchant.authority.publicKey.toBase58());
+    expect(token.mint.toBase58()).to.equal(fixture.usdcMint.toBase58());
+  });
+
+  it("rejects a settlement wallet owned by a program", async () => {
+    await expectError(createMerchant("wallet", { settlementWallet: fixture.config }), "InvalidSettlementWallet");
+  });
+
+  function changeWallet(merchant: MerchantFixture, wallet: PublicKey, accounts = merchantConfig(merchant, wallet)) {
+    return program.methods
+      .updateMerchant(wallet, null, null)
+      .accountsStrict(accounts)
+      .signers([merchant.authority])
+      .rpc();
+  }
+
+  it("creates the token account of a new settlement wallet", async () => {
+    const merchant = await createMerchant("wallet");
+    const wallet = Keypair.generate().publicKey;
+
+    await changeWallet(merchant, wallet);
+    const { settlementToken } = merchantConfig(merchant, wallet);
+    expect((await getAccount(connection, settlementToken)).owner.toBase58()).to.equal(wallet.toBase58());
+    const record = await program.account.merchant.fetch(merchant.merchant);
+    expect(record.settlementWallet.toBase58()).to.equal(wallet.toBase58());
+  });
+
+  it("rejects changing to a settlement wallet owned by a program", async () => {
+    const merchant = await createMerchant("wallet");
+    await expectError(changeWallet(merchant, fixture.config), "InvalidSettlementWallet");
+  });
+
+  it("rejects a settlement wallet change without the wallet's accounts", async () => {
+    const merchant = await createMerchant("wallet");
+    await expectError(
+      changeWallet(merchant, Keypair.generate().publicKey, merchantConfig(merchant)),
+      "MissingSettlementAccounts",
+    );
+  });
+});
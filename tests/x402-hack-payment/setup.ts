@@ -0,0 +1,604 @@
+import * as anchor from "@coral-xyz/anchor";
+import { BN, Program } from "@coral-xyz/anchor";
+import {
+  Ed25519Program,
+  Keypair,
+  LAMPORTS_PER_SOL,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+  Transaction,
+  TransactionInstruction,
+} from "@solana/web3.js";
+import {
+  createMint,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import { X402HackPayment } from "../../target/types/x402_hack_payment";
+
+// Shared fixtures for the behavioral specs in this directory. Every spec creates its own
+// merchants and customers so the files can run in any order against the same cluster.
+
+export const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+export const program = anchor.workspace.X402HackPayment as Program<X402HackPayment>;
+export const connection = provider.connection;
+export const authority = provider.wallet;
+
+export const PLATFORM_FEE_BPS = 250;
+export const MIN_PAYMENT_AMOUNT = 10000;
+
+const seed = (value: string) => Buffer.from(value);
+const le8 = (value: number | BN) => new BN(value).toArrayLike(Buffer, "le", 8);
+const find = (seeds: Buffer[]) => PublicKey.findProgramAddressSync(seeds, program.programId)[0];
+
+export const pda = {
+  platform: () => find([seed("platform_config")]),
+  treasury: () => find([seed("platform_treasury")]),
+  mintTreasury: (mint: PublicKey) => find([seed("platform_treasury"), mint.toBuffer()]),
+  acceptedMint: (mint: PublicKey) => find([seed("accepted_mint"), mint.toBuffer()]),
+  paymentDelegate: () => find([seed("payment_delegate")]),
+  delegation: (tokenAccount: PublicKey) => find([seed("delegation"), tokenAccount.toBuffer()]),
+  merchant: (merchantId: string) => find([seed("merchant"), seed(merchantId)]),
+  merchantStats: (merchant: PublicKey) => find([seed("merchant_stats"), merchant.toBuffer()]),
+  reservedMerchantId: (merchantId: string) => find([seed("reserved_merchant_id"), seed(merchantId)]),
+  merchantApplication: (merchantId: string) => find([seed("merchant_application"), seed(merchantId)]),
+  customer: (wallet: PublicKey) => find([seed("customer"), wallet.toBuffer()]),
+  payment: (merchant: PublicKey, paymentId: string) => find([seed("payment"), merchant.toBuffer(), seed(paymentId)]),
+  merchantVault: (merchant: PublicKey, mint: PublicKey) =>
+    find([seed("merchant_vault"), merchant.toBuffer(), mint.toBuffer()]),
+  merchantVaultTokens: (merchant: PublicKey, mint: PublicKey) =>
+    find([seed("merchant_vault_tokens"), merchant.toBuffer(), mint.toBuffer()]),
+  merchantReserve: (merchant: PublicKey, mint: PublicKey) =>
+    find([seed("merchant_reserve"), merchant.toBuffer(), mint.toBuffer()]),
+  merchantReserveTokens: (merchant: PublicKey, mint: PublicKey) =>
+    find([seed("merchant_reserve_tokens"), merchant.toBuffer(), mint.toBuffer()]),
+  referrer: (wallet: PublicKey) => find([seed("referrer"), wallet.toBuffer()]),
+  referralVault: (referrer: PublicKey, mint: PublicKey) =>
+    find([seed("referral_vault"), referrer.toBuffer(), mint.toBuffer()]),
+  plan: (merchant: PublicKey, planId: string) => find([seed("subscription_plan"), merchant.toBuffer(), seed(planId)]),
+  subscription: (plan: PublicKey, customer: PublicKey) =>
+    find([seed("subscription"), plan.toBuffer(), customer.toBuffer()]),
+  // Subscription charges and invoice payments, by cycle or invoice payment count
+  sequencedPayment: (parent: PublicKey, sequence: number) =>
+    find([seed("payment"), parent.toBuffer(), new BN(sequence).toArrayLike(Buffer, "le", 4)]),
+  allowance: (customer: PublicKey, merchant: PublicKey) =>
+    find([seed("spending_allowance"), customer.toBuffer(), merchant.toBuffer()]),
+  escrow: (payment: PublicKey) => find([seed("escrow"), payment.toBuffer()]),
+  escrowVault: (payment: PublicKey) => find([seed("escrow_vault"), payment.toBuffer()]),
+  dispute: (payment: PublicKey) => find([seed("dispute"), payment.toBuffer()]),
+  accessReceipt: (merchant: PublicKey, nonce: number | BN) =>
+    find([seed("access_receipt"), merchant.toBuffer(), le8(nonce)]),
+  invoice: (merchant: PublicKey, invoiceId: string) => find([seed("invoice"), merchant.toBuffer(), seed(invoiceId)]),
+  splitConfig: (merchant: PublicKey) => find([seed("split_config"), merchant.toBuffer()]),
+  paymentSplit: (payment: PublicKey) => find([seed("payment_split"), payment.toBuffer()]),
+};
+
+export interface PlatformFixture {
+  config: PublicKey;
+  usdcMint: PublicKey;
+  acceptedMint: PublicKey;
+  treasury: PublicKey;
+}
+
+export interface MerchantFixture {
+  merchantId: string;
+  authority: Keypair;
+  merchant: PublicKey;
+  stats: PublicKey;
+  mint: PublicKey;
+  acceptedMint: PublicKey;
+  treasury: PublicKey;
+  tokenProgram: PublicKey;
+  // Settlement wallet token account; the wallet defaults to the authority so refunds can be signed
+  settlementToken: PublicKey;
+}
+
+export interface CustomerFixture {
+  wallet: Keypair;
+  customer: PublicKey;
+  token: PublicKey;
+}
+
+let platformFixture: Promise<PlatformFixture> | undefined;
+
+// Initializes the platform on first use, or reuses it, and resets the global settings
+// other specs may have changed to the defaults these specs assume.
+export function platform(): Promise<PlatformFixture> {
+  platformFixture ??= loadPlatform();
+  return platformFixture;
+}
+
+async function loadPlatform(): Promise<PlatformFixture> {
+  const config = pda.platform();
+  let existing = await program.account.platform.fetchNullable(config);
+
+  if (!existing) {
+    const usdcMint = await createMint(connection, authority.payer, authority.publicKey, null, 6);
+    await program.methods
+      .setPlatformConfig(new BN(PLATFORM_FEE_BPS), new BN(MIN_PAYMENT_AMOUNT))
+      .accountsStrict({
+        authority: authority.publicKey,
+        platformConfig: config,
+        platformTreasury: pda.treasury(),
+        usdcMint,
+        usdcAcceptedMint: pda.acceptedMint(usdcMint),
+        systemProgram: SystemProgram.programId,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    existing = await program.account.platform.fetch(config);
+  }
+
+  const usdcMint = existing.usdcMint;
+  const acceptedMint = pda.acceptedMint(usdcMint);
+  await resetPlatform(usdcMint);
+
+  return { config, usdcMint, acceptedMint, treasury: pda.treasury() };
+}
+
+// Restores the fee, minimum, schedule, limits and onboarding settings to the defaults
+export async function resetPlatform(usdcMint?: PublicKey) {
+  const config = pda.platform();
+  usdcMint ??= (await program.account.platform.fetch(config)).usdcMint;
+  const admin = { authority: authority.publicKey, platformConfig: config };
+
+  await program.methods
+    .updatePlatformConfig(new BN(PLATFORM_FEE_BPS), new BN(MIN_PAYMENT_AMOUNT), true)
+    .accountsStrict({ ...admin, usdcAcceptedMint: pda.acceptedMint(usdcMint) })
+    .rpc();
+  await program.methods.setFeeSchedule([], new BN(0), new BN(0), new BN(0)).accountsStrict(admin).rpc();
+  await program.methods
+    .setPaymentLimits({ maxPaymentAmount: new BN(0), customerDailyLimit: new BN(0), merchantDailyLimit: new BN(0) })
+    .accountsStrict(admin)
+    .rpc();
+  await program.methods.setMerchantOnboarding(false).accountsStrict(admin).rpc();
+}
+
+const runId = Date.now().toString(36);
+let idCounter = 0;
+
+// An ID no earlier run on the same cluster has taken; keep `prefix` short, IDs cap at 32 bytes
+export function uniqueId(prefix: string): string {
+  return `${prefix}-${runId}-${(idCounter++).toString(36)}`;
+}
+
+export async function fund(wallet: PublicKey, sol = 0.2) {
+  const tx = new Transaction().add(
+    SystemProgram.transfer({
+      fromPubkey: authority.publicKey,
+      toPubkey: wallet,
+      lamports: Math.round(sol * LAMPORTS_PER_SOL),
+    }),
+  );
+  await provider.sendAndConfirm(tx);
+}
+
+export interface MerchantOptions {
+  feeBps?: number;
+  mint?: PublicKey;
+  tokenProgram?: PublicKey;
+  // Exact ID to register instead of a fresh one derived from the prefix
+  merchantId?: string;
+  authority?: Keypair;
+  // Settlement wallet other than the authority; such merchants cannot sign refunds
+  settlementWallet?: PublicKey;
+  // Pass the approved onboarding application for the ID
+  withApplication?: boolean;
+}
+
+export async function createMerchant(prefix: string, options: MerchantOptions = {}): Promise<MerchantFixture> {
+  const { usdcMint } = await platform();
+  const mint = options.mint ?? usdcMint;
+  const tokenProgram = options.tokenProgram ?? TOKEN_PROGRAM_ID;
+  const merchantId = options.merchantId ?? uniqueId(prefix);
+  const merchantAuthority = options.authority ?? Keypair.generate();
+  await fund(merchantAuthority.publicKey);
+
+  const merchant = pda.merchant(merchantId);
+  const acceptedMint = pda.acceptedMint(mint);
+  const settlementWallet = options.settlementWallet ?? merchantAuthority.publicKey;
+  const settlementToken = getAssociatedTokenAddressSync(mint, settlementWallet, true, tokenProgram);
+
+  await program.methods
+    .initializeMerchant(merchantId, options.feeBps ?? 0)
+    .accountsStrict({
+      payer: merchantAuthority.publicKey,
+      merchantAccount: merchant,
+      merchantStats: pda.merchantStats(merchant),
+      platformConfig: pda.platform(),
+      reservedMerchantId: pda.reservedMerchantId(merchantId),
+      merchantApplication: options.withApplication ? pda.merchantApplication(merchantId) : null,
+      settlementWallet,
+      mint,
+      acceptedMint,
+      settlementToken,
+      tokenProgram,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .signers([merchantAuthority])
+    .rpc();
+
+  const { treasury } = await program.account.acceptedMint.fetch(acceptedMint);
+  return {
+    merchantId,
+    authority: merchantAuthority,
+    merchant,
+    stats: pda.merchantStats(merchant),
+    mint,
+    acceptedMint,
+    treasury,
+    tokenProgram,
+    settlementToken,
+  };
+}
+
+// Accounts for `update_merchant`; the wallet's accounts are only passed when changing it
+export function merchantConfig(merchant: MerchantFixture, settlementWallet?: PublicKey) {
+  const changing = settlementWallet !== undefined;
+  return {
+    authority: merchant.authority.publicKey,
+    merchantAccount: merchant.merchant,
+    settlementWallet: settlementWallet ?? null,
+    mint: changing ? merchant.mint : null,
+    acceptedMint: changing ? merchant.acceptedMint : null,
+    settlementToken: changing
+      ? getAssociatedTokenAddressSync(merchant.mint, settlementWallet, true, merchant.tokenProgram)
+      : null,
+    tokenProgram: merchant.tokenProgram,
+    associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+    systemProgram: SystemProgram.programId,
+  };
+}
+
+// A funded wallet holding `amount` of the merchant's mint
+export async function createCustomer(
+  mint: PublicKey,
+  amount = 1_000_000,
+  tokenProgram: PublicKey = TOKEN_PROGRAM_ID,
+): Promise<CustomerFixture> {
+  const wallet = Keypair.generate();
+  await fund(wallet.publicKey);
+  const token = await getOrCreateAssociatedTokenAccount(
+    connection,
+    authority.payer,
+    mint,
+    wallet.publicKey,
+    false,
+    undefined,
+    undefined,
+    tokenProgram,
+  );
+  await mintTo(connection, authority.payer, mint, token.address, authority.publicKey, amount, [], undefined, tokenProgram);
+  return { wallet, customer: pda.customer(wallet.publicKey), token: token.address };
+}
+
+export async function tokenBalance(account: PublicKey): Promise<number> {
+  return Number((await connection.getTokenAccountBalance(account)).value.amount);
+}
+
+// Token account for `owner` that the specs pay into, created if missing
+export async function tokenAccount(
+  mint: PublicKey,
+  owner: PublicKey,
+  tokenProgram: PublicKey = TOKEN_PROGRAM_ID,
+): Promise<PublicKey> {
+  const account = await getOrCreateAssociatedTokenAccount(
+    connection,
+    authority.payer,
+    mint,
+    owner,
+    true,
+    undefined,
+    undefined,
+    tokenProgram,
+  );
+  return account.address;
+}
+
+// Accepts `mint` on the platform, opening its treasury
+export async function addMint(mint: PublicKey, tokenProgram: PublicKey, allowRestrictedExtensions = false) {
+  await program.methods
+    .addAcceptedMint(null, allowRestrictedExtensions)
+    .accountsStrict({
+      authority: authority.publicKey,
+      platformConfig: pda.platform(),
+      mint,
+      acceptedMint: pda.acceptedMint(mint),
+      treasury: pda.mintTreasury(mint),
+      tokenProgram,
+      systemProgram: SystemProgram.programId,
+    })
+    .rpc();
+}
+
+// Ed25519 pre-instruction carrying a merchant-signed payment intent
+export function intentInstruction(
+  signer: Keypair,
+  merchant: PublicKey,
+  mint: PublicKey,
+  amount: number,
+  expiry: number,
+  paymentId: string,
+): TransactionInstruction {
+  const message = Buffer.concat([
+    seed("x402-payment-intent"),
+    merchant.toBuffer(),
+    mint.toBuffer(),
+    le8(amount),
+    le8(expiry),
+    seed(paymentId),
+  ]);
+  return Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+}
+
+// Ed25519 pre-instruction carrying a merchant-signed escrow intent
+export function escrowIntentInstruction(
+  signer: Keypair,
+  merchant: PublicKey,
+  mint: PublicKey,
+  amount: number,
+  deliveryDeadline: number,
+  expiry: number,
+  paymentId: string,
+): TransactionInstruction {
+  const message = Buffer.concat([
+    seed("x402-escrow-intent"),
+    merchant.toBuffer(),
+    mint.toBuffer(),
+    le8(amount),
+    le8(deliveryDeadline),
+    le8(expiry),
+    seed(paymentId),
+  ]);
+  return Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+}
+
+// Ed25519 pre-instruction carrying a merchant-signed resource quote for `customer`
+export function quoteInstruction(
+  signer: Keypair,
+  merchant: PublicKey,
+  customer: PublicKey,
+  resourceHash: Buffer,
+  mint: PublicKey,
+  amount: number,
+  nonce: number,
+  expiry: number,
+): TransactionInstruction {
+  const message = Buffer.concat([
+    seed("x402-resource-quote"),
+    merchant.toBuffer(),
+    customer.toBuffer(),
+    resourceHash,
+    mint.toBuffer(),
+    le8(amount),
+    le8(nonce),
+    le8(expiry),
+  ]);
+  return Ed25519Program.createInstructionWithPrivateKey({ privateKey: signer.secretKey, message });
+}
+
+export const now = () => Math.floor(Date.now() / 1000);
+
+export const sleep = (ms: number) => new Promise((resolve) => setTimeout(resolve, ms));
+
+// Optional settlement accounts for a merchant with no hold, reserve or referrer
+export function directSettlement(merchant: MerchantFixture) {
+  return {
+    merchantVault: null,
+    merchantVaultTokens: null,
+    merchantReserve: null,
+    merchantReserveTokens: null,
+    merchantFeeToken: merchant.settlementToken,
+    referrer: null,
+    referralVault: null,
+    memoProgram: null,
+  };
+}
+
+// Vault and reserve accounts for a merchant whose proceeds are held
+export function heldSettlement(merchant: MerchantFixture, { vault = false, reserve = false } = {}) {
+  return {
+    merchantVault: vault ? pda.merchantVault(merchant.merchant, merchant.mint) : null,
+    merchantVaultTokens: vault ? pda.merchantVaultTokens(merchant.merchant, merchant.mint) : null,
+    merchantReserve: reserve ? pda.merchantReserve(merchant.merchant, merchant.mint) : null,
+    merchantReserveTokens: reserve ? pda.merchantReserveTokens(merchant.merchant, merchant.mint) : null,
+  };
+}
+
+export interface PayOptions {
+  paymentId?: string;
+  expiry?: number;
+  // Amount the intent is signed for, when it should differ from the amount paid
+  signedAmount?: number;
+  signer?: Keypair;
+  accounts?: Record<string, PublicKey | null>;
+}
+
+// Pays `amount` to `merchant` through `process_payment` under a merchant-signed intent
+export async function pay(merchant: MerchantFixture, customer: CustomerFixture, amount: number, options: PayOptions = {}) {
+  const paymentId = options.paymentId ?? uniqueId("pay");
+  const expiry = options.expiry ?? now() + 600;
+  const payment = pda.payment(merchant.merchant, paymentId);
+
+  const signature = await program.methods
+    .processPayment(paymentId, new BN(amount), new BN(expiry))
+    .accountsStrict({
+      payer: customer.wallet.publicKey,
+      platformConfig: pda.platform(),
+      paymentAccount: payment,
+      customerAccount: customer.customer,
+      merchantAccount: merchant.merchant,
+      merchantStats: merchant.stats,
+      mint: merchant.mint,
+      acceptedMint: merchant.acceptedMint,
+      customerToken: customer.token,
+      merchantToken: merchant.settlementToken,
+      ...directSettlement(merchant),
+      platformTreasury: merchant.treasury,
+      instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      tokenProgram: merchant.tokenProgram,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      ...options.accounts,
+    })
+    .preInstructions([
+      intentInstruction(
+        options.signer ?? merchant.authority,
+        merchant.merchant,
+        merchant.mint,
+        options.signedAmount ?? amount,
+        expiry,
+        paymentId,
+      ),
+    ])
+    .signers([customer.wallet])
+    .rpc({ commitment: "confirmed" });
+
+  return { paymentId, payment, signature };
+}
+
+// Refunds `amount` of `payment`, or everything left when `amount` is omitted
+export function refund(
+  merchant: MerchantFixture,
+  customer: CustomerFixture,
+  payment: PublicKey,
+  amount?: number,
+  accounts: Record<string, PublicKey | null> = {},
+) {
+  const method =
+    amount === undefined ? program.methods.refundPayment() : program.methods.refundPaymentPartial(new BN(amount));
+  return method
+    .accountsStrict({
+      merchantAuthority: merchant.authority.publicKey,
+      platformConfig: pda.platform(),
+      paymentAccount: payment,
+      merchantAccount: merchant.merchant,
+      merchantStats: merchant.stats,
+      customerAccount: customer.customer,
+      customer: customer.wallet.publicKey,
+      mint: merchant.mint,
+      acceptedMint: merchant.acceptedMint,
+      customerToken: customer.token,
+      merchantToken: merchant.settlementToken,
+      ...heldSettlement(merchant),
+      invoice: null,
+      platformTreasury: merchant.treasury,
+      tokenProgram: merchant.tokenProgram,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      memoProgram: null,
+      systemProgram: SystemProgram.programId,
+      ...accounts,
+    })
+    .signers([merchant.authority])
+    .rpc({ commitment: "confirmed" });
+}
+
+// Escrows `amount` for `merchant` until `deliveryDeadline` under a merchant-signed escrow intent;
+// returns the payment account
+export async function createEscrow(
+  merchant: MerchantFixture,
+  customer: CustomerFixture,
+  amount: number,
+  deliveryDeadline: number,
+  signer: Keypair = merchant.authority,
+) {
+  const paymentId = uniqueId("escrow");
+  const expiry = now() + 600;
+  const payment = pda.payment(merchant.merchant, paymentId);
+  await program.methods
+    .createEscrowPayment(paymentId, new BN(amount), new BN(deliveryDeadline), new BN(expiry))
+    .accountsStrict({
+      payer: customer.wallet.publicKey,
+      platformConfig: pda.platform(),
+      paymentAccount: payment,
+      escrow: pda.escrow(payment),
+      escrowVault: pda.escrowVault(payment),
+      customerAccount: customer.customer,
+      merchantAccount: merchant.merchant,
+      merchantStats: merchant.stats,
+      mint: merchant.mint,
+      acceptedMint: merchant.acceptedMint,
+      customerToken: customer.token,
+      referrer: null,
+      referralVault: null,
+      instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      tokenProgram: merchant.tokenProgram,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    })
+    .preInstructions([
+      escrowIntentInstruction(signer, merchant.merchant, merchant.mint, amount, deliveryDeadline, expiry, paymentId),
+    ])
+    .signers([customer.wallet])
+    .rpc();
+  return payment;
+}
+
+// Releases an escrowed payment to `merchant`, signed by `caller`
+export function releaseEscrow(merchant: MerchantFixture, caller: Keypair, payment: PublicKey) {
+  return program.methods
+    .releaseEscrow()
+    .accountsStrict({
+      caller: caller.publicKey,
+      paymentAccount: payment,
+      escrow: pda.escrow(payment),
+      escrowVault: pda.escrowVault(payment),
+      merchantAccount: merchant.merchant,
+      merchantStats: merchant.stats,
+      mint: merchant.mint,
+      acceptedMint: merchant.acceptedMint,
+      merchantToken: merchant.settlementToken,
+      ...heldSettlement(merchant),
+      merchantFeeToken: merchant.settlementToken,
+      platformTreasury: merchant.treasury,
+      referralVault: null,
+      tokenProgram: merchant.tokenProgram,
+      memoProgram: null,
+    })
+    .signers([caller])
+    .rpc();
+}
+
+// Anchor events emitted by a confirmed transaction
+export async function eventsOf(signature: string) {
+  const tx = await connection.getTransaction(signature, {
+    commitment: "confirmed",
+    maxSupportedTransactionVersion: 0,
+  });
+  const parser = new anchor.EventParser(program.programId, program.coder);
+  return Array.from(parser.parseLogs(tx?.meta?.logMessages ?? []));
+}
+
+// The first `name` event a transaction emitted; IDL event names differ in case across Anchor versions
+export async function eventOf(signature: string, name: string): Promise<any> {
+  const event = (await eventsOf(signature)).find((event) => event.name.toLowerCase() === name.toLowerCase());
+  expect(event, `expected a ${name} event`).to.exist;
+  return event.data;
+}
+
+// Asserts that `tx` fails with the program error `code`, or with logs mentioning it
+export async function expectError(tx: Promise<unknown>, code: string) {
+  let failure: any;
+  try {
+    await tx;
+  } catch (err) {
+    failure = err;
+  }
+  expect(failure, `expected the transaction to fail with ${code}`).to.exist;
+
+  const reported = failure.error?.errorCode?.code;
+  if (reported) {
+    expect(reported).to.equal(code);
+  } else {
+    const logs = (failure.logs ?? failure.transactionLogs ?? []).join("\n");
+    expect(`${failure.message ?? failure}\n${logs}`).to.include(code);
+  }
+}
@@ -0,0 +1,124 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram, SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
+import { expect } from "chai";
+import {
+  authority,
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  expectError,
+  intentInstruction,
+  MerchantFixture,
+  now,
+  pda,
+  platform,
+  program,
+  tokenAccount,
+  tokenBalance,
+  uniqueId,
+} from "./setup";
+
+describe("split payments", () => {
+  let merchant: MerchantFixture;
+  let customer: CustomerFixture;
+  const partner = Keypair.generate().publicKey;
+  const supplier = Keypair.generate().publicKey;
+  let partnerToken: PublicKey;
+  let supplierToken: PublicKey;
+
+  function setSplitConfig(target: MerchantFixture, recipients: { wallet: PublicKey; bps: number }[]) {
+    return program.methods
+      .setSplitConfig(recipients)
+      .accountsStrict({
+        authority: target.authority.publicKey,
+        merchantAccount: target.merchant,
+        splitConfig: pda.splitConfig(target.merchant),
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([target.authority])
+      .rpc();
+  }
+
+  async function paySplit(amount: number, recipientTokens: PublicKey[]) {
+    const paymentId = uniqueId("split");
+    const expiry = now() + 600;
+    const payment = pda.payment(merchant.merchant, paymentId);
+    await program.methods
+      .processSplitPayment(paymentId, new BN(amount), new BN(expiry))
+      .accountsStrict({
+        payer: customer.wallet.publicKey,
+        platformConfig: pda.platform(),
+        paymentAccount: payment,
+        paymentSplit: pda.paymentSplit(payment),
+        customerAccount: customer.customer,
+        merchantAccount: merchant.merchant,
+        merchantStats: merchant.stats,
+        splitConfig: pda.splitConfig(merchant.merchant),
+        mint: merchant.mint,
+        acceptedMint: merchant.acceptedMint,
+        customerToken: customer.token,
+        merchantReserve: null,
+        merchantReserveTokens: null,
+        merchantFeeToken: merchant.settlementToken,
+        platformTreasury: merchant.treasury,
+        referrer: null,
+        referralVault: null,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        tokenProgram: merchant.tokenProgram,
+        memoProgram: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(recipientTokens.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true })))
+      .preInstructions([
+        intentInstruction(merchant.authority, merchant.merchant, merchant.mint, amount, expiry, paymentId),
+      ])
+      .signers([customer.wallet])
+      .rpc();
+    return payment;
+  }
+
+  before(async () => {
+    await platform();
+    merchant = await createMerchant("splits");
+    customer = await createCustomer(merchant.mint);
+    partnerToken = await tokenAccount(merchant.mint, partner);
+    supplierToken = await tokenAccount(merchant.mint, supplier);
+    await setSplitConfig(merchant, [
+      { wallet: partner, bps: 7000 },
+      { wallet: supplier, bps: 3000 },
+    ]);
+  });
+
+  it("pays each recipient its share of the merchant amount", async () => {
+    const payment = await paySplit(100000, [partnerToken, supplierToken]);
+
+    expect(await tokenBalance(partnerToken)).to.equal(68250);
+    expect(await tokenBalance(supplierToken)).to.equal(29250);
+    const split = await program.account.paymentSplit.fetch(pda.paymentSplit(payment));
+    expect(split.shares.map((share) => share.amount.toNumber())).to.deep.equal([68250, 29250]);
+  });
+
+  it("rejects recipient accounts out of config order", async () => {
+    await expectError(paySplit(100000, [supplierToken, partnerToken]), "InvalidSplitRecipient");
+  });
+
+  it("rejects shares that do not add up to 100%", async () => {
+    await expectError(
+      setSplitConfig(merchant, [
+        { wallet: partner, bps: 6000 },
+        { wallet: supplier, bps: 3000 },
+      ]),
+      "InvalidSplitConfig",
+    );
+  });
+
+  it("rejects a split for a merchant whose proceeds are held", async () => {
+    const held = await createMerchant("splits");
+    await program.methods
+      .setMerchantHoldPeriod(new BN(0))
+      .accountsStrict({ authority: authority.publicKey, platformConfig: pda.platform(), merchantAccount: held.merchant })
+      .rpc();
+
+    await expectError(setSplitConfig(held, [{ wallet: partner, bps: 10000 }]), "HeldSettlementUnsupported");
+  });
+});
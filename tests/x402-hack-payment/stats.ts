@@ -0,0 +1,42 @@
+import { BN } from "@coral-xyz/anchor";
+import { expect } from "chai";
+import { createCustomer, createMerchant, expectError, now, pay, platform, program, refund } from "./setup";
+
+const SECONDS_PER_DAY = 86400;
+
+describe("stats", () => {
+  before(async () => {
+    await platform();
+  });
+
+  it("records daily volume for the merchant and spend for the customer", async () => {
+    const merchant = await createMerchant("stats");
+    const customer = await createCustomer(merchant.mint);
+    const { payment } = await pay(merchant, customer, 60000);
+    await refund(merchant, customer, payment, 10000);
+
+    const today = Math.floor(now() / SECONDS_PER_DAY);
+    const stats = await program.account.merchantStats.fetch(merchant.stats);
+    const bucket = stats.daily.find((entry) => entry.period === today);
+    // Volume counts the merchant's share; the platform fee is tracked beside it
+    expect(bucket.volume.toNumber()).to.equal(58500);
+    expect(bucket.fees.toNumber()).to.equal(1500);
+    expect(bucket.payments).to.equal(1);
+    expect(bucket.refunded.toNumber()).to.equal(10000);
+    // Only the merchant's share of the refund comes off the volume counted towards fee tiers
+    expect(bucket.refundedVolume.toNumber()).to.equal(9750);
+
+    const spender = await program.account.customer.fetch(customer.customer);
+    expect(spender.spentToday.toNumber()).to.equal(60000);
+    expect(spender.totalRefunded.toNumber()).to.equal(10000);
+  });
+
+  it("leaves stats untouched when a payment is rejected", async () => {
+    const merchant = await createMerchant("stats");
+    const customer = await createCustomer(merchant.mint);
+
+    await expectError(pay(merchant, customer, 5000), "PaymentBelowMinimum");
+    const stats = await program.account.merchantStats.fetch(merchant.stats);
+    expect(stats.daily.every((entry) => entry.volume.eq(new BN(0)))).to.be.true;
+  });
+});
@@ -0,0 +1,167 @@
+import { BN } from "@coral-xyz/anchor";
+import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { getAccount } from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  connection,
+  createCustomer,
+  createMerchant,
+  CustomerFixture,
+  directSettlement,
+  expectError,
+  MerchantFixture,
+  pda,
+  platform,
+  program,
+  tokenBalance,
+  uniqueId,
+} from "./setup";
+
+describe("subscriptions", () => {
+  let merchant: MerchantFixture;
+  let plan: PublicKey;
+
+  before(async () => {
+    await platform();
+    merchant = await createMerchant("subs");
+    const planId = uniqueId("monthly");
+    plan = pda.plan(merchant.merchant, planId);
+    await program.methods
+      .createSubscriptionPlan(planId, new BN(20000), new BN(30 * 86400), new BN(0), 12)
+      .accountsStrict({
+        authority: merchant.authority.publicKey,
+        merchantAccount: merchant.merchant,
+        plan,
+        acceptedMint: merchant.acceptedMint,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([merchant.authority])
+      .rpc();
+  });
+
+  function subscribe(customer: CustomerFixture, delegateAmount: number) {
+    return program.methods
+      .subscribe(new BN(delegateAmount))
+      .accountsStrict({
+        customer: customer.wallet.publicKey,
+        platformConfig: pda.platform(),
+        plan,
+        merchantAccount: merchant.merchant,
+        subscription: pda.subscription(plan, customer.wallet.publicKey),
+        customerAccount: customer.customer,
+        mint: merchant.mint,
+        customerToken: customer.token,
+        delegation: pda.delegation(customer.token),
+        paymentDelegate: pda.paymentDelegate(),
+        tokenProgram: merchant.tokenProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([customer.wallet])
+      .rpc();
+  }
+
+  async function charge(customer: CustomerFixture) {
+    const subscription = pda.subscription(plan, customer.wallet.publicKey);
+    const { cyclesCharged } = await program.account.subscription.fetch(subscription);
+    return program.methods
+      .chargeSubscription()
+      .accountsStrict({
+        cranker: program.provider.publicKey,
+        platformConfig: pda.platform(),
+        plan,
+        subscription,
+        merchantAccount: merchant.merchant,
+        merchantStats: merchant.stats,
+        customerAccount: customer.customer,
+        paymentAccount: pda.sequencedPayment(subscription, cyclesCharged),
+        mint: merchant.mint,
+        acceptedMint: merchant.acceptedMint,
+        customerToken: customer.token,
+        delegation: pda.delegation(customer.token),
+        merchantToken: merchant.settlementToken,
+        ...directSettlement(merchant),
+        platformTreasury: merchant.treasury,
+        paymentDelegate: pda.paymentDelegate(),
+        tokenProgram: merchant.tokenProgram,
+        systemProgram: SystemProgram.programId,
+      })
+      .rpc();
+  }
+
+  function cancel(customer: CustomerFixture, authority: Keypair) {
+    return program.methods
+      .cancelSubscription()
+      .accountsStrict({
+        authority: authority.publicKey,
+        merchantAccount: merchant.merchant,
+        subscription: pda.subscription(plan, customer.wallet.publicKey),
+        mint: merchant.mint,
+        customerToken: customer.token,
+        delegation: pda.delegation(customer.token),
+        paymentDelegate: pda.paymentDelegate(),
+        tokenProgram: merchant.tokenProgram,
+      })
+      .signers([authority])
+      .rpc();
+  }
+
+  it("charges a due subscription through the payment delegate", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await subscribe(customer, 60000);
+    const before = await tokenBalance(customer.token);
+    const address = pda.subscription(plan, customer.wallet.publicKey);
+
+    await charge(customer);
+    const subscription = await program.account.subscription.fetch(address);
+    expect(subscription.cyclesCharged).to.equal(1);
+    expect(subscription.delegatedAmount.toNumber()).to.equal(40000);
+    expect(before - (await tokenBalance(customer.token))).to.equal(20000);
+
+    const payment = await program.account.payment.fetch(pda.sequencedPayment(address, 0));
+    expect(payment.subscription.toBase58()).to.equal(address.toBase58());
+    expect(payment.amount.toNumber()).to.equal(20000);
+  });
+
+  it("takes back the remaining approval when the customer cancels", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await subscribe(customer, 60000);
+    await charge(customer);
+
+    await cancel(customer, customer.wallet);
+    expect((await getAccount(connection, customer.token)).delegate).to.be.null;
+    const subscription = await program.account.subscription.fetch(pda.subscription(plan, customer.wallet.publicKey));
+    expect(subscription.status).to.deep.equal({ cancelled: {} });
+    expect(subscription.delegatedAmount.toNumber()).to.equal(0);
+  });
+
+  it("drops a merchant-cancelled subscription from the token account's delegation", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await subscribe(customer, 60000);
+
+    await cancel(customer, merchant.authority);
+    // The customer's approval stays until they next approve or revoke, but no longer counts
+    const delegation = await program.account.delegation.fetch(pda.delegation(customer.token));
+    expect(delegation.approvedAmount.toNumber()).to.equal(0);
+    expect((await getAccount(connection, customer.token)).delegatedAmount).to.equal(60000n);
+  });
+
+  it("rejects a cancellation by anyone but the customer or merchant", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await subscribe(customer, 60000);
+
+    await expectError(cancel(customer, Keypair.generate()), "Unauthorized");
+  });
+
+  it("rejects a charge before the next billing date", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await subscribe(customer, 60000);
+    await charge(customer);
+
+    await expectError(charge(customer), "SubscriptionNotDue");
+  });
+
+  it("rejects a delegation smaller than one charge", async () => {
+    const customer = await createCustomer(merchant.mint);
+    await expectError(subscribe(customer, 10000), "InsufficientDelegation");
+  });
+});
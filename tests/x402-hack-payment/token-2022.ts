@@ -0,0 +1,73 @@
+import { Keypair, SystemProgram, Transaction } from "@solana/web3.js";
+import {
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  createMint,
+  ExtensionType,
+  getMintLen,
+  TOKEN_2022_PROGRAM_ID,
+} from "@solana/spl-token";
+import { expect } from "chai";
+import {
+  addMint,
+  authority,
+  connection,
+  createCustomer,
+  createMerchant,
+  expectError,
+  pay,
+  pda,
+  platform,
+  program,
+  provider,
+  tokenBalance,
+} from "./setup";
+
+describe("token-2022", () => {
+  before(async () => {
+    await platform();
+  });
+
+  it("settles payments in a Token-2022 mint", async () => {
+    const mint = await createMint(
+      connection,
+      authority.payer,
+      authority.publicKey,
+      null,
+      6,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID,
+    );
+    await addMint(mint, TOKEN_2022_PROGRAM_ID);
+    const merchant = await createMerchant("t22", { mint, tokenProgram: TOKEN_2022_PROGRAM_ID });
+    const customer = await createCustomer(mint, 1_000_000, TOKEN_2022_PROGRAM_ID);
+
+    const { payment } = await pay(merchant, customer, 100000);
+    const record = await program.account.payment.fetch(payment);
+    expect(record.mint.toBase58()).to.equal(mint.toBase58());
+    expect(await tokenBalance(merchant.settlementToken)).to.equal(97500);
+  });
+
+  it("rejects a permanent-delegate mint unless explicitly allowed", async () => {
+    const mint = Keypair.generate();
+    const space = getMintLen([ExtensionType.PermanentDelegate]);
+    const tx = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: authority.publicKey,
+        newAccountPubkey: mint.publicKey,
+        space,
+        lamports: await connection.getMinimumBalanceForRentExemption(space),
+        programId: TOKEN_2022_PROGRAM_ID,
+      }),
+      createInitializePermanentDelegateInstruction(mint.publicKey, authority.publicKey, TOKEN_2022_PROGRAM_ID),
+      createInitializeMintInstruction(mint.publicKey, 6, authority.publicKey, null, TOKEN_2022_PROGRAM_ID),
+    );
+    await provider.sendAndConfirm(tx, [mint]);
+
+    await expectError(addMint(mint.publicKey, TOKEN_2022_PROGRAM_ID), "RestrictedMintExtension");
+    await addMint(mint.publicKey, TOKEN_2022_PROGRAM_ID, true);
+    const accepted = await program.account.acceptedMint.fetch(pda.acceptedMint(mint.publicKey));
+    expect(accepted.restrictedExtensionsAllowed).to.be.true;
+  });
+});